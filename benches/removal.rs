@@ -0,0 +1,92 @@
+//! Removal workloads, comparing the passes of this crate with `Vec::retain`, `Vec::extract_if`
+//! and manual index loops.
+//!
+//! Run with `cargo bench --bench removal`, an argument filters the workloads by name. Every
+//! measurement is the median of several runs over a fresh copy of the input. The default
+//! features include `loop-lifetime-guard`, add `--no-default-features` to measure the passes
+//! without the guard.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use inplace_iter::prelude::*;
+
+const RUNS: usize = 21;
+const LEN: usize = 100_000;
+
+/// Returns the median duration of `f` over fresh copies of `input`.
+fn measure<T: Clone, R>(input: &[T], mut f: impl FnMut(&mut Vec<T>) -> R) -> Duration {
+    let mut times = (0..RUNS)
+        .map(|_| {
+            let mut v = input.to_vec();
+            let start = Instant::now();
+            black_box(f(black_box(&mut v)));
+            let elapsed = start.elapsed();
+            drop(v);
+            elapsed
+        })
+        .collect::<Vec<_>>();
+    times.sort();
+    times[RUNS / 2]
+}
+
+/// Measures the removal of the elements matching `remove` by every contender.
+fn workload<T: Clone>(name: &str, filter: &Option<String>, input: &[T], remove: impl Fn(&T) -> bool + Copy) {
+    if filter.as_ref().is_some_and(|filter| !name.contains(filter.as_str())) {
+        return;
+    }
+    let contenders: [(&str, Duration); 6] = [
+        ("Vec::retain", measure(input, |v| v.retain(|x| !remove(x)))),
+        ("Vec::extract_if", measure(input, |v| v.extract_if(.., |x| remove(x)).count())),
+        ("swap_remove loop", measure(input, |v| {
+            let mut index = 0;
+            while index < v.len() {
+                if remove(&v[index]) {
+                    v.swap_remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+        })),
+        ("removable_iter", measure(input, |v| {
+            for item in v.removable_iter() {
+                if remove(item.get()) {
+                    item.remove();
+                }
+            }
+        })),
+        ("ordered_removable_iter", measure(input, |v| {
+            for item in v.ordered_removable_iter() {
+                if remove(item.get()) {
+                    item.remove();
+                }
+            }
+        })),
+        ("takeable_iter into Vec", measure(input, |v| {
+            v.takeable_iter().filter(|item| remove(item.get())).map(|item| item.take()).collect::<Vec<_>>()
+        })),
+    ];
+    println!("{name}");
+    for (contender, time) in contenders {
+        println!("  {contender:<24} {time:>12.2?}");
+    }
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let numbers = (0..LEN as u64).collect::<Vec<_>>();
+    workload("sparse removal (1%)", &filter, &numbers, |x| x % 100 == 0);
+    workload("dense removal (90%)", &filter, &numbers, |x| x % 10 != 0);
+    let wide = (0..LEN as u128).collect::<Vec<_>>();
+    workload("small copy elements (16 bytes, 50%)", &filter, &wide, |x| x % 2 == 0);
+    let large = (0..LEN as u64 / 10).map(|i| [i; 32]).collect::<Vec<_>>();
+    workload("large elements (256 bytes, 50%)", &filter, &large, |x| x[0] % 2 == 0);
+    let strings = (0..LEN / 10).map(|i| i.to_string()).collect::<Vec<_>>();
+    workload("owned strings (50%)", &filter, &strings, |x| x.len() % 2 == 0);
+    let units = vec![(); LEN];
+    let counter = std::cell::Cell::new(0u32);
+    workload("zero sized (50%)", &filter, &units, |_| {
+        counter.set(counter.get().wrapping_add(1));
+        counter.get().is_multiple_of(2)
+    });
+}
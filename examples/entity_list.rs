@@ -0,0 +1,62 @@
+//! A list of UI entities maintained frame by frame, the way a `wasm32` front end keeps its DOM
+//! nodes: the detached entities are removed and the ones dragged to another panel are taken
+//! out, in a single pass each, without reallocating the list.
+//!
+//! The example only depends on `std`, so it runs natively and on `wasm32` alike. In a browser,
+//! the `println!`s become calls to the DOM bindings.
+
+use inplace_iter::prelude::*;
+
+#[derive(Debug)]
+struct Entity {
+    id: u32,
+    panel: &'static str,
+    attached: bool,
+}
+
+/// Reports a misuse of an item to the host instead of only trapping.
+#[cfg(feature = "loop-lifetime-guard")]
+fn report_to_host(message: &str) -> ! {
+    // in a browser, e.g. `web_sys::console::error_1(&message.into())`
+    eprintln!("inplace-iter: {message}");
+    panic!("{message}")
+}
+
+fn main() {
+    #[cfg(feature = "loop-lifetime-guard")]
+    inplace_iter::epoch::set_violation_hook(Some(report_to_host));
+
+    let mut sidebar = (0..8)
+        .map(|id| Entity { id, panel: "sidebar", attached: true })
+        .collect::<Vec<_>>();
+    let mut canvas = Vec::with_capacity(sidebar.len());
+
+    for frame in 0..3 {
+        // the host detached some nodes since the last frame
+        for entity in sidebar.iter_mut().filter(|e| e.id % 4 == frame) {
+            entity.attached = false;
+        }
+        // entity 5 was dragged to the canvas
+        if let Some(entity) = sidebar.iter_mut().find(|e| e.id == 5) {
+            entity.panel = "canvas";
+        }
+
+        for item in sidebar.removable_iter() {
+            if !item.get().attached {
+                println!("frame {frame}: remove the node of entity {}", item.get().id);
+                item.remove();
+            }
+        }
+        for item in sidebar.takeable_iter() {
+            if item.get().panel == "canvas" {
+                let entity = item.take();
+                println!("frame {frame}: move the node of entity {} to the canvas", entity.id);
+                canvas.push(entity);
+            }
+        }
+    }
+
+    let mut ids = sidebar.iter().map(|e| e.id).collect::<Vec<_>>();
+    ids.sort();
+    println!("sidebar: {ids:?}, canvas: {:?}", canvas.iter().map(|e| e.id).collect::<Vec<_>>());
+}
@@ -0,0 +1,201 @@
+//! The derive macro of the `inplace-iter` crate, enabled by its `derive` feature.
+//!
+//! The macro only finds the `#[inplace]` field and its element type, the forwarding method is
+//! generated by the `inplace_iter::__forward_inplace_vector!` macro, which is kept next to the
+//! trait. The struct is parsed by hand, so the macro has no dependencies.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Implements `InplaceVector<T>` for a struct by forwarding to its `Vec<T>` field annotated with
+/// `#[inplace]`.
+///
+/// See `inplace_iter::prelude::InplaceVector` for the documentation and examples.
+#[proc_macro_derive(InplaceVector, attributes(inplace))]
+pub fn derive_inplace_vector(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err(message) => format!("::core::compile_error!({message:?});").parse().unwrap(),
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let tokens = input.into_iter().collect::<Vec<_>>();
+    let mut pos = tokens
+        .iter()
+        .position(|t| matches!(t, TokenTree::Ident(i) if i.to_string() == "struct"))
+        .ok_or("#[derive(InplaceVector)] is only supported on structs")?
+        + 1;
+    let name = tokens.get(pos).ok_or("expected the struct name")?.to_string();
+    pos += 1;
+
+    let mut generics = Vec::new();
+    if is_punct(tokens.get(pos), '<') {
+        let end = closing_angle(&tokens, pos)?;
+        generics = split_commas(&tokens[pos + 1..end]);
+        pos = end + 1;
+    }
+
+    let (fields, where_clause) = match tokens.get(pos) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            // a tuple struct has the where clause after the fields
+            let rest = &tokens[pos + 1..];
+            let end = rest.iter().position(|t| is_punct(Some(t), ';')).unwrap_or(rest.len());
+            (parse_fields(g.stream(), false)?, to_string(&rest[..end]))
+        }
+        _ => {
+            let body = tokens[pos..]
+                .iter()
+                .position(|t| matches!(t, TokenTree::Group(g) if g.delimiter() == Delimiter::Brace))
+                .ok_or("#[derive(InplaceVector)] needs a struct with fields")?
+                + pos;
+            let TokenTree::Group(g) = &tokens[body] else { unreachable!() };
+            (parse_fields(g.stream(), true)?, to_string(&tokens[pos..body]))
+        }
+    };
+
+    let mut marked = fields.into_iter().filter(|f| f.inplace);
+    let field = marked.next().ok_or("#[derive(InplaceVector)] needs a `Vec<T>` field annotated with #[inplace]")?;
+    if marked.next().is_some() {
+        return Err("only one field can be annotated with #[inplace]".into());
+    }
+    let element = vec_element(&field.ty).ok_or("the #[inplace] field must be a `Vec<T>`")?;
+
+    let impl_generics = generics.iter().map(|p| impl_param(p)).collect::<Vec<_>>().join(", ");
+    let type_generics = generics.iter().map(|p| param_name(p)).collect::<Vec<_>>().join(", ");
+    format!(
+        "impl<{impl_generics}> ::inplace_iter::prelude::InplaceVector<{element}> for {name}<{type_generics}> {where_clause} {{
+            ::inplace_iter::__forward_inplace_vector!({}, {element});
+        }}",
+        field.name,
+    )
+    .parse()
+    .map_err(|e| format!("{e:?}"))
+}
+
+/// A field of the struct, `name` is the index for a tuple struct.
+struct Field {
+    name: String,
+    ty: Vec<TokenTree>,
+    inplace: bool,
+}
+
+fn parse_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, String> {
+    let tokens = stream.into_iter().collect::<Vec<_>>();
+    let mut fields = Vec::new();
+    for (index, field) in split_commas(&tokens).into_iter().enumerate() {
+        let mut pos = 0;
+        let mut inplace = false;
+        // attributes
+        while is_punct(field.get(pos), '#') {
+            if let Some(TokenTree::Group(g)) = field.get(pos + 1) {
+                inplace |= matches!(g.stream().into_iter().next(), Some(TokenTree::Ident(i)) if i.to_string() == "inplace");
+            }
+            pos += 2;
+        }
+        // visibility
+        if matches!(field.get(pos), Some(TokenTree::Ident(i)) if i.to_string() == "pub") {
+            pos += 1;
+            if matches!(field.get(pos), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis) {
+                pos += 1;
+            }
+        }
+        let name = if named {
+            let name = field.get(pos).ok_or("expected a field name")?.to_string();
+            // the name and the colon
+            pos += 2;
+            name
+        } else {
+            index.to_string()
+        };
+        fields.push(Field {
+            name,
+            ty: field[pos.min(field.len())..].to_vec(),
+            inplace,
+        });
+    }
+    Ok(fields)
+}
+
+/// Returns the element type of a `Vec<T>` (or `std::vec::Vec<T>`) type.
+fn vec_element(ty: &[TokenTree]) -> Option<String> {
+    let open = ty.iter().position(|t| is_punct(Some(t), '<'))?;
+    if !matches!(open.checked_sub(1).and_then(|i| ty.get(i)), Some(TokenTree::Ident(i)) if i.to_string() == "Vec") {
+        return None;
+    }
+    let close = closing_angle(ty, open).ok()?;
+    (close == ty.len() - 1).then(|| to_string(&ty[open + 1..close]))
+}
+
+/// Returns a generic parameter without its default, for the `impl<...>` list.
+fn impl_param(param: &[TokenTree]) -> String {
+    let mut depth = 0i32;
+    let end = param
+        .iter()
+        .enumerate()
+        .position(|(i, t)| {
+            depth += angle_delta(param, i);
+            depth == 0 && is_punct(Some(t), '=')
+        })
+        .unwrap_or(param.len());
+    to_string(&param[..end])
+}
+
+/// Returns the name of a generic parameter, for the argument list of the type.
+fn param_name(param: &[TokenTree]) -> String {
+    match param {
+        [TokenTree::Punct(p), TokenTree::Ident(lifetime), ..] if p.as_char() == '\'' => format!("'{lifetime}"),
+        [TokenTree::Ident(c), TokenTree::Ident(name), ..] if c.to_string() == "const" => name.to_string(),
+        [name, ..] => name.to_string(),
+        [] => String::new(),
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+/// The change of the angle bracket depth at `tokens[i]`, the `>` of a `->` is not a bracket.
+fn angle_delta(tokens: &[TokenTree], i: usize) -> i32 {
+    match &tokens[i] {
+        TokenTree::Punct(p) if p.as_char() == '<' => 1,
+        TokenTree::Punct(p) if p.as_char() == '>' => {
+            let arrow = matches!(i.checked_sub(1).map(|j| &tokens[j]), Some(TokenTree::Punct(m)) if m.as_char() == '-' && m.spacing() == Spacing::Joint);
+            if arrow { 0 } else { -1 }
+        }
+        _ => 0,
+    }
+}
+
+/// Returns the index of the `>` closing the `<` at `open`.
+fn closing_angle(tokens: &[TokenTree], open: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for i in open..tokens.len() {
+        depth += angle_delta(tokens, i);
+        if depth == 0 {
+            return Ok(i);
+        }
+    }
+    Err("unbalanced angle brackets".into())
+}
+
+/// Splits the tokens on the commas outside of angle brackets, skipping a trailing comma.
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for i in 0..tokens.len() {
+        depth += angle_delta(tokens, i);
+        if depth == 0 && is_punct(Some(&tokens[i]), ',') {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
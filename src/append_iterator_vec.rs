@@ -0,0 +1,342 @@
+//! Swap removing passes which also append elements to the vector.
+//!
+//! The vector is borrowed by the pass, so the elements are appended through the iterator, and
+//! the [`AppendMode`] chosen at the start decides whether the pass visits them.
+
+use std::marker::PhantomData;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{violation, Epoch};
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::handle::VecShape;
+use crate::handle::PassStamp;
+use crate::inplace_iterator::InplaceIterator;
+use crate::inplace_vec_iterator::InplaceVecItem;
+use crate::item_index;
+
+/// Whether the elements appended during a pass are visited by it.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut jobs = vec![3, 1];
+/// // the jobs spawned in the pass are written into the spare capacity
+/// jobs.reserve(2);
+/// let mut iter = jobs.appending_removable_iter_mut(AppendMode::CurrentPass);
+/// let mut done = Vec::new();
+/// while let Some(item) = iter.next() {
+///     let job = *item.get();
+///     item.remove();
+///     done.push(job);
+///     if job > 1 {
+///         // a job spawns a smaller one, done in the same pass
+///         iter.try_push(job - 1).expect("the capacity is reserved");
+///     }
+/// }
+/// drop(iter);
+/// assert_eq!(done, vec![3, 1, 2, 1]);
+/// assert!(jobs.is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendMode {
+    /// The appended elements are written to the spare capacity of the vector right away and
+    /// visited by the pass, like the elements present at its start. The vector does not grow
+    /// its allocation during the pass, so the capacity has to be reserved before it.
+    CurrentPass,
+    /// The appended elements are queued and pushed to the vector when the pass ends, so it does
+    /// not visit them. The next pass does.
+    NextPass,
+}
+
+impl AppendMode {
+    /// The pass visits at most the elements present at its start, whatever is appended during
+    /// it, and visits each of them exactly once unless removed before being reached.
+    ///
+    /// This is what [`AppendMode::NextPass`] guarantees: the length of the vector is in effect
+    /// snapshotted at the start, as the appended elements only reach the vector when the pass
+    /// ends.
+    pub const SNAPSHOT_LEN: Self = Self::NextPass;
+}
+
+/// An iterator of a swap removing pass, which can append elements to its vector.
+pub trait AppendingIterator<T>: InplaceIterator<T> {
+    /// Appends `value` to the vector, to be visited by this pass or by the next one, as
+    /// selected by [`mode`](Self::mode).
+    ///
+    /// In [`AppendMode::CurrentPass`], the element is written to the spare capacity of the
+    /// vector, which never moves the other elements, so the references returned by the items
+    /// stay valid. When the vector is full, `value` is given back as the error. In
+    /// [`AppendMode::NextPass`], the element is queued and this always succeeds.
+    fn try_push(&mut self, value: T) -> Result<(), T>;
+
+    /// Returns whether the appended elements are visited by this pass.
+    fn mode(&self) -> AppendMode;
+
+    /// Returns the number of elements queued for the next pass.
+    fn queued(&self) -> usize;
+}
+
+/// A swap removing pass over a vector, which can append elements to it.
+///
+/// A removal moves the last element into the place of the removed one, which is visited next,
+/// so every element is visited exactly once, including the ones appended in
+/// [`AppendMode::CurrentPass`].
+pub struct AppendingVecIterator<'a, T> {
+    /// This tells the borrow checker that the underlying vector is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
+    /// A raw pointer to the vector data for unsafe access.
+    data: *mut Vec<T>,
+    /// A flag indicating whether an item has been removed.
+    removed: bool,
+    /// The current index in the vector, or None if iteration hasn't started.
+    index: Option<usize>,
+    /// Whether the appended elements are visited by this pass.
+    mode: AppendMode,
+    /// The elements appended in [`AppendMode::NextPass`], pushed to the vector on drop.
+    queue: Vec<T>,
+    /// The items do not give out handles, the vector grows under them.
+    handles: Option<PassStamp>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+    /// The state of the vector after the last change done through the items or the iterator.
+    #[cfg(feature = "loop-lifetime-guard")]
+    expected: VecShape,
+}
+
+impl<'a, T> AppendingVecIterator<'a, T> {
+    pub fn new(v: &'a mut Vec<T>, mode: AppendMode) -> Self {
+        item_index::check_len(v.len());
+        Self {
+            _lifetime_guard: PhantomData,
+            #[cfg(feature = "loop-lifetime-guard")]
+            expected: VecShape::of(v),
+            data: v as *mut Vec<T>,
+            removed: false,
+            index: None,
+            mode,
+            queue: Vec::new(),
+            handles: None,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    /// The index of the element the next call to `next()` yields.
+    fn next_index(&self) -> usize {
+        match self.index {
+            // the swapped in element is visited in the place of the removed one
+            Some(index) if self.removed => index,
+            Some(index) => index + 1,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(feature = "loop-lifetime-guard")]
+impl<'a, T> AppendingVecIterator<'a, T> {
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+
+    /// Panics if the vector was changed other than through the items or this iterator.
+    fn check_unchanged(&self) {
+        let v = unsafe { &(*self.data) };
+        if VecShape::of(v) != self.expected {
+            violation("The vector was changed during the iteration other than through the iterator items!");
+        }
+    }
+}
+
+impl<'a, T> Drop for AppendingVecIterator<'a, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let v = unsafe { &mut (*self.data) };
+        v.append(&mut self.queue);
+    }
+}
+
+impl<'a, T> Iterator for AppendingVecIterator<'a, T> {
+    type Item = InplaceVecItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_unchanged();
+        let len = unsafe { (*self.data).len() };
+        let index = self.next_index();
+        self.removed = false;
+        self.index = Some(index);
+        if index < len {
+            #[cfg(feature = "loop-lifetime-guard")]
+            let rotten = self.epoch.item();
+            Some(InplaceVecItem::new(self.data, index, &mut self.removed, &mut self.handles, true, #[cfg(feature = "loop-lifetime-guard")] rotten, #[cfg(feature = "loop-lifetime-guard")] &mut self.expected))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = unsafe { (*self.data).len() };
+        let remaining = len.saturating_sub(self.next_index());
+        match self.mode {
+            AppendMode::CurrentPass => (remaining, None),
+            AppendMode::NextPass => (remaining, Some(remaining)),
+        }
+    }
+}
+
+impl<'a, T> InplaceIterator<T> for AppendingVecIterator<'a, T> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        let v = unsafe { &(*self.data) };
+        v[self.next_index().min(v.len())..].iter()
+    }
+}
+
+impl<'a, T> AppendingIterator<T> for AppendingVecIterator<'a, T> {
+    fn try_push(&mut self, value: T) -> Result<(), T> {
+        match self.mode {
+            AppendMode::CurrentPass => {
+                #[cfg(feature = "loop-lifetime-guard")]
+                self.check_unchanged();
+                let v = unsafe { &mut (*self.data) };
+                let len = v.len();
+                if len == v.capacity() {
+                    return Err(value);
+                }
+                item_index::check_len(len + 1);
+                // a push could reallocate under the references given out by the items, the spare
+                // capacity is written through the raw pointer without touching the elements
+                unsafe {
+                    v.as_mut_ptr().add(len).write(value);
+                    v.set_len(len + 1);
+                }
+                #[cfg(feature = "loop-lifetime-guard")]
+                {
+                    self.expected = VecShape::of(v);
+                }
+            }
+            AppendMode::NextPass => self.queue.push(value),
+        }
+        Ok(())
+    }
+
+    fn mode(&self) -> AppendMode {
+        self.mode
+    }
+
+    fn queued(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::{AppendMode, AppendingIterator, InplaceVector, RemovableItem, RemovableItemMut};
+
+    #[test]
+    fn test_current_pass_visits_appended() {
+        let mut v = vec![1, 2, 3];
+        v.reserve(2);
+        let mut visited = Vec::new();
+        let mut iter = v.appending_removable_iter(AppendMode::CurrentPass);
+        while let Some(item) = iter.next() {
+            visited.push(*item.get());
+            if *item.get() == 2 {
+                item.remove();
+            }
+            if *visited.last().unwrap() < 3 {
+                iter.try_push(visited.last().unwrap() + 10).unwrap();
+            }
+        }
+        drop(iter);
+        // 3 was swapped into the place of 2, the appended elements came after it
+        assert_eq!(visited, vec![1, 2, 11, 3, 12]);
+        assert_eq!(v, vec![1, 11, 3, 12]);
+    }
+
+    #[test]
+    fn test_next_pass_queues_appended() {
+        let mut v = vec![1, 2, 3];
+        let mut visited = Vec::new();
+        let mut iter = v.appending_removable_iter_mut(AppendMode::NextPass);
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        while let Some(mut item) = iter.next() {
+            visited.push(*item.get());
+            if *item.get() == 1 {
+                item.remove();
+            } else {
+                *item.get_mut() *= 10;
+                iter.try_push(*visited.last().unwrap()).unwrap();
+            }
+        }
+        assert_eq!(iter.queued(), 2);
+        drop(iter);
+        assert_eq!(visited, vec![1, 3, 2]);
+        assert_eq!(v, vec![30, 20, 3, 2]);
+    }
+
+    #[test]
+    fn test_snapshot_len_bounds_the_pass() {
+        let mut v = (0..4).collect::<Vec<u32>>();
+        let mut iter = v.appending_removable_iter(AppendMode::SNAPSHOT_LEN);
+        let mut visited = 0;
+        while let Some(item) = iter.next() {
+            visited += 1;
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+            for appended in 0..3 {
+                iter.try_push(100 + appended).unwrap();
+            }
+        }
+        drop(iter);
+        assert_eq!(visited, 4);
+        assert_eq!(v.len(), 2 + 4 * 3);
+    }
+
+    #[test]
+    fn test_early_drop_appends_queue() {
+        let mut v = vec![1, 2, 3];
+        let mut iter = v.appending_removable_iter(AppendMode::NextPass);
+        iter.next();
+        iter.try_push(4).unwrap();
+        drop(iter);
+        assert_eq!(v, vec![1, 2, 3, 4]);
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[test]
+    #[should_panic]
+    fn test_rotten_after_push() {
+        let mut v = vec![1, 2, 3];
+        v.reserve(1);
+        let mut iter = v.appending_removable_iter(AppendMode::CurrentPass);
+        let item = iter.next().unwrap();
+        iter.next();
+        iter.try_push(4).unwrap();
+        item.remove();
+    }
+
+    #[test]
+    fn test_full_vector_gives_value_back() {
+        let mut v = Vec::with_capacity(3);
+        v.extend([String::from("a"), String::from("b")]);
+        let mut iter = v.appending_removable_iter(AppendMode::CurrentPass);
+        let item = iter.next().unwrap();
+        let first = item.get();
+        assert_eq!(iter.try_push(String::from("c")), Ok(()));
+        assert_eq!(iter.try_push(String::from("d")), Err(String::from("d")));
+        // nothing was moved, the reference is still valid
+        assert_eq!(first, "a");
+        drop(item);
+        drop(iter);
+        assert_eq!(v, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,159 @@
+//! Copy-on-prune for shared immutable slices, and pruning of vectors of shared pointers.
+
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::inplace_vector::InplaceVector;
+use crate::removal_plan::{Decision, PlanRemovals};
+use crate::removable_iterator::RemovableItem;
+use crate::takeable_iterator::TakeableItem;
+
+/// A trait for shared slices, which are filtered into a new shared slice.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use inplace_iter::prelude::*;
+///
+/// let snapshot: Arc<[u32]> = Arc::from([1, 2, 3, 4, 5]);
+/// let pruned = snapshot.pruned(|v| if v % 2 == 0 { Decision::Remove } else { Decision::Keep });
+/// assert_eq!(*pruned, [1, 3, 5]);
+/// // the readers of the old snapshot are not affected
+/// assert_eq!(*snapshot, [1, 2, 3, 4, 5]);
+/// ```
+pub trait SharedSlice<T> {
+    /// Returns a new slice of the elements for which `f` decides to keep them, in their order.
+    ///
+    /// The decisions are recorded first, so the survivors are cloned into a single allocation
+    /// of the exact size. If nothing is removed, the slice itself is shared, without copying.
+    fn pruned<F>(&self, f: F) -> Self
+    where
+        T: Clone,
+        F: FnMut(&T) -> Decision;
+}
+
+impl<T> SharedSlice<T> for Arc<[T]> {
+    fn pruned<F>(&self, f: F) -> Self
+    where
+        T: Clone,
+        F: FnMut(&T) -> Decision,
+    {
+        let plan = self.plan_removals_into(Vec::<u64>::new(), f);
+        if plan.removals() == 0 {
+            return self.clone();
+        }
+        let mut pruned = Arc::new_uninit_slice(self.len() - plan.removals());
+        let slots = Arc::get_mut(&mut pruned).unwrap();
+        let survivors = self.iter().zip(plan.decisions()).filter(|(_, d)| *d == Decision::Keep);
+        for (slot, (value, _)) in slots.iter_mut().zip(survivors) {
+            // if a clone panics, the already cloned survivors are leaked, not dropped
+            slot.write(value.clone());
+        }
+        unsafe { pruned.assume_init() }
+    }
+}
+
+/// A trait for vectors of shared pointers, removing the pointers nobody else holds.
+///
+/// Implemented for `Vec<Arc<T>>` and `Vec<Rc<T>>`. Only the strong references count, a value
+/// with only weak references left is removed.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use inplace_iter::prelude::*;
+///
+/// let mut cache = vec![Arc::new("a"), Arc::new("b"), Arc::new("c")];
+/// let in_use = cache[1].clone();
+/// assert_eq!(cache.prune_unique(), 2);
+/// assert_eq!(cache, vec![in_use]);
+/// ```
+pub trait PruneUnique<P> {
+    /// Removes the pointers held only by the vector and returns the number of removed pointers.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the order of elements is not preserved
+    #[doc(alias = "prune_unique_arcs")]
+    fn prune_unique(&mut self) -> usize;
+
+    /// Takes the pointers held only by the vector out of it.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity, the order of elements is not preserved
+    fn take_unique(&mut self) -> Vec<P>;
+}
+
+impl<T> PruneUnique<Arc<T>> for Vec<Arc<T>> {
+    fn prune_unique(&mut self) -> usize {
+        prune_where(self, |p| Arc::strong_count(p) == 1)
+    }
+
+    fn take_unique(&mut self) -> Vec<Arc<T>> {
+        take_where(self, |p| Arc::strong_count(p) == 1)
+    }
+}
+
+impl<T> PruneUnique<Rc<T>> for Vec<Rc<T>> {
+    fn prune_unique(&mut self) -> usize {
+        prune_where(self, |p| Rc::strong_count(p) == 1)
+    }
+
+    fn take_unique(&mut self) -> Vec<Rc<T>> {
+        take_where(self, |p| Rc::strong_count(p) == 1)
+    }
+}
+
+fn prune_where<P>(v: &mut Vec<P>, unique: impl Fn(&P) -> bool) -> usize {
+    let mut removed = 0;
+    for item in v.removable_iter() {
+        if unique(item.get()) {
+            item.remove();
+            removed += 1;
+        }
+    }
+    removed
+}
+
+fn take_where<P>(v: &mut Vec<P>, unique: impl Fn(&P) -> bool) -> Vec<P> {
+    v.takeable_iter().filter(|item| unique(item.get())).map(|item| item.take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use crate::prelude::{Decision, PruneUnique, SharedSlice};
+
+    #[test]
+    fn test_pruned_clones_survivors() {
+        let a: Arc<[String]> = ["a", "bb", "c", "dd"].map(String::from).into();
+        let b = a.pruned(|s| if s.len() > 1 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(*b, ["a".to_string(), "c".to_string()]);
+        let c = b.pruned(|_| Decision::Remove);
+        assert!(c.is_empty());
+    }
+
+    #[test]
+    fn test_pruned_nothing_shares() {
+        let a: Arc<[i32]> = Arc::from([1, 2]);
+        let b = a.pruned(|_| Decision::Keep);
+        assert!(Arc::ptr_eq(&a, &b));
+    }
+
+    #[test]
+    fn test_prune_unique_rcs() {
+        let mut a = (0..5).map(Rc::new).collect::<Vec<_>>();
+        let held = [a[0].clone(), a[3].clone()];
+        let weak = Rc::downgrade(&a[4]);
+        let mut taken = a.take_unique().into_iter().map(|p| *p).collect::<Vec<_>>();
+        taken.sort();
+        assert_eq!(taken, vec![1, 2, 4]);
+        assert!(weak.upgrade().is_none());
+        drop(held);
+        assert_eq!(a.prune_unique(), 2);
+        assert!(a.is_empty());
+    }
+}
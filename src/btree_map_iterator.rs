@@ -0,0 +1,189 @@
+//! In-place iteration over a range of `BTreeMap` entries.
+//!
+//! The range is split off the map when the iteration starts, which is O(log n). Each yielded
+//! item owns its entry and inserts it back into the map when it is dropped, unless it was
+//! taken, and the entries not yet visited and the ones after the range are merged back when
+//! the iterator is dropped. Removing an entry costs nothing extra, keeping one is an O(log n)
+//! insertion.
+//!
+//! Leaking the iterator (e.g. with `mem::forget`) loses the entries of the range not yet
+//! visited and the entries after the range, and leaking an item loses its entry.
+
+use std::collections::{btree_map, BTreeMap};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch};
+use crate::hash_map_iterator::{MapEntry, TakeableMapValue};
+
+/// An iterator over a range of the entries of a `BTreeMap`, which allows removing or taking
+/// them.
+///
+/// The entries are visited in ascending order of their keys.
+pub struct BTreeMapRangeEntries<'a, K: Ord, V> {
+    /// This tells the borrow checker that the map is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut BTreeMap<K, V>>,
+    /// The map, it holds the entries before the range. The items insert the kept ones.
+    map: *mut BTreeMap<K, V>,
+    /// The entries of the range not yet visited.
+    entries: btree_map::IntoIter<K, V>,
+    /// The entries after the range.
+    after: BTreeMap<K, V>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, K: Ord, V> BTreeMapRangeEntries<'a, K, V> {
+    pub fn new<R: RangeBounds<K>>(map: &'a mut BTreeMap<K, V>, range: R) -> Self {
+        let mut entries = match range.start_bound() {
+            Bound::Included(start) => map.split_off(start),
+            Bound::Excluded(start) => {
+                let mut entries = map.split_off(start);
+                if let Some((key, value)) = entries.remove_entry(start) {
+                    map.insert(key, value);
+                }
+                entries
+            }
+            Bound::Unbounded => mem::take(map),
+        };
+        let after = match range.end_bound() {
+            Bound::Included(end) => {
+                let mut after = entries.split_off(end);
+                if let Some((key, value)) = after.remove_entry(end) {
+                    entries.insert(key, value);
+                }
+                after
+            }
+            Bound::Excluded(end) => entries.split_off(end),
+            Bound::Unbounded => BTreeMap::new(),
+        };
+        Self {
+            _lifetime_guard: PhantomData,
+            map,
+            entries: entries.into_iter(),
+            after,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+/// Inserts an entry kept by an item back into the `BTreeMap` behind `map`.
+unsafe fn reinsert<K: Ord, V>(map: *mut (), key: K, value: V) {
+    unsafe { (*map.cast::<BTreeMap<K, V>>()).insert(key, value) };
+}
+
+impl<'a, K: Ord, V> Drop for BTreeMapRangeEntries<'a, K, V> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let map = unsafe { &mut *self.map };
+        map.extend(&mut self.entries);
+        // the smaller part is inserted into the larger one
+        if self.after.len() > map.len() {
+            mem::swap(map, &mut self.after);
+        }
+        map.extend(mem::take(&mut self.after));
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for BTreeMapRangeEntries<'a, K, V> {
+    type Item = MapEntry<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let entry = self.entries.next()?;
+        Some(MapEntry::new(TakeableMapValue::new(self.map.cast(), reinsert::<K, V>, entry, #[cfg(feature = "loop-lifetime-guard")] self.epoch.item())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::prelude::InplaceRangeMap;
+
+    fn timeline() -> BTreeMap<u32, String> {
+        (0..10).map(|t| (t * 10, format!("event {t}"))).collect()
+    }
+
+    #[test]
+    fn test_expire_prefix() {
+        let mut events = timeline();
+        let mut expired = Vec::new();
+        for entry in events.removable_range_iter(..35) {
+            expired.push(entry.take().0);
+        }
+        assert_eq!(expired, vec![0, 10, 20, 30]);
+        assert_eq!(events.keys().copied().collect::<Vec<_>>(), vec![40, 50, 60, 70, 80, 90]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut events = timeline();
+        let mut visited = Vec::new();
+        for mut entry in events.removable_range_iter(20..=50) {
+            visited.push(*entry.key());
+            if *entry.key() % 20 == 0 {
+                entry.remove();
+            } else {
+                entry.get_mut().push('!');
+            }
+        }
+        assert_eq!(visited, vec![20, 30, 40, 50]);
+        assert_eq!(events.keys().copied().collect::<Vec<_>>(), vec![0, 10, 30, 50, 60, 70, 80, 90]);
+        assert_eq!(events[&50], "event 5!");
+
+        let excluded = (std::ops::Bound::Excluded(30), std::ops::Bound::Excluded(60));
+        let visited = events.removable_range_iter(excluded).map(|entry| *entry.key()).collect::<Vec<_>>();
+        assert_eq!(visited, vec![50]);
+        assert_eq!(events.len(), 8);
+    }
+
+    #[test]
+    fn test_early_drop_keeps_rest() {
+        let mut events = timeline();
+        let mut iter = events.removable_range_iter(30..);
+        iter.next().unwrap().remove();
+        iter.next();
+        drop(iter);
+        assert_eq!(events.len(), 9);
+        assert!(!events.contains_key(&30));
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[test]
+    #[should_panic]
+    fn test_rotten_entry() {
+        let mut events = timeline();
+        let mut iter = events.removable_range_iter(..);
+        let entry = iter.next().unwrap();
+        iter.next();
+        entry.remove();
+    }
+
+    #[test]
+    fn test_reference_held_across_next() {
+        let mut events = BTreeMap::from([(1, "one".to_string())]);
+        let mut iter = events.removable_range_iter(..);
+        let entry = iter.next().unwrap();
+        let name = entry.get();
+        assert!(iter.next().is_none());
+        drop(iter);
+        // the entry still holds its key and value, and inserts them back when dropped
+        assert_eq!(name, "one");
+        drop(entry);
+        assert_eq!(events, BTreeMap::from([(1, "one".to_string())]));
+    }
+}
@@ -0,0 +1,141 @@
+//! In-place iteration over `BTreeSet` elements.
+//!
+//! The elements are moved out of the set when the iteration starts. Each yielded item owns its
+//! element and inserts it back into the set when it is dropped, unless it was taken, and the
+//! elements not yet visited are merged back when the iterator is dropped.
+//!
+//! Leaking the iterator (e.g. with `mem::forget`) loses the elements not yet visited, and
+//! leaking an item loses its element.
+
+use std::collections::{btree_set, BTreeSet};
+use std::marker::PhantomData;
+use std::mem;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch};
+use crate::hash_set_iterator::TakeableSetItem;
+
+/// An iterator over the elements of a `BTreeSet`, in ascending order, which allows taking them
+/// out of the set.
+pub struct TakeableBTreeSetIter<'a, T: Ord> {
+    /// This tells the borrow checker that the set is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut BTreeSet<T>>,
+    /// The set, it holds the visited and kept elements, inserted by the items.
+    set: *mut BTreeSet<T>,
+    /// The elements not yet visited.
+    elements: btree_set::IntoIter<T>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T: Ord> TakeableBTreeSetIter<'a, T> {
+    pub fn new(set: &'a mut BTreeSet<T>) -> Self {
+        let elements = mem::take(set).into_iter();
+        Self {
+            _lifetime_guard: PhantomData,
+            set,
+            elements,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+/// Inserts an element kept by an item back into the `BTreeSet` behind `set`.
+unsafe fn reinsert<T: Ord>(set: *mut (), value: T) {
+    unsafe { (*set.cast::<BTreeSet<T>>()).insert(value) };
+}
+
+impl<'a, T: Ord> Drop for TakeableBTreeSetIter<'a, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let set = unsafe { &mut *self.set };
+        let mut rest = self.elements.by_ref().collect::<BTreeSet<_>>();
+        // the smaller part is inserted into the larger one
+        if rest.len() > set.len() {
+            mem::swap(set, &mut rest);
+        }
+        set.extend(rest);
+    }
+}
+
+impl<'a, T: Ord> Iterator for TakeableBTreeSetIter<'a, T> {
+    type Item = TakeableSetItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let value = self.elements.next()?;
+        Some(TakeableSetItem::new(self.set.cast(), reinsert::<T>, value, #[cfg(feature = "loop-lifetime-guard")] self.epoch.item()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::prelude::InplaceSet;
+
+    #[test]
+    fn test_take_in_order() {
+        let mut set = (0..10).collect::<BTreeSet<u32>>();
+        let mut visited = Vec::new();
+        let mut taken = Vec::new();
+        for item in set.takeable_iter() {
+            visited.push(*item.get());
+            if *item.get() % 3 == 0 {
+                taken.push(item.take());
+            } else if *item.get() == 5 {
+                item.remove();
+            }
+        }
+        assert_eq!(visited, (0..10).collect::<Vec<_>>());
+        assert_eq!(taken, vec![0, 3, 6, 9]);
+        assert_eq!(set, BTreeSet::from([1, 2, 4, 7, 8]));
+    }
+
+    #[test]
+    fn test_early_drop_keeps_rest() {
+        let mut set = (0..10).map(|x| x.to_string()).collect::<BTreeSet<_>>();
+        let mut iter = set.takeable_iter();
+        iter.next();
+        assert_eq!(iter.next().unwrap().take(), "1");
+        drop(iter);
+        assert_eq!(set.len(), 9);
+        assert!(set.contains("0") && !set.contains("1"));
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[test]
+    #[should_panic]
+    fn test_rotten_element() {
+        let mut set = BTreeSet::from([1, 2]);
+        let mut iter = set.takeable_iter();
+        let item = iter.next().unwrap();
+        iter.next();
+        item.take();
+    }
+
+    #[test]
+    fn test_reference_held_across_next() {
+        let mut set = BTreeSet::from(["a".to_string(), "b".to_string()]);
+        let mut iter = set.takeable_iter();
+        let first = iter.next().unwrap();
+        let value = first.get();
+        iter.next().unwrap().take();
+        drop(iter);
+        // the first item still holds its element, and inserts it back when dropped
+        assert_eq!(value, "a");
+        drop(first);
+        assert_eq!(set, BTreeSet::from(["a".to_string()]));
+    }
+}
@@ -0,0 +1,170 @@
+//! Stopping a pass early on an external signal.
+//!
+//! The removals of a pass are applied as the pass goes, so a cancelled pass leaves the vector
+//! in a consistent state: the visited elements are decided, the unvisited ones are untouched.
+
+use std::iter::FusedIterator;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::inplace_iterator::InplaceIterator;
+
+/// A signal which tells a pass to stop.
+pub trait Cancellation {
+    /// Returns `true` once the pass should stop.
+    fn is_cancelled(&self) -> bool;
+}
+
+impl Cancellation for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Acquire)
+    }
+}
+
+impl<C: Cancellation + ?Sized> Cancellation for &C {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+impl<C: Cancellation + ?Sized> Cancellation for Arc<C> {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+/// How far a cancellable pass got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelReport {
+    /// The number of yielded items.
+    pub visited: usize,
+    /// The number of items the pass would still yield if it was not cancelled.
+    pub remaining: usize,
+    /// Whether the pass was stopped by the signal.
+    pub cancelled: bool,
+}
+
+/// An iterator which stops yielding once its [`Cancellation`] is signalled, created by
+/// [`InplaceIterator::with_cancellation`].
+///
+/// The signal is checked on each call to `next()`, the item in use is never interrupted.
+pub struct Cancellable<I, C> {
+    /// The underlying iterator.
+    iter: I,
+    /// The signal checked before each item.
+    token: C,
+    /// The number of yielded items.
+    visited: usize,
+    /// Whether the signal stopped the iteration.
+    cancelled: bool,
+}
+
+impl<I, C> Cancellable<I, C> {
+    pub(crate) fn new(iter: I, token: C) -> Self {
+        Self { iter, token, visited: 0, cancelled: false }
+    }
+
+    /// The number of yielded items.
+    pub fn visited(&self) -> usize {
+        self.visited
+    }
+
+    /// Returns `true` if the signal stopped the iteration.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl<I: Iterator, C> Cancellable<I, C> {
+    /// Returns how far the pass got.
+    pub fn report(&self) -> CancelReport {
+        CancelReport {
+            visited: self.visited,
+            remaining: self.iter.size_hint().0,
+            cancelled: self.cancelled,
+        }
+    }
+}
+
+impl<I: Iterator, C: Cancellation> Iterator for Cancellable<I, C> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cancelled {
+            return None;
+        }
+        if self.token.is_cancelled() {
+            self.cancelled = true;
+            return None;
+        }
+        let item = self.iter.next()?;
+        self.visited += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.cancelled {
+            (0, Some(0))
+        } else {
+            // the signal may come at any time
+            (0, self.iter.size_hint().1)
+        }
+    }
+}
+
+impl<I: FusedIterator, C: Cancellation> FusedIterator for Cancellable<I, C> {}
+
+impl<T, I: InplaceIterator<T>, C: Cancellation> InplaceIterator<T> for Cancellable<I, C> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        let limit = if self.cancelled { 0 } else { usize::MAX };
+        self.iter.lookahead().take(limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::prelude::{InplaceIterator, InplaceVector, RemovableItem};
+
+    #[test]
+    fn test_cancelled_pass_keeps_removals() {
+        let mut a = (0..10).collect::<Vec<_>>();
+        let stop = AtomicBool::new(false);
+        let mut iter = a.removable_iter().with_cancellation(&stop);
+        while let Some(item) = iter.next() {
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+            if iter.visited() == 4 {
+                stop.store(true, Ordering::Release);
+            }
+        }
+        let report = iter.report();
+        assert!(report.cancelled);
+        assert_eq!(report.visited, 4);
+        assert_eq!(report.remaining, 6);
+        assert_eq!(iter.peek(), None);
+        drop(iter);
+        // 0 and 2 removed, 9 and 8 swapped in, 8 is not visited yet
+        assert_eq!(a, vec![9, 1, 8, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_not_cancelled_pass() {
+        let mut a = vec![1, 2, 3];
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut iter = a.ordered_removable_iter().with_cancellation(stop.clone());
+        for item in iter.by_ref() {
+            if *item.get() == 2 {
+                item.remove();
+            }
+        }
+        assert!(!iter.is_cancelled());
+        assert_eq!(iter.visited(), 3);
+        drop(iter);
+        assert_eq!(a, vec![1, 3]);
+    }
+}
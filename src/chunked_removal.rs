@@ -0,0 +1,134 @@
+//! Interruptible removal passes, processed in chunks.
+//!
+//! The pass preserves the order of the elements, so stopping after a chunk leaves the unvisited
+//! elements untouched and the pass can be resumed later from the reported boundary without
+//! visiting anything twice.
+
+use crate::inplace_iterator::InplaceIterator;
+use crate::ordered_iterator_vec::InplaceOrderedVecIterator;
+use crate::prelude::RemovableItem;
+
+/// How far a chunked removal pass got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkProgress {
+    /// The index of the first unvisited element, pass it as `start` to resume.
+    pub next_start: usize,
+    /// The number of elements removed by the pass.
+    pub removed: usize,
+    /// `true` if all the elements were visited.
+    pub finished: bool,
+}
+
+/// A trait for removal passes which can be interrupted between chunks.
+pub trait ChunkedRemoval<T> {
+    /// Removes the elements matching `pred`, starting at `start`, in chunks of `chunk_len`
+    /// elements.
+    ///
+    /// After every full chunk followed by more elements, `proceed` is called with the progress
+    /// so far, returning `false` stops the pass. It is not called once the last element is
+    /// visited, even if the last chunk is full. The removals of the visited chunks are applied,
+    /// the rest of the vector is left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    /// // stop after the first chunk
+    /// let progress = numbers.remove_where_chunked(0, 3, |n| n % 2 == 0, |_| false);
+    /// assert_eq!(progress.next_start, 2);
+    /// assert_eq!(numbers, vec![1, 3, 4, 5, 6, 7, 8]);
+    ///
+    /// // resume where the pass stopped
+    /// let progress = numbers.remove_where_chunked(progress.next_start, 3, |n| n % 2 == 0, |_| true);
+    /// assert!(progress.finished);
+    /// assert_eq!(numbers, vec![1, 3, 5, 7]);
+    /// ```
+    fn remove_where_chunked<F, C>(&mut self, start: usize, chunk_len: usize, pred: F, proceed: C) -> ChunkProgress
+    where
+        F: FnMut(&T) -> bool,
+        C: FnMut(&ChunkProgress) -> bool;
+}
+
+impl<T> ChunkedRemoval<T> for Vec<T> {
+    fn remove_where_chunked<F, C>(&mut self, start: usize, chunk_len: usize, mut pred: F, mut proceed: C) -> ChunkProgress
+    where
+        F: FnMut(&T) -> bool,
+        C: FnMut(&ChunkProgress) -> bool,
+    {
+        assert!(chunk_len > 0, "the chunk length must not be 0");
+        let mut progress = ChunkProgress { next_start: start.min(self.len()), removed: 0, finished: false };
+        let mut iter = InplaceOrderedVecIterator::new_from(self, start);
+        loop {
+            for _ in 0..chunk_len {
+                let Some(item) = iter.next() else {
+                    progress.finished = true;
+                    return progress;
+                };
+                if pred(item.get()) {
+                    item.remove();
+                    progress.removed += 1;
+                } else {
+                    progress.next_start += 1;
+                }
+            }
+            progress.finished = iter.peek().is_none();
+            if progress.finished || !proceed(&progress) {
+                return progress;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::ChunkedRemoval;
+
+    #[test]
+    fn test_chunked_resume() {
+        let mut a = (0..10).collect::<Vec<_>>();
+        let mut calls = 0;
+        let mut progress = a.remove_where_chunked(0, 2, |v| v % 3 == 0, |_| {
+            calls += 1;
+            calls < 2
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(progress.removed, 2);
+        assert!(!progress.finished);
+        assert_eq!(a, vec![1, 2, 4, 5, 6, 7, 8, 9]);
+        while !progress.finished {
+            progress = a.remove_where_chunked(progress.next_start, 2, |v| v % 3 == 0, |_| false);
+        }
+        assert_eq!(a, vec![1, 2, 4, 5, 7, 8]);
+        assert_eq!(progress.next_start, a.len());
+    }
+
+    #[test]
+    fn test_chunked_exact_end() {
+        let mut a = vec![1, 2, 3, 4];
+        let progress = a.remove_where_chunked(0, 2, |v| *v > 2, |_| true);
+        assert!(progress.finished);
+        assert_eq!(progress.removed, 2);
+        assert_eq!(a, vec![1, 2]);
+        let progress = a.remove_where_chunked(5, 2, |_| true, |_| true);
+        assert_eq!(progress, super::ChunkProgress { next_start: 2, removed: 0, finished: true });
+    }
+
+    #[test]
+    fn test_no_proceed_after_the_last_chunk() {
+        let mut a = (0..6).collect::<Vec<_>>();
+        let mut calls = 0;
+        let progress = a.remove_where_chunked(0, 3, |v| v % 2 == 0, |_| {
+            calls += 1;
+            true
+        });
+        assert!(progress.finished);
+        assert_eq!(calls, 1);
+        assert_eq!(a, vec![1, 3, 5]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Background pruning of a shared vector, as a runtime agnostic task.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use crate::chunked_removal::ChunkedRemoval;
+use crate::predicate::Predicate;
+use crate::try_inplace::Poisoned;
+
+/// A future removing the elements matching a policy from a shared vector, a budget of elements
+/// per poll, yielding to the runtime between the budgets.
+///
+/// The vector is locked only for a budget, so the other users of the vector are not blocked for
+/// the whole pass. The pass preserves the order and resumes at the first unvisited index, so
+/// elements pushed meanwhile are visited too, but removing elements meanwhile can make the pass
+/// skip some.
+///
+/// It resolves to the number of removed elements, or to [`Poisoned`] if the lock of the vector
+/// is poisoned.
+///
+/// # Performance
+///
+/// - Every poll is an order preserving pass, which closes the gaps of its removals by moving
+///   all the elements after its budget, so a whole cleanup of n elements costs O(n²/budget)
+///   moves when elements are removed. Choose the budget with the length of the vector in mind,
+///   or prune with [`InplaceVector::removable_iter_from`](crate::prelude::InplaceVector::removable_iter_from)
+///   under the lock if the order does not matter
+/// - A poll which removes nothing moves nothing
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::sync::{Arc, Mutex};
+/// use std::task::{Context, Poll, Waker};
+/// use inplace_iter::cleanup_task::CleanupTask;
+///
+/// let cache = Arc::new(Mutex::new((0..10).collect::<Vec<u32>>()));
+/// let mut task = pin!(CleanupTask::new(cache.clone(), |v: &u32| v % 3 == 0).with_budget(4));
+/// let mut cx = Context::from_waker(Waker::noop());
+/// let mut polls = 1;
+/// // spawned on a runtime, the task is simply awaited
+/// while task.as_mut().poll(&mut cx).is_pending() {
+///     polls += 1;
+/// }
+/// assert_eq!(polls, 3);
+/// assert_eq!(*cache.lock().unwrap(), vec![1, 2, 4, 5, 7, 8]);
+/// ```
+pub struct CleanupTask<T, P> {
+    /// The shared vector.
+    vector: Arc<Mutex<Vec<T>>>,
+    /// The policy, matching the elements to remove.
+    policy: P,
+    /// The number of elements visited per poll.
+    budget: usize,
+    /// The index of the first unvisited element.
+    next_start: usize,
+    /// The number of removed elements so far.
+    removed: usize,
+}
+
+impl<T, P> CleanupTask<T, P>
+where
+    P: Predicate<T>,
+{
+    /// Creates the task, removing the elements of `vector` matching `policy`.
+    pub fn new(vector: Arc<Mutex<Vec<T>>>, policy: P) -> Self {
+        Self {
+            vector,
+            policy,
+            budget: 256,
+            next_start: 0,
+            removed: 0,
+        }
+    }
+
+    /// Sets the number of elements visited per poll, 256 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is 0.
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        assert!(budget > 0, "the budget must not be 0");
+        self.budget = budget;
+        self
+    }
+
+    /// Returns the policy, e.g. for its clause statistics.
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+}
+
+impl<T, P> Future for CleanupTask<T, P>
+where
+    P: Predicate<T> + Unpin,
+{
+    type Output = Result<usize, Poisoned>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Ok(mut vector) = this.vector.lock() else {
+            return Poll::Ready(Err(Poisoned));
+        };
+        let policy = &mut this.policy;
+        let progress = vector.remove_where_chunked(this.next_start, this.budget, |v| policy.test(v), |_| false);
+        drop(vector);
+        this.next_start = progress.next_start;
+        this.removed += progress.removed;
+        if progress.finished {
+            return Poll::Ready(Ok(this.removed));
+        }
+        // yield to the other tasks, but be polled again
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use crate::prelude::Predicate;
+    use crate::try_inplace::Poisoned;
+    use super::CleanupTask;
+
+    #[test]
+    fn test_cleanup_between_yields() {
+        let shared = Arc::new(Mutex::new((0..6).collect::<Vec<i32>>()));
+        let mut task = pin!(CleanupTask::new(shared.clone(), (|v: &i32| *v < 0).labeled("negative")).with_budget(2));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+        // the vector is usable between the polls
+        shared.lock().unwrap().extend([-1, 7, -2]);
+        let removed = loop {
+            if let Poll::Ready(removed) = task.as_mut().poll(&mut cx) {
+                break removed;
+            }
+        };
+        assert_eq!(removed, Ok(2));
+        assert_eq!(task.policy().clause_stats()[0].evaluated, 9);
+        assert_eq!(*shared.lock().unwrap(), vec![0, 1, 2, 3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn test_cleanup_poisoned() {
+        let shared = Arc::new(Mutex::new(vec![1]));
+        let other = shared.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = other.lock().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join();
+        let mut task = pin!(CleanupTask::new(shared, |_: &i32| true));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(task.as_mut().poll(&mut cx), Poll::Ready(Err(Poisoned)));
+    }
+}
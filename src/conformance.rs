@@ -0,0 +1,280 @@
+//! A conformance suite for the implementations of [`InplaceCollection`], so the unsafe code of
+//! a custom container can be checked against the semantics of the built-in ones, and of
+//! [`InplaceVector`], where only the vector accessor of the container is checked.
+//!
+//! The suite panics on the first violation, so it is meant to be called from a test.
+//!
+//! # Examples
+//!
+//! ```
+//! use inplace_iter::conformance::{test_backend, test_vector_backend, Backend};
+//!
+//! struct VecBackend;
+//!
+//! impl Backend for VecBackend {
+//!     type Collection<E> = Vec<E>;
+//!
+//!     fn from_elements<E>(elements: Vec<E>) -> Vec<E> {
+//!         elements
+//!     }
+//!
+//!     fn into_elements<E>(collection: Vec<E>) -> Vec<E> {
+//!         collection
+//!     }
+//! }
+//!
+//! test_backend::<VecBackend>();
+//! test_vector_backend::<VecBackend>();
+//! ```
+
+use std::cell::Cell;
+use std::rc::Rc;
+use crate::inplace_collection::InplaceCollection;
+use crate::inplace_vector::InplaceVector;
+use crate::removable_confirm_iterator_vec::RemovableConfirmIterator;
+use crate::removable_iterator::RemovableItem;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// A family of containers under test, for any element type.
+pub trait Backend {
+    /// The container of the elements of type `E`.
+    type Collection<E>: InplaceCollection<Element = E>;
+
+    /// Creates the container holding `elements`.
+    fn from_elements<E>(elements: Vec<E>) -> Self::Collection<E>;
+
+    /// Moves the elements out of the container, in any order.
+    fn into_elements<E>(collection: Self::Collection<E>) -> Vec<E>;
+}
+
+/// The element used by the suite, counting its drops.
+#[derive(Debug)]
+pub struct Probe {
+    /// The identity of the element.
+    id: usize,
+    /// The drop counter shared by all the probes of a test.
+    drops: Rc<Cell<usize>>,
+}
+
+impl Probe {
+    /// Returns the identity of the element.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+/// Creates `n` probes with the ids `0..n` and their drop counter.
+fn probes(n: usize) -> (Vec<Probe>, Rc<Cell<usize>>) {
+    let drops = Rc::new(Cell::new(0));
+    let probes = (0..n).map(|id| Probe { id, drops: drops.clone() }).collect();
+    (probes, drops)
+}
+
+/// Returns the sorted ids of `probes`.
+fn ids(probes: &[Probe]) -> Vec<usize> {
+    let mut ids = probes.iter().map(Probe::id).collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+/// Runs the suite for the [`InplaceCollection`] of the backend.
+///
+/// It checks that every element is visited exactly once while others are removed, that the
+/// removed elements are dropped exactly once and the taken ones not at all, that the changes
+/// done through the items stay, and the edge cases: an empty container, removing every
+/// element and zero sized elements.
+///
+/// # Panics
+///
+/// Panics if the container violates the semantics.
+pub fn test_backend<B: Backend>() {
+    // empty
+    let mut empty = B::from_elements(Vec::<Probe>::new());
+    assert_eq!(empty.inplace_items().count(), 0, "an empty container yields no items");
+    assert_eq!(empty.remove_items_where(|_| true), 0);
+    assert!(B::into_elements(empty).is_empty());
+
+    // every element is visited once, while every other one is removed
+    let (elements, drops) = probes(10);
+    let mut c = B::from_elements(elements);
+    let mut visited = Vec::new();
+    let removed = c.remove_items_where(|p| {
+        visited.push(p.id());
+        p.id() % 2 == 0
+    });
+    visited.sort();
+    assert_eq!(visited, (0..10).collect::<Vec<_>>(), "every element is visited exactly once");
+    assert_eq!(removed, 5);
+    assert_eq!(drops.get(), 5, "the removed elements are dropped once");
+    let remaining = B::into_elements(c);
+    assert_eq!(ids(&remaining), vec![1, 3, 5, 7, 9]);
+    drop(remaining);
+    assert_eq!(drops.get(), 10, "no element is leaked or dropped twice");
+
+    // taken elements are moved out, not dropped
+    let (elements, drops) = probes(10);
+    let mut c = B::from_elements(elements);
+    let taken = c.take_items_where(|p| p.id() % 3 == 0);
+    assert_eq!(ids(&taken), vec![0, 3, 6, 9]);
+    assert_eq!(drops.get(), 0, "the taken elements are not dropped");
+    assert_eq!(ids(&B::into_elements(c)), vec![1, 2, 4, 5, 7, 8]);
+    drop(taken);
+    assert_eq!(drops.get(), 10);
+
+    // changes through the items stay
+    let mut c = B::from_elements((0..5).collect::<Vec<u64>>());
+    for mut item in c.inplace_items() {
+        *item.get_mut() *= 10;
+    }
+    let mut values = B::into_elements(c);
+    values.sort();
+    assert_eq!(values, vec![0, 10, 20, 30, 40], "the changes through the items stay");
+
+    // all removed
+    let (elements, drops) = probes(7);
+    let mut c = B::from_elements(elements);
+    assert_eq!(c.remove_items_where(|_| true), 7);
+    assert_eq!(c.inplace_items().count(), 0);
+    assert!(B::into_elements(c).is_empty());
+    assert_eq!(drops.get(), 7);
+
+    // zero sized elements
+    let mut c = B::from_elements(vec![(); 100]);
+    let mut nth = 0;
+    let removed = c.remove_items_where(|_| {
+        nth += 1;
+        nth % 2 == 0
+    });
+    assert_eq!((removed, nth), (50, 100), "zero sized elements are visited and removed");
+    assert_eq!(B::into_elements(c).len(), 50);
+}
+
+/// Runs the suite for the [`InplaceVector`] of the backend: the confirm sessions, the order
+/// preserving pass and the draining.
+///
+/// The passes of [`InplaceVector`] all run on the `Vec` returned by
+/// [`InplaceVector::inplace_vec`], so they are the crate's own code for every backend. This only
+/// checks that the accessor returns the backend's vector and that the elements survive the
+/// `from_elements`/`into_elements` round trip, not any unsafe code of the backend.
+///
+/// # Panics
+///
+/// Panics if the vector violates the semantics.
+pub fn test_vector_backend<B>()
+where
+    B: Backend,
+    B::Collection<Probe>: InplaceVector<Probe>,
+    B::Collection<()>: InplaceVector<()>,
+{
+    // cancelled removals keep every element
+    let (elements, drops) = probes(8);
+    let mut c = B::from_elements(elements);
+    let mut confirm = c.removable_confirm_iter();
+    for item in confirm.iter() {
+        if item.get().id() < 5 {
+            item.remove();
+        }
+    }
+    confirm.cancel_removals();
+    assert_eq!(drops.get(), 0, "cancelled removals drop nothing");
+    let elements = B::into_elements(c);
+    assert_eq!(ids(&elements), (0..8).collect::<Vec<_>>(), "cancelled removals keep every element");
+
+    // confirmed removals drop the marked elements only
+    let mut c = B::from_elements(elements);
+    let mut confirm = c.removable_confirm_iter();
+    for item in confirm.iter() {
+        if item.get().id() % 4 == 0 {
+            item.remove();
+        }
+    }
+    confirm.confirm_removals();
+    assert_eq!(drops.get(), 2, "confirmed removals drop the marked elements");
+    let elements = B::into_elements(c);
+    assert_eq!(ids(&elements), vec![1, 2, 3, 5, 6, 7]);
+
+    // the ordered pass keeps the order of the survivors
+    let mut c = B::from_elements(elements);
+    for item in c.ordered_removable_iter() {
+        if item.get().id() % 3 == 0 {
+            item.remove();
+        }
+    }
+    let order = c.drain_unordered().collect::<Vec<_>>();
+    assert_eq!(order.len(), 4, "draining yields every element");
+    assert_eq!(drops.get(), 4);
+    assert!(B::into_elements(c).is_empty(), "draining empties the vector");
+    assert_eq!(ids(&order), vec![1, 2, 5, 7]);
+    drop(order);
+    assert_eq!(drops.get(), 8);
+
+    let mut c = B::from_elements(vec![(); 10]);
+    let mut confirm = c.removable_confirm_iter();
+    for item in confirm.iter().step_by(2) {
+        item.remove();
+    }
+    confirm.confirm_removals();
+    assert_eq!(B::into_elements(c).len(), 5, "zero sized elements are removed by confirm sessions");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+    use super::{test_backend, test_vector_backend, Backend};
+
+    struct VecBackend;
+
+    impl Backend for VecBackend {
+        type Collection<E> = Vec<E>;
+
+        fn from_elements<E>(elements: Vec<E>) -> Vec<E> {
+            elements
+        }
+
+        fn into_elements<E>(collection: Vec<E>) -> Vec<E> {
+            collection
+        }
+    }
+
+    struct DequeBackend;
+
+    impl Backend for DequeBackend {
+        type Collection<E> = VecDeque<E>;
+
+        fn from_elements<E>(elements: Vec<E>) -> VecDeque<E> {
+            elements.into()
+        }
+
+        fn into_elements<E>(collection: VecDeque<E>) -> Vec<E> {
+            collection.into()
+        }
+    }
+
+    struct MapBackend;
+
+    impl Backend for MapBackend {
+        type Collection<E> = HashMap<usize, E>;
+
+        fn from_elements<E>(elements: Vec<E>) -> HashMap<usize, E> {
+            elements.into_iter().enumerate().collect()
+        }
+
+        fn into_elements<E>(collection: HashMap<usize, E>) -> Vec<E> {
+            collection.into_values().collect()
+        }
+    }
+
+    #[test]
+    fn test_builtin_backends() {
+        test_backend::<VecBackend>();
+        test_backend::<DequeBackend>();
+        test_backend::<MapBackend>();
+        test_vector_backend::<VecBackend>();
+    }
+}
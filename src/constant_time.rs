@@ -0,0 +1,117 @@
+//! Removal with a data-independent branching pattern, for filtering secret-dependent data.
+//!
+//! # What is constant time
+//!
+//! [`RetainConstantTime::retain_constant_time`] runs the same instructions for every element,
+//! whatever the predicate returns: one call to the predicate, one swap of two elements and one
+//! addition of the verdict to the write position. There is no branch on the verdict in this
+//! crate, and the verdict goes through [`std::hint::black_box`] so the compiler does not
+//! introduce one.
+//!
+//! # What is not
+//!
+//! - The predicate itself, it must be written branch free by the caller
+//! - The addresses of the swaps, the write position is the number of kept elements so far, so
+//!   the memory access pattern (e.g. through the cache) depends on the verdicts
+//! - The final length of the vector, which is the number of kept elements
+//! - Dropping the removed elements, a `Drop` implementation runs only for them, use types
+//!   without drop glue (e.g. `Copy` types) or take them out by
+//!   [`RetainConstantTime::split_off_constant_time`]
+//! - Anything the hardware does not guarantee, e.g. variable time multiplication on some
+//!   targets, or the optimizer seeing through `black_box`, which is only a hint
+
+use std::hint::black_box;
+use std::ptr;
+
+/// A trait for vectors, removing elements with a data-independent branching pattern.
+///
+/// See the [module documentation](self) for the exact guarantees.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut keys = vec![0x11u8, 0x80, 0x22, 0xf0, 0x33];
+/// let removed = keys.retain_constant_time(|k| k & 0x80 == 0);
+/// assert_eq!(removed, 2);
+/// assert_eq!(keys, vec![0x11, 0x22, 0x33]);
+/// ```
+pub trait RetainConstantTime<T> {
+    /// Keeps the elements for which `f` returns `true` and returns the number of removed
+    /// elements.
+    ///
+    /// # Performance
+    ///
+    /// - Every element is swapped once, whether it is kept or not
+    /// - The order of the kept elements is preserved
+    fn retain_constant_time<F>(&mut self, f: F) -> usize
+    where
+        F: FnMut(&T) -> bool;
+
+    /// Moves the elements for which `f` returns `false` to a new vector and returns it, like
+    /// [`RetainConstantTime::retain_constant_time`], so the removed elements are not dropped.
+    fn split_off_constant_time<F>(&mut self, f: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool;
+}
+
+impl<T> RetainConstantTime<T> for Vec<T> {
+    fn retain_constant_time<F>(&mut self, f: F) -> usize
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let kept = compact(self, f);
+        let removed = self.len() - kept;
+        self.truncate(kept);
+        removed
+    }
+
+    fn split_off_constant_time<F>(&mut self, f: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let kept = compact(self, f);
+        self.split_off(kept)
+    }
+}
+
+/// Moves the elements for which `f` returns `true` to the front, in their order, and returns
+/// their number.
+fn compact<T, F>(v: &mut [T], mut f: F) -> usize
+where
+    F: FnMut(&T) -> bool,
+{
+    let data = v.as_mut_ptr();
+    let mut write = 0;
+    for read in 0..v.len() {
+        unsafe {
+            let keep = black_box(f(&*data.add(read)));
+            // always swapped, with itself while nothing was removed yet
+            ptr::swap(data.add(write), data.add(read));
+            write += usize::from(keep);
+        }
+    }
+    write
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetainConstantTime;
+
+    #[test]
+    fn test_same_result_as_retain() {
+        for mask in 0..64u32 {
+            let mut expected = (0..6).collect::<Vec<u32>>();
+            expected.retain(|x| mask & (1 << x) != 0);
+            let mut v = (0..6).collect::<Vec<u32>>();
+            let removed = v.retain_constant_time(|x| mask & (1 << x) != 0);
+            assert_eq!(v, expected);
+            assert_eq!(removed, 6 - expected.len());
+        }
+        let mut names = vec!["a".to_string(), "bb".to_string(), "c".to_string()];
+        let long = names.split_off_constant_time(|name| name.len() == 1);
+        assert_eq!(names, vec!["a", "c"]);
+        assert_eq!(long, vec!["bb"]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Confirm sessions which settle themselves when they are dropped after a deadline.
+
+use std::time::Instant;
+use crate::removable_confirm_iterator_vec::{ConfirmState, RemovableConfirmIterator};
+
+/// What a [`DeadlineConfirm`] dropped after its deadline does with the marked removals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnExpiry {
+    /// The removals are confirmed.
+    Confirm,
+    /// The removals are cancelled.
+    Cancel,
+}
+
+/// A confirm session which, when dropped after its deadline without being confirmed or
+/// cancelled, settles the marked removals as chosen by [`OnExpiry`].
+///
+/// Dropped before the deadline, the removals are cancelled, like for any confirm session. It is
+/// meant for cleanup tasks which can be abandoned by a timeout layer above them, e.g. a future
+/// dropped by a timeout, so the work done until then is not lost.
+///
+/// Created by [`RemovableConfirmIterator::with_deadline`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3, 4];
+/// let mut confirm = numbers.removable_confirm_iter().with_deadline(Instant::now(), OnExpiry::Confirm);
+/// for item in confirm.iter() {
+///     if *item.get() % 2 == 0 {
+///         item.remove();
+///     }
+/// }
+/// // abandoned after the deadline
+/// drop(confirm);
+/// assert_eq!(numbers, vec![1, 3]);
+/// ```
+pub struct DeadlineConfirm<C: RemovableConfirmIterator> {
+    /// The session, taken when it is settled.
+    inner: Option<C>,
+    /// After this instant, dropping the session applies `on_expiry`.
+    deadline: Instant,
+    /// What to do with the removals when dropped after the deadline.
+    on_expiry: OnExpiry,
+}
+
+impl<C: RemovableConfirmIterator> DeadlineConfirm<C> {
+    pub(crate) fn new(inner: C, deadline: Instant, on_expiry: OnExpiry) -> Self {
+        Self {
+            inner: Some(inner),
+            deadline,
+            on_expiry,
+        }
+    }
+
+    /// Returns `true` if the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Returns the deadline.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl<C: RemovableConfirmIterator> RemovableConfirmIterator for DeadlineConfirm<C> {
+    type Element = C::Element;
+    type Item = C::Item;
+
+    fn iter(&mut self) -> impl Iterator<Item = Self::Item> {
+        self.inner.as_mut().expect("the session is settled").iter()
+    }
+
+    fn confirm_removals(mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.confirm_removals();
+        }
+    }
+
+    fn cancel_removals(mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.cancel_removals();
+        }
+    }
+
+    fn cancel_removals_with<F>(mut self, f: F)
+    where
+        F: FnMut(&Self::Element),
+    {
+        if let Some(inner) = self.inner.take() {
+            inner.cancel_removals_with(f);
+        }
+    }
+
+    fn suspend(mut self) -> ConfirmState {
+        self.inner.take().expect("the session is settled").suspend()
+    }
+}
+
+impl<C: RemovableConfirmIterator> Drop for DeadlineConfirm<C> {
+    fn drop(&mut self) {
+        let Some(inner) = self.inner.take() else {
+            return;
+        };
+        if self.is_expired() && self.on_expiry == OnExpiry::Confirm {
+            inner.confirm_removals();
+        } else {
+            inner.cancel_removals();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::prelude::{InplaceVector, OnExpiry, RemovableConfirmIterator, RemovableItem};
+
+    fn mark_even(numbers: &mut Vec<i32>, deadline: Instant, on_expiry: OnExpiry, confirm: Option<bool>) {
+        let mut session = numbers.removable_confirm_iter().with_deadline(deadline, on_expiry);
+        for item in session.iter() {
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+        }
+        match confirm {
+            Some(true) => session.confirm_removals(),
+            Some(false) => session.cancel_removals(),
+            None => {}
+        }
+    }
+
+    #[test]
+    fn test_drop_settles_by_deadline() {
+        let past = Instant::now();
+        let future = past + Duration::from_secs(3600);
+
+        let mut a = vec![1, 2, 3, 4];
+        mark_even(&mut a, past, OnExpiry::Cancel, None);
+        assert_eq!(a.len(), 4);
+
+        let mut a = vec![1, 2, 3, 4];
+        mark_even(&mut a, future, OnExpiry::Confirm, None);
+        assert_eq!(a.len(), 4);
+
+        let mut a = vec![1, 2, 3, 4];
+        mark_even(&mut a, past, OnExpiry::Confirm, None);
+        assert_eq!(a, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_explicit_settling_wins() {
+        let past = Instant::now();
+        let future = past + Duration::from_secs(3600);
+
+        let mut a = vec![1, 2, 3, 4];
+        mark_even(&mut a, future, OnExpiry::Cancel, Some(true));
+        assert_eq!(a, vec![1, 3]);
+
+        let mut a = vec![1, 2, 3, 4];
+        mark_even(&mut a, past, OnExpiry::Confirm, Some(false));
+        assert_eq!(a.len(), 4);
+    }
+}
@@ -0,0 +1,58 @@
+//! Forwarding of [`InplaceVector`](crate::prelude::InplaceVector) to a field, used by
+//! `#[derive(InplaceVector)]` behind the `derive` feature.
+
+/// Expands to the required method of `InplaceVector<$t>`, returning the `Vec<$t>` field
+/// `$field` of `self`.
+///
+/// Not a public API, it is only to be used by the derive macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __forward_inplace_vector {
+    ($field:tt, $t:ty) => {
+        fn inplace_vec(&mut self) -> &mut ::std::vec::Vec<$t> {
+            &mut self.$field
+        }
+    };
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::prelude::{InplaceVector, RemovableItem, TakeableItem};
+
+    #[derive(InplaceVector)]
+    struct Inbox {
+        owner: &'static str,
+        #[inplace]
+        messages: Vec<String>,
+    }
+
+    #[derive(InplaceVector)]
+    struct Tagged<'a, T: Clone, const N: usize = 2>(&'a str, #[inplace] pub Vec<[T; N]>)
+    where
+        T: PartialEq;
+
+    #[test]
+    fn test_derive_named_field() {
+        let mut inbox = Inbox {
+            owner: "alice",
+            messages: vec!["hi".to_string(), "spam".to_string(), "bye".to_string()],
+        };
+        let spam = inbox.takeable_iter().filter(|item| item.get() == "spam").map(|item| item.take()).collect::<Vec<_>>();
+        assert_eq!(spam, vec!["spam"]);
+        assert_eq!(inbox.owner, "alice");
+        assert_eq!(inbox.messages, vec!["hi", "bye"]);
+        assert_eq!(inbox.into_put_back_iter().count(), 2);
+    }
+
+    #[test]
+    fn test_derive_generic_tuple_struct() {
+        let mut tagged = Tagged::<u8>("pairs", vec![[1, 1], [1, 2], [2, 2]]);
+        for item in tagged.removable_iter() {
+            if item.get()[0] != item.get()[1] {
+                item.remove();
+            }
+        }
+        assert_eq!(tagged.0, "pairs");
+        assert_eq!(tagged.1, vec![[1, 1], [2, 2]]);
+    }
+}
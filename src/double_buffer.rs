@@ -0,0 +1,143 @@
+//! Ping-pong buffering for vectors shared with concurrent readers.
+//!
+//! Readers hold a snapshot `Arc<Vec<T>>` of the published buffer. A removal pass never touches
+//! the published buffer, the survivors are written into the back buffer, which is then
+//! published with a single pointer swap. Readers therefore see either the old or the new
+//! vector, never a partially compacted one. The replaced buffer becomes the next back buffer,
+//! its allocation is reused once the last reader drops its snapshot.
+
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// A vector published to concurrent readers, which is filtered into a second buffer.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::double_buffer::DoubleBuffer;
+/// use inplace_iter::prelude::*;
+///
+/// let buffer = DoubleBuffer::new(vec![1, 2, 3, 4, 5]);
+/// let snapshot = buffer.load();
+/// assert_eq!(buffer.remove_where(|v| v % 2 == 0), 2);
+/// // the old snapshot is untouched
+/// assert_eq!(*snapshot, vec![1, 2, 3, 4, 5]);
+/// assert_eq!(*buffer.load(), vec![1, 3, 5]);
+///
+/// buffer.update(|back| {
+///     for item in back.removable_iter() {
+///         if *item.get() == 1 {
+///             item.remove();
+///         }
+///     }
+/// });
+/// assert_eq!(*buffer.load(), vec![5, 3]);
+/// ```
+pub struct DoubleBuffer<T> {
+    /// The buffer the readers see.
+    published: RwLock<Arc<Vec<T>>>,
+    /// The previously published buffer, held while a pass runs so the passes are serialized.
+    spare: Mutex<Option<Arc<Vec<T>>>>,
+}
+
+impl<T> DoubleBuffer<T> {
+    /// Publishes `v` as the initial buffer.
+    pub fn new(v: Vec<T>) -> Self {
+        Self {
+            published: RwLock::new(Arc::new(v)),
+            spare: Mutex::new(None),
+        }
+    }
+
+    /// Returns a snapshot of the published buffer, it is never modified by later passes.
+    pub fn load(&self) -> Arc<Vec<T>> {
+        self.published.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Writes the survivors of the published buffer into the back buffer, without cloning the
+    /// removed elements, and publishes it. Returns the number of removed elements.
+    ///
+    /// The order of the survivors is preserved.
+    pub fn remove_where<F>(&self, mut f: F) -> usize
+    where
+        T: Clone,
+        F: FnMut(&T) -> bool,
+    {
+        self.pass(|current, back| {
+            back.extend(current.iter().filter(|v| !f(v)).cloned());
+            current.len() - back.len()
+        })
+    }
+
+    /// Copies the published buffer into the back buffer, lets `f` modify it (e.g. with a
+    /// removable iterator) and publishes the result.
+    pub fn update<R, F>(&self, f: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&mut Vec<T>) -> R,
+    {
+        self.pass(|current, back| {
+            back.extend(current.iter().cloned());
+            f(back)
+        })
+    }
+
+    fn pass<R>(&self, fill: impl FnOnce(&[T], &mut Vec<T>) -> R) -> R {
+        // a panicking pass never publishes, so a poisoned lock still guards consistent data
+        let mut spare = self.spare.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut back = match spare.take().map(Arc::try_unwrap) {
+            Some(Ok(mut back)) => {
+                back.clear();
+                back
+            }
+            // a reader still holds the spare buffer, it is freed with the last snapshot
+            _ => Vec::new(),
+        };
+        let current = self.load();
+        let result = fill(&current, &mut back);
+        drop(current);
+        let mut published = self.published.write().unwrap_or_else(PoisonError::into_inner);
+        *spare = Some(std::mem::replace(&mut *published, Arc::new(back)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use super::DoubleBuffer;
+
+    #[test]
+    fn test_spare_buffer_is_reused() {
+        let buffer = DoubleBuffer::new((0..100).collect::<Vec<_>>());
+        buffer.remove_where(|v| *v >= 50);
+        let first = buffer.load().as_ptr() as usize;
+        // no reader holds a snapshot, so the two buffers alternate
+        buffer.remove_where(|v| *v >= 25);
+        buffer.remove_where(|v| *v >= 10);
+        assert_eq!(buffer.load().as_ptr() as usize, first);
+        assert_eq!(*buffer.load(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_readers_never_see_partial_pass() {
+        let buffer = Arc::new(DoubleBuffer::new((0..1000).collect::<Vec<_>>()));
+        let readers = (0..4)
+            .map(|_| {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let snapshot = buffer.load();
+                        // every pass removes the largest hundred, so each snapshot is a full range
+                        assert!(snapshot.iter().enumerate().all(|(i, v)| i == *v));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for limit in (0..1000).step_by(100).rev() {
+            buffer.remove_where(|v| *v >= limit);
+        }
+        readers.into_iter().for_each(|r| r.join().unwrap());
+        assert!(buffer.load().is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Draining iterators, which yield the owned elements directly instead of item handles.
+
+use std::iter::FusedIterator;
+
+/// An iterator which removes every element of the vector and yields it.
+///
+/// The elements are visited from the back, so each one is popped without moving any other
+/// element. The elements which are not visited are dropped together with the iterator.
+pub struct DrainUnordered<'a, T> {
+    /// The underlying vector.
+    vector: &'a mut Vec<T>,
+    /// The number of elements not yet visited, they are at the front of the vector.
+    index: usize,
+}
+
+impl<'a, T> DrainUnordered<'a, T> {
+    pub fn new(v: &'a mut Vec<T>) -> Self {
+        let index = v.len();
+        Self { vector: v, index }
+    }
+}
+
+impl<'a, T> Drop for DrainUnordered<'a, T> {
+    fn drop(&mut self) {
+        self.vector.drain(..self.index);
+    }
+}
+
+impl<'a, T> Iterator for DrainUnordered<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+        Some(self.vector.swap_remove(self.index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.index, Some(self.index))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for DrainUnordered<'a, T> {}
+
+impl<'a, T> FusedIterator for DrainUnordered<'a, T> {}
+
+/// What [`drain_unless`](crate::prelude::InplaceVector::drain_unless) does with an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainAction<T> {
+    /// The element is removed from the vector and yielded.
+    Yield(T),
+    /// The element is put back into the vector.
+    Keep(T),
+}
+
+/// An iterator which removes the elements and yields them, unless the closure puts them back.
+///
+/// The elements are visited from the back. Putting an element back restores it at its place,
+/// yielding it moves the last element of the vector into its place, like a removal does. The
+/// elements which are not visited are kept, when the iterator is dropped.
+pub struct DrainUnless<'a, T, F> {
+    /// The underlying vector.
+    vector: &'a mut Vec<T>,
+    /// The number of elements not yet visited, they are at the front of the vector.
+    index: usize,
+    /// Decides what happens with each element.
+    f: F,
+}
+
+impl<'a, T, F> DrainUnless<'a, T, F>
+where
+    F: FnMut(T) -> DrainAction<T>,
+{
+    pub fn new(v: &'a mut Vec<T>, f: F) -> Self {
+        let index = v.len();
+        Self { vector: v, index, f }
+    }
+}
+
+impl<'a, T, F> Iterator for DrainUnless<'a, T, F>
+where
+    F: FnMut(T) -> DrainAction<T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index > 0 {
+            self.index -= 1;
+            match (self.f)(self.vector.swap_remove(self.index)) {
+                DrainAction::Yield(value) => return Some(value),
+                DrainAction::Keep(value) => {
+                    // undo the swap, so the kept element is not moved
+                    self.vector.push(value);
+                    let last = self.vector.len() - 1;
+                    self.vector.swap(self.index, last);
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.index))
+    }
+}
+
+impl<'a, T, F> FusedIterator for DrainUnless<'a, T, F> where F: FnMut(T) -> DrainAction<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::prelude::{DrainAction, InplaceVector};
+
+    #[test]
+    fn test_drain_unordered() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut drained = a.drain_unordered().collect::<Vec<_>>();
+        drained.sort();
+        assert_eq!(drained, vec![1, 2, 3, 4]);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_drain_unordered_drops_rest() {
+        let counter = Rc::new(());
+        let mut a = vec![counter.clone(), counter.clone(), counter.clone()];
+        let mut iter = a.drain_unordered();
+        assert_eq!(iter.len(), 3);
+        let first = iter.next().unwrap();
+        drop(iter);
+        assert!(a.is_empty());
+        assert_eq!(Rc::strong_count(&counter), 2);
+        drop(first);
+    }
+
+    #[test]
+    fn test_drain_unless() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let drained = a
+            .drain_unless(|v| if v % 3 == 0 { DrainAction::Yield(v) } else { DrainAction::Keep(v) })
+            .collect::<Vec<_>>();
+        assert_eq!(drained, vec![6, 3]);
+        assert_eq!(a, vec![1, 2, 5, 4]);
+    }
+
+    #[test]
+    fn test_drain_unless_keeps_unvisited() {
+        let mut a = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let iter = a.drain_unless(|mut v| {
+            v.push('!');
+            DrainAction::Keep(v)
+        });
+        assert_eq!(iter.size_hint(), (0, Some(3)));
+        drop(iter);
+        assert_eq!(a, vec!["a", "b", "c"]);
+        let taken = a.drain_unless(DrainAction::Yield).take(1).collect::<Vec<_>>();
+        assert_eq!(taken, vec!["c"]);
+        assert_eq!(a, vec!["a", "b"]);
+    }
+}
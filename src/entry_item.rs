@@ -0,0 +1,90 @@
+//! Traits for the items of map-like containers, which carry a key next to the value.
+
+/// A trait for map entries that can be removed from their container during iteration.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use inplace_iter::prelude::*;
+///
+/// fn remove_empty<E: RemovableEntry<&'static str, Vec<u32>>>(items: impl Iterator<Item = E>) {
+///     for item in items {
+///         if item.get().is_empty() {
+///             item.remove();
+///         }
+///     }
+/// }
+///
+/// let mut groups = HashMap::from([("a", vec![1]), ("b", vec![])]);
+/// remove_empty(groups.takeable_values_iter());
+/// assert_eq!(groups, HashMap::from([("a", vec![1])]));
+/// ```
+pub trait RemovableEntry<K, V> {
+    /// Removes the current entry from the container.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn remove(self);
+
+    /// Returns the key of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn key(&self) -> &K;
+
+    /// Returns a reference to the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get(&self) -> &V;
+
+    /// Returns a mutable reference to the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_mut(&mut self) -> &mut V;
+}
+
+/// A trait for map entries that can be taken from their container during iteration.
+pub trait TakeableEntry<K, V> {
+    /// Takes the current entry out of the container and returns its key and value.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn take(self) -> (K, V);
+
+    /// Returns the key of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn key(&self) -> &K;
+
+    /// Returns a reference to the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get(&self) -> &V;
+
+    /// Returns a mutable reference to the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_mut(&mut self) -> &mut V;
+}
@@ -0,0 +1,220 @@
+//! The invalidation of the yielded items, for the `loop-lifetime-guard` feature.
+//!
+//! An iterator owns one shared counter, each item remembers the value it was yielded at.
+//! Advancing the counter invalidates all the items yielded so far at once, without any
+//! allocation per item.
+//!
+//! A detected misuse panics by default. With the `guard-abort` feature the process is aborted
+//! instead, so no code runs on after the misuse, not even the unwinding. A hook set with
+//! [`set_violation_hook`] replaces both.
+//!
+//! On `wasm32`, where a panic or an abort traps the whole instance and the standard error goes
+//! nowhere, the hook is the place to report the misuse to the host before trapping, e.g. through
+//! a binding to `console.error`.
+//!
+//! A second pass over a container while one is alive cannot be started by safe code, the pass
+//! borrows the container mutably. If unsafe code does it anyway, the creation of the second pass
+//! is not detected. Only the swap removing, order preserving, pairwise and appending passes over
+//! a vector, and the string passes, notice it afterwards, and only if the second pass changed
+//! the length or the allocation of the vector: they check its shape on their next step (see
+//! `check_detached` and the `check_unchanged` of the vector iterators). A confirm session, whose
+//! marking only swaps elements, and the deque, map and set passes detect nothing. There is no
+//! registry of the alive passes: a pass leaked with `mem::forget` could never leave it, and
+//! would make every later pass over the same address fail.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::RwLock;
+
+/// The hook called on a detected misuse, instead of panicking or aborting.
+static VIOLATION_HOOK: RwLock<Option<fn(&str) -> !>> = RwLock::new(None);
+
+/// Sets the function called when the guard detects a misuse, e.g. to log the message and
+/// terminate the process in a custom way, and returns the previous one. The hook gets the
+/// description of the misuse and must not return. `None` restores the default reaction.
+///
+/// The hook is global to the process.
+///
+/// # Examples
+///
+/// ```no_run
+/// fn fail_hard(message: &str) -> ! {
+///     eprintln!("fatal: {message}");
+///     std::process::exit(101)
+/// }
+///
+/// let previous = inplace_iter::epoch::set_violation_hook(Some(fail_hard));
+/// // ...
+/// inplace_iter::epoch::set_violation_hook(previous);
+/// ```
+pub fn set_violation_hook(hook: Option<fn(&str) -> !>) -> Option<fn(&str) -> !> {
+    std::mem::replace(&mut *VIOLATION_HOOK.write().unwrap_or_else(|e| e.into_inner()), hook)
+}
+
+/// Reports a detected misuse: calls the hook, or aborts with the `guard-abort` feature, or
+/// panics.
+pub(crate) fn violation(message: &str) -> ! {
+    let hook = *VIOLATION_HOOK.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(hook) = hook {
+        hook(message);
+    }
+    if cfg!(feature = "guard-abort") {
+        eprintln!("{message}");
+        std::process::abort();
+    }
+    panic!("{}", message);
+}
+
+/// Returns `true` if a vector detached for a compacting pass (its length set to 0 for the pass)
+/// still has the length 0 and the allocation `data`.
+pub(crate) fn is_detached<T>(vector: &[T], data: *const T) -> bool {
+    vector.is_empty() && std::ptr::eq(vector.as_ptr(), data)
+}
+
+/// Reports a detached vector changed other than through the pass, e.g. grown into a new
+/// allocation while the pass holds pointers into the old one.
+pub(crate) fn check_detached<T>(vector: &[T], data: *const T) {
+    if !is_detached(vector, data) {
+        violation("The vector was changed during the iteration other than through the iterator items!");
+    }
+}
+
+/// The iterator side of the guard.
+#[derive(Debug, Default)]
+pub(crate) struct Epoch {
+    /// The current epoch, shared with the yielded items.
+    current: Rc<Cell<u64>>,
+}
+
+impl Epoch {
+    /// Invalidates all the items yielded so far.
+    pub(crate) fn advance(&self) {
+        self.current.set(self.current.get().wrapping_add(1));
+    }
+
+    /// Returns the guard for an item yielded now.
+    pub(crate) fn item(&self) -> ItemEpoch {
+        ItemEpoch { current: self.current.clone(), at: self.current.get() }
+    }
+}
+
+/// The item side of the guard.
+#[derive(Debug)]
+pub(crate) struct ItemEpoch {
+    /// The current epoch of the iterator.
+    current: Rc<Cell<u64>>,
+    /// The epoch the item was yielded at.
+    at: u64,
+}
+
+impl ItemEpoch {
+    /// Panics if the item was invalidated.
+    pub(crate) fn check(&self) {
+        if self.current.get() != self.at {
+            violation("This iterator item is no longer valid!");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "guard-abort"))]
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    #[cfg(not(feature = "guard-abort"))]
+    use super::Epoch;
+
+    #[test]
+    #[cfg(not(feature = "guard-abort"))]
+    fn test_advance_invalidates_all_items() {
+        let epoch = Epoch::default();
+        let first = epoch.item();
+        let second = epoch.item();
+        first.check();
+        epoch.advance();
+        let third = epoch.item();
+        third.check();
+        assert!(catch_unwind(AssertUnwindSafe(|| first.check())).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| second.check())).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "guard-abort"))]
+    #[cfg_attr(miri, ignore)]
+    fn test_second_pass_over_a_container() {
+        use crate::prelude::{InplaceVector, RemovableItem};
+
+        let mut v = vec![1, 2, 3];
+        let alias = &mut v as *mut Vec<i32>;
+        let mut first = v.removable_iter();
+        first.next();
+        // only reachable through a raw pointer
+        unsafe { &mut *alias }.removable_iter().next().unwrap().remove();
+        assert!(catch_unwind(AssertUnwindSafe(|| first.next().is_some())).is_err());
+    }
+
+    #[test]
+    fn test_leaked_pass_does_not_block_the_next() {
+        use crate::prelude::{InplaceVector, RemovableItem};
+
+        let mut v = vec![1, 2, 3];
+        std::mem::forget(v.removable_iter());
+        for item in v.removable_iter() {
+            if *item.get() == 2 {
+                item.remove();
+            }
+        }
+        assert_eq!(v, vec![1, 3]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "guard-abort"))]
+    fn test_items_invalid_after_caught_panic() {
+        use crate::prelude::{InplaceVector, RemovableConfirmIterator, RemovableItem};
+
+        let mut v = (0..6).collect::<Vec<u32>>();
+        let mut smuggled = None;
+        let (borrowed, stash) = (&mut v, &mut smuggled);
+        let caught = catch_unwind(AssertUnwindSafe(move || {
+            let borrowed = borrowed;
+            for item in borrowed.removable_iter() {
+                if *item.get() == 0 {
+                    item.remove();
+                } else if *item.get() == 2 {
+                    *stash = Some(item);
+                    panic!("the loop body failed");
+                }
+            }
+        }));
+        assert!(caught.is_err());
+        let item = smuggled.take().unwrap();
+        assert!(catch_unwind(AssertUnwindSafe(|| item.remove())).is_err());
+        drop(smuggled);
+        // the removal before the panic stays applied
+        let mut kept = v.clone();
+        kept.sort();
+        assert_eq!(kept, vec![1, 2, 3, 4, 5]);
+
+        let caught = catch_unwind(AssertUnwindSafe(|| {
+            for item in v.ordered_removable_iter() {
+                if *item.get() == 5 {
+                    item.remove();
+                } else if *item.get() == 3 {
+                    panic!("the loop body failed");
+                }
+            }
+        }));
+        assert!(caught.is_err());
+        assert_eq!(v.len(), 4);
+
+        let caught = catch_unwind(AssertUnwindSafe(|| {
+            let mut confirm = v.removable_confirm_iter();
+            for item in confirm.iter() {
+                item.remove();
+            }
+            panic!("failed before confirming");
+        }));
+        assert!(caught.is_err());
+        // nothing confirmed
+        assert_eq!(v.len(), 4);
+    }
+}
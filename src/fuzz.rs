@@ -0,0 +1,286 @@
+//! An interpreter of operation scripts, the entry point for fuzzing and property testing.
+//!
+//! A script is a sequence of [`Op`]s driving the passes over a vector step by step, so random
+//! scripts from `cargo fuzz` or `proptest` reach the states a hand written test does not, e.g.
+//! a removal right after the end of a pass. The [`Event`]s returned by [`apply_script`] and the
+//! final vector are compared against [`apply_script_model`], a slow but safe model of the same
+//! semantics, by [`check_equivalence`].
+//!
+//! # Passes
+//!
+//! The script is split into passes after every [`Op::Confirm`] and [`Op::Cancel`]:
+//!
+//! - A pass ended by one of them is a confirm session, where [`Op::Remove`] and [`Op::Take`]
+//!   mark the current element for removal, and the end confirms or cancels the marks.
+//! - The pass at the end of the script is a swap removing pass, where [`Op::Remove`] drops the
+//!   current element and [`Op::Take`] moves it into the events.
+//!
+//! # Examples
+//!
+//! ```
+//! use inplace_iter::fuzz::{apply_script, Event, Op};
+//!
+//! let mut v = vec![1, 2, 3, 4];
+//! let script = [Op::Next, Op::Remove, Op::Next, Op::Get, Op::Cancel, Op::Next, Op::Take];
+//! let events = apply_script(&script, &mut v);
+//! // the cancelled session swapped the marked 1 behind the kept elements
+//! assert_eq!(events, vec![Event::Got(Some(4)), Event::Taken(4)]);
+//! assert_eq!(v, vec![1, 2, 3]);
+//! ```
+
+use std::fmt::Debug;
+use crate::inplace_vector::InplaceVector;
+use crate::removable_confirm_iterator_vec::RemovableConfirmIterator;
+use crate::removable_iterator::RemovableItemMut;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// A step of a script, see [`apply_script`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    /// Advances the pass to the next item, there is no current item once the pass is done.
+    Next,
+    /// Records the current element, see [`Event::Got`].
+    Get,
+    /// Removes the current element, nothing without a current item.
+    Remove,
+    /// Takes the current element, nothing without a current item.
+    Take,
+    /// Ends the confirm session confirming the marked removals.
+    Confirm,
+    /// Ends the confirm session cancelling the marked removals.
+    Cancel,
+}
+
+impl From<u8> for Op {
+    /// Maps any byte to an operation, for the raw byte input of a fuzzer.
+    fn from(byte: u8) -> Self {
+        match byte % 6 {
+            0 => Op::Next,
+            1 => Op::Get,
+            2 => Op::Remove,
+            3 => Op::Take,
+            4 => Op::Confirm,
+            _ => Op::Cancel,
+        }
+    }
+}
+
+/// What a script observed, in the order of the operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<T> {
+    /// The current element at an [`Op::Get`], `None` without a current item.
+    Got(Option<T>),
+    /// The element taken by an [`Op::Take`] in a swap removing pass.
+    Taken(T),
+}
+
+/// Runs `script` against `v` and returns the observed events.
+///
+/// See the [module documentation](self) for the semantics of the operations.
+pub fn apply_script<T: Clone>(script: &[Op], v: &mut Vec<T>) -> Vec<Event<T>> {
+    let mut events = Vec::new();
+    for pass in script.split_inclusive(|op| matches!(op, Op::Confirm | Op::Cancel)) {
+        match pass.split_last() {
+            Some((end @ (Op::Confirm | Op::Cancel), steps)) => run_confirm_session(steps, *end, v, &mut events),
+            _ => run_swap_pass(pass, v, &mut events),
+        }
+    }
+    events
+}
+
+/// Runs the steps of a swap removing pass.
+fn run_swap_pass<T: Clone>(steps: &[Op], v: &mut Vec<T>, events: &mut Vec<Event<T>>) {
+    let mut iter = v.takeable_iter_mut();
+    let mut current = None;
+    for step in steps {
+        match step {
+            Op::Next => current = iter.next(),
+            Op::Get => events.push(Event::Got(current.as_ref().map(|item| item.get().clone()))),
+            Op::Remove => {
+                if let Some(item) = current.take() {
+                    drop(item.take());
+                }
+            }
+            Op::Take => {
+                if let Some(item) = current.take() {
+                    events.push(Event::Taken(item.take()));
+                }
+            }
+            Op::Confirm | Op::Cancel => unreachable!("a swap removing pass is not ended by {step:?}"),
+        }
+    }
+}
+
+/// Runs the steps of a confirm session, and ends it by `end`.
+fn run_confirm_session<T: Clone>(steps: &[Op], end: Op, v: &mut Vec<T>, events: &mut Vec<Event<T>>) {
+    let mut session = v.removable_confirm_iter_mut();
+    let mut iter = session.iter();
+    let mut current = None;
+    for step in steps {
+        match step {
+            Op::Next => current = iter.next(),
+            Op::Get => events.push(Event::Got(current.as_ref().map(|item| item.get().clone()))),
+            Op::Remove | Op::Take => {
+                if let Some(item) = current.take() {
+                    item.remove();
+                }
+            }
+            Op::Confirm | Op::Cancel => unreachable!("{step:?} ends the session"),
+        }
+    }
+    drop(current);
+    drop(iter);
+    if end == Op::Confirm {
+        session.confirm_removals();
+    } else {
+        session.cancel_removals();
+    }
+}
+
+/// Runs `script` against `v` like [`apply_script`], but on a reference model of the passes.
+///
+/// The model is safe code only, it replays the positions of the passes with plain vector
+/// operations and clones the elements it reports.
+pub fn apply_script_model<T: Clone>(script: &[Op], v: &mut Vec<T>) -> Vec<Event<T>> {
+    let mut events = Vec::new();
+    for pass in script.split_inclusive(|op| matches!(op, Op::Confirm | Op::Cancel)) {
+        let (steps, end) = match pass.split_last() {
+            Some((end @ (Op::Confirm | Op::Cancel), steps)) => (steps, Some(*end)),
+            _ => (pass, None),
+        };
+        let mut model = PassModel { index: None, removed: false, size: v.len(), current: None };
+        for step in steps {
+            match step {
+                Op::Next => model.next(v, end.is_some()),
+                Op::Get => events.push(Event::Got(model.current.map(|index| v[index].clone()))),
+                Op::Remove | Op::Take => {
+                    let Some(index) = model.current.take() else {
+                        continue;
+                    };
+                    model.removed = true;
+                    if end.is_some() {
+                        // marked: swapped behind the elements still in the session
+                        model.size -= 1;
+                        v.swap(index, model.size);
+                    } else {
+                        let value = v.swap_remove(index);
+                        if *step == Op::Take {
+                            events.push(Event::Taken(value));
+                        }
+                    }
+                }
+                Op::Confirm | Op::Cancel => unreachable!("{step:?} ends the pass"),
+            }
+        }
+        if end == Some(Op::Confirm) {
+            v.truncate(model.size);
+        }
+    }
+    events
+}
+
+/// The position of a pass in the reference model.
+struct PassModel {
+    /// The index of the last item, `None` before the first one.
+    index: Option<usize>,
+    /// Whether the last item was removed, so its index is visited again.
+    removed: bool,
+    /// The number of elements not marked for removal, in a confirm session.
+    size: usize,
+    /// The index of the current item.
+    current: Option<usize>,
+}
+
+impl PassModel {
+    fn next<T>(&mut self, v: &[T], confirm: bool) {
+        self.current = None;
+        if v.is_empty() {
+            return;
+        }
+        let index = match self.index {
+            Some(index) if self.removed => index,
+            Some(index) => index + 1,
+            None => 0,
+        };
+        self.index = Some(index);
+        self.removed = false;
+        let end = if confirm { self.size } else { v.len() };
+        if index < end {
+            self.current = Some(index);
+        }
+    }
+}
+
+/// Runs `script` by [`apply_script`] and by [`apply_script_model`] on copies of `v`, and panics
+/// if the events or the resulting vectors differ.
+///
+/// Meant as the body of a fuzz target or a property test.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::fuzz::{check_equivalence, Op};
+///
+/// let data = [0u8, 2, 0, 0, 3, 1, 4, 0, 2, 5];
+/// let script = data.iter().map(|byte| Op::from(*byte)).collect::<Vec<_>>();
+/// check_equivalence(&script, &[1, 2, 3, 4, 5]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the crate and the model disagree.
+pub fn check_equivalence<T: Clone + PartialEq + Debug>(script: &[Op], v: &[T]) {
+    let mut actual = v.to_vec();
+    let mut expected = v.to_vec();
+    let actual_events = apply_script(script, &mut actual);
+    let expected_events = apply_script_model(script, &mut expected);
+    assert_eq!(actual_events, expected_events, "the events differ for the script {script:?}");
+    assert_eq!(actual, expected, "the vectors differ for the script {script:?}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_script, check_equivalence, Event, Op};
+
+    #[test]
+    fn test_swap_pass_script() {
+        let mut v = vec![1, 2, 3, 4];
+        let script = [Op::Next, Op::Get, Op::Remove, Op::Next, Op::Take, Op::Remove, Op::Next, Op::Get, Op::Next, Op::Next, Op::Get, Op::Take];
+        let events = apply_script(&script, &mut v);
+        assert_eq!(events, vec![Event::Got(Some(1)), Event::Taken(4), Event::Got(Some(3)), Event::Got(None)]);
+        assert_eq!(v, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_confirm_session_scripts() {
+        let mut v = vec![1, 2, 3, 4];
+        let script = [Op::Next, Op::Take, Op::Next, Op::Get, Op::Next, Op::Confirm, Op::Confirm, Op::Next, Op::Next, Op::Remove, Op::Cancel];
+        let events = apply_script(&script, &mut v);
+        assert_eq!(events, vec![Event::Got(Some(4))]);
+        // the cancelled session swapped the marked 2 behind 3
+        assert_eq!(v, vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn test_any_bytes() {
+        let script = (0..=255u8).map(Op::from).collect::<Vec<_>>();
+        let mut v = (0..50).collect::<Vec<_>>();
+        apply_script(&script, &mut v);
+        assert!(v.len() <= 50);
+    }
+
+    #[test]
+    fn test_equivalence_with_model() {
+        // a fixed linear congruential generator, the scripts are the same on every run
+        let mut state = 0x2545_f491u32;
+        for n in 0..500 {
+            let script = (0..n % 64)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    Op::from((state >> 16) as u8)
+                })
+                .collect::<Vec<_>>();
+            check_equivalence(&script, &(0..n % 9).collect::<Vec<_>>());
+        }
+    }
+}
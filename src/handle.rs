@@ -0,0 +1,292 @@
+//! Stable keys for the elements kept by a removable pass.
+//!
+//! An item must not outlive its loop, but it can be downgraded to a [`VecHandle`], which can be
+//! stored and later resolved against the vector. The handle remembers the index and the shape
+//! of the vector (its allocation and length) at the end of the pass, and only resolves while
+//! the vector still has that shape. This is a cheap heuristic, not a proof that the vector is
+//! unchanged: see the validation notes of [`VecHandle`].
+//!
+//! A [`RemovalRequest`] is a handle which removes its element when applied. It is `Send`, so a
+//! worker can decide the removals and send them to the thread owning the vector.
+
+use std::sync::{Arc, OnceLock};
+
+/// The shape of a vector, its allocation and length, compared to detect the common changes.
+///
+/// A change which ends with the same allocation and length (e.g. a `pop` followed by a `push`)
+/// keeps the shape, so two equal shapes do not prove that the vector is unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VecShape {
+    /// The address of the allocation, only compared.
+    data: usize,
+    len: usize,
+}
+
+impl VecShape {
+    pub(crate) fn of<T>(v: &[T]) -> Self {
+        Self { data: v.as_ptr().addr(), len: v.len() }
+    }
+}
+
+/// The shape shared by the handles of a pass, it is set when the pass ends, unless the pass
+/// moved kept elements.
+pub(crate) type PassStamp = Arc<OnceLock<VecShape>>;
+
+/// A trait for items which can be downgraded to a [`VecHandle`].
+pub trait ToHandle {
+    /// Gives up the item in exchange for a handle to its element, which can be kept after the
+    /// loop. The element stays in the vector.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn to_handle(self) -> VecHandle;
+
+    /// Gives up the item in exchange for an owned request to remove its element, which can be
+    /// sent to the thread owning the vector and applied after the loop. The element stays in
+    /// the vector until then.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn into_removal_request(self) -> RemovalRequest
+    where
+        Self: Sized,
+    {
+        RemovalRequest { handle: self.to_handle() }
+    }
+}
+
+/// A handle to an element kept by a removable pass.
+///
+/// # Validation
+///
+/// The validation is a best-effort heuristic. The handle resolves only if the vector has the
+/// allocation and the length it had at the end of the pass, which catches e.g. a single push,
+/// removal or reallocation since then. Changes which end with the same length and allocation
+/// are not detected: after a `swap`, a `sort`, or a `pop` followed by a `push`, the handle
+/// resolves to whatever element is now at its index. The handle is not tied to a vector
+/// either, it is on the caller to resolve it against the vector of its pass.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3, 4, 5];
+/// let mut largest = None;
+/// for item in numbers.removable_iter() {
+///     if *item.get() % 2 == 0 {
+///         item.remove();
+///     } else if *item.get() == 5 {
+///         largest = Some(item.to_handle());
+///     }
+/// }
+/// let largest = largest.unwrap();
+/// *largest.resolve(&mut numbers).unwrap() *= 10;
+/// assert_eq!(numbers, vec![1, 50, 3]);
+///
+/// numbers.push(7);
+/// assert!(largest.resolve(&mut numbers).is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct VecHandle {
+    /// The index of the element.
+    index: usize,
+    /// The shape of the vector at the end of the pass, `None` if the pass moved kept elements.
+    stamp: PassStamp,
+}
+
+impl VecHandle {
+    pub(crate) fn new(index: usize, stamp: PassStamp) -> Self {
+        Self { index, stamp }
+    }
+
+    /// The index of the element in the vector, valid as long as the handle resolves.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns `true` if `v` has the shape it had at the end of the pass, see the validation
+    /// notes above.
+    pub fn is_valid<T>(&self, v: &[T]) -> bool {
+        self.stamp.get() == Some(&VecShape::of(v))
+    }
+
+    /// Returns a reference to the element, or `None` if the shape of the vector changed since
+    /// the pass.
+    pub fn resolve_ref<'v, T>(&self, v: &'v [T]) -> Option<&'v T> {
+        if self.is_valid(v) { v.get(self.index) } else { None }
+    }
+
+    /// Returns a mutable reference to the element, or `None` if the shape of the vector changed
+    /// since the pass.
+    pub fn resolve<'v, T>(&self, v: &'v mut [T]) -> Option<&'v mut T> {
+        if self.is_valid(v) { v.get_mut(self.index) } else { None }
+    }
+}
+
+/// An owned request to remove an element kept by a removable pass, see
+/// [`ToHandle::into_removal_request`].
+///
+/// # Validation
+///
+/// The request is validated like a [`VecHandle`], by the shape of the vector at the end of the
+/// pass, with the same best-effort heuristic and its blind spots. Applying one request changes
+/// the vector, so the other requests of the same pass are to be applied together by
+/// [`RemovalRequest::apply_all`].
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+/// use std::sync::mpsc;
+///
+/// let mut jobs = vec![1, 2, 3, 4, 5, 6];
+/// let (sender, receiver) = mpsc::channel();
+/// for item in jobs.removable_iter() {
+///     if *item.get() % 2 == 0 {
+///         sender.send(item.into_removal_request()).unwrap();
+///     }
+/// }
+/// drop(sender);
+///
+/// // e.g. a worker thread confirms the requests before they come back
+/// let confirmed = std::thread::spawn(move || receiver.iter().collect::<Vec<_>>()).join().unwrap();
+/// let mut removed = RemovalRequest::apply_all(confirmed, &mut jobs);
+/// removed.sort();
+/// assert_eq!(removed, vec![2, 4, 6]);
+/// assert_eq!(jobs.len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RemovalRequest {
+    /// The handle of the element to remove.
+    handle: VecHandle,
+}
+
+impl RemovalRequest {
+    /// The index of the element in the vector, valid as long as the request is.
+    pub fn index(&self) -> usize {
+        self.handle.index()
+    }
+
+    /// Returns `true` if `v` has the shape it had at the end of the pass, see
+    /// [`VecHandle::is_valid`].
+    pub fn is_valid<T>(&self, v: &[T]) -> bool {
+        self.handle.is_valid(v)
+    }
+
+    /// Removes the element and returns it, or returns `None` if the shape of the vector changed
+    /// since the pass.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the last element takes the place of the removed one
+    pub fn apply<T>(self, v: &mut Vec<T>) -> Option<T> {
+        if self.is_valid(v) { Some(v.swap_remove(self.index())) } else { None }
+    }
+
+    /// Removes the elements of the valid `requests` and returns them, the invalid requests and
+    /// the repeated ones are ignored.
+    ///
+    /// All the requests are validated before the first removal, so the requests of one pass
+    /// apply together.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity per element, after sorting the indices
+    /// - The order of elements is not preserved
+    pub fn apply_all<T, I>(requests: I, v: &mut Vec<T>) -> Vec<T>
+    where
+        I: IntoIterator<Item = RemovalRequest>,
+    {
+        let mut indices = requests.into_iter().filter(|request| request.is_valid(v)).map(|request| request.index()).collect::<Vec<_>>();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices.dedup();
+        // from the back, so the element swapped in by a removal is never one still to remove
+        indices.into_iter().map(|index| v.swap_remove(index)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RemovalRequest;
+    use crate::prelude::{InplaceVector, RemovableItem, TakeableItemMut, ToHandle};
+
+    #[test]
+    fn test_handles_survive_later_removals() {
+        let mut a = (0..10).collect::<Vec<_>>();
+        let mut handles = Vec::new();
+        for mut item in a.takeable_iter_mut() {
+            if *item.get() % 3 == 0 {
+                *item.get_mut() += 100;
+                handles.push(item.to_handle());
+            } else if *item.get() > 5 {
+                item.take();
+            }
+        }
+        let resolved = handles.iter().map(|h| *h.resolve_ref(&a).unwrap()).collect::<Vec<_>>();
+        assert_eq!(resolved, vec![100, 103, 106, 109]);
+        assert!(handles.iter().all(|h| a[h.index()] >= 100));
+    }
+
+    #[test]
+    fn test_handles_invalidated_by_changes() {
+        let mut a = vec![1, 2, 3];
+        let handle = a.removable_iter().next().unwrap().to_handle();
+        assert!(handle.is_valid(&a));
+        a.pop();
+        assert!(handle.resolve(&mut a).is_none());
+    }
+
+    #[test]
+    fn test_same_shape_is_not_detected() {
+        let mut a = vec![1, 2, 3];
+        let handle = a.removable_iter().nth(2).unwrap().to_handle();
+        a.pop();
+        a.push(99);
+        // the length and the allocation are the same, the heuristic cannot tell
+        assert_eq!(handle.resolve_ref(&a), Some(&99));
+    }
+
+    #[test]
+    fn test_handles_invalidated_by_wrapped_move() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut handles = Vec::new();
+        for item in a.removable_iter_from(2, true) {
+            if *item.get() == 1 {
+                // swaps in the already visited 4
+                item.remove();
+            } else {
+                handles.push(item.to_handle());
+            }
+        }
+        assert!(handles.iter().all(|h| !h.is_valid(&a)));
+    }
+
+    #[test]
+    fn test_removal_requests() {
+        fn assert_send<S: Send>(_: &S) {}
+        let mut a = (0..10).collect::<Vec<_>>();
+        let mut requests = Vec::new();
+        for item in a.removable_iter() {
+            if *item.get() % 3 == 0 {
+                requests.push(item.into_removal_request());
+            } else if *item.get() == 8 {
+                item.remove();
+            }
+        }
+        assert_send(&requests[0]);
+        requests.push(requests[1].clone());
+        assert!(requests.iter().all(|request| request.is_valid(&a)));
+        let mut removed = RemovalRequest::apply_all(requests.clone(), &mut a);
+        removed.sort();
+        assert_eq!(removed, vec![0, 3, 6, 9]);
+        a.sort();
+        assert_eq!(a, vec![1, 2, 4, 5, 7]);
+        // the vector changed, the requests are stale
+        assert!(requests.into_iter().all(|request| request.apply(&mut a).is_none()));
+    }
+}
@@ -0,0 +1,507 @@
+//! In-place iteration over `HashMap` entries.
+//!
+//! A `HashMap` cannot remove entries while being iterated, so the entries are moved out of the
+//! map when the iteration starts. Each yielded item owns its entry and inserts it back into the
+//! map when it is dropped, unless it was taken, and the entries not yet visited are inserted
+//! back when the iterator is dropped.
+//!
+//! This is not free: the whole table is moved out of the map, `map.reserve(len)` allocates a
+//! second table of the same size, and every kept entry is rehashed when it is inserted back.
+//! Only taking an entry costs nothing extra. Code which only decides by a predicate which
+//! entries to drop or move out is better served by `HashMap::retain` or
+//! `HashMap::extract_if`, which work on the table in place.
+//!
+//! Leaking the iterator (e.g. with `mem::forget`) loses the entries not yet visited, and
+//! leaking an item loses its entry, like leaking a `Vec::drain` loses the drained tail.
+
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use crate::entry_item::{RemovableEntry, TakeableEntry};
+use crate::prelude::TakeableItem;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// An iterator over the values of a `HashMap`, which allows taking them out of the map.
+pub struct TakeableHashMapValues<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// This tells the borrow checker that the map is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut HashMap<K, V, S>>,
+    /// The map, it holds the entries outside the iteration. The items insert the kept ones.
+    map: *mut HashMap<K, V, S>,
+    /// The entries not yet visited.
+    entries: hash_map::IntoIter<K, V>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, K, V, S> TakeableHashMapValues<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn new(map: &'a mut HashMap<K, V, S>) -> Self
+    where
+        S: Clone,
+    {
+        let empty = HashMap::with_hasher(map.hasher().clone());
+        let entries = std::mem::replace(map, empty).into_iter();
+        map.reserve(entries.len());
+        Self {
+            _lifetime_guard: PhantomData,
+            map,
+            entries,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+/// Inserts an entry kept by an item back into the `HashMap` behind `map`.
+unsafe fn reinsert<K: Eq + Hash, V, S: BuildHasher>(map: *mut (), key: K, value: V) {
+    unsafe { (*map.cast::<HashMap<K, V, S>>()).insert(key, value) };
+}
+
+impl<'a, K, V, S> Drop for TakeableHashMapValues<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        unsafe { (*self.map).extend(&mut self.entries) };
+    }
+}
+
+impl<'a, K, V, S> Iterator for TakeableHashMapValues<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = TakeableMapValue<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let entry = self.entries.next()?;
+        Some(TakeableMapValue::new(self.map.cast(), reinsert::<K, V, S>, entry, #[cfg(feature = "loop-lifetime-guard")] self.epoch.item()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+/// An iterator over the entries of a `HashMap`, which allows removing or taking them.
+///
+/// It visits the map like [`TakeableHashMapValues`], only its items take the whole entry.
+pub struct HashMapEntries<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    values: TakeableHashMapValues<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> HashMapEntries<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn new(map: &'a mut HashMap<K, V, S>) -> Self
+    where
+        S: Clone,
+    {
+        Self { values: TakeableHashMapValues::new(map) }
+    }
+}
+
+impl<'a, K, V, S> Iterator for HashMapEntries<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = MapEntry<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values.next().map(MapEntry::new)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+/// An item of a map iteration, which can take its value (or the whole entry) out of the map.
+///
+/// The item holds its entry, and inserts it back into the map when dropped, unless it was
+/// taken. The references it returns borrow the item, so they stay valid for as long as the
+/// item lives, also over the following calls to `next()`.
+pub struct TakeableMapValue<'a, K, V> {
+    /// This ties the item to the borrow of the map by the iterator.
+    _lifetime_guard: PhantomData<&'a mut ()>,
+    /// A raw pointer to the map, for inserting the kept entry back.
+    map: *mut (),
+    /// Inserts a kept entry into the map behind `map`.
+    reinsert: unsafe fn(*mut (), K, V),
+    /// The entry, `None` once taken.
+    entry: Option<(K, V)>,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, K, V> TakeableMapValue<'a, K, V> {
+    pub(crate) fn new(map: *mut (), reinsert: unsafe fn(*mut (), K, V), entry: (K, V), #[cfg(feature = "loop-lifetime-guard")] rotten: ItemEpoch) -> Self {
+        Self {
+            _lifetime_guard: PhantomData,
+            map,
+            reinsert,
+            entry: Some(entry),
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    fn entry(&self) -> &(K, V) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.entry.as_ref().unwrap()
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.entry().0
+    }
+
+    /// Returns a reference to the value.
+    pub fn get(&self) -> &V {
+        &self.entry().1
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut V {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        &mut self.entry.as_mut().unwrap().1
+    }
+
+    /// Takes the entry out of the map and returns its value.
+    pub fn take(self) -> V {
+        self.take_entry().1
+    }
+
+    /// Takes the entry out of the map and returns its key and value.
+    pub fn take_entry(mut self) -> (K, V) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.entry.take().unwrap()
+    }
+}
+
+impl<'a, K, V> Drop for TakeableMapValue<'a, K, V> {
+    fn drop(&mut self) {
+        // the map outlives the item, its borrow is held for 'a
+        if let Some((key, value)) = self.entry.take() {
+            unsafe { (self.reinsert)(self.map, key, value) };
+        }
+    }
+}
+
+impl<'a, K, V> TakeableItem<V> for TakeableMapValue<'a, K, V> {
+    fn take(self) -> V {
+        TakeableMapValue::take(self)
+    }
+
+    fn get(&self) -> &V {
+        TakeableMapValue::get(self)
+    }
+}
+
+impl<'a, K, V> TakeableItemMut<V> for TakeableMapValue<'a, K, V> {
+    fn take(self) -> V {
+        TakeableMapValue::take(self)
+    }
+
+    fn get(&self) -> &V {
+        TakeableMapValue::get(self)
+    }
+
+    fn get_mut(&mut self) -> &mut V {
+        TakeableMapValue::get_mut(self)
+    }
+}
+
+impl<'a, K, V> RemovableEntry<K, V> for TakeableMapValue<'a, K, V> {
+    fn remove(self) {
+        let _ = self.take_entry();
+    }
+
+    fn key(&self) -> &K {
+        TakeableMapValue::key(self)
+    }
+
+    fn get(&self) -> &V {
+        TakeableMapValue::get(self)
+    }
+
+    fn get_mut(&mut self) -> &mut V {
+        TakeableMapValue::get_mut(self)
+    }
+}
+
+impl<'a, K, V> TakeableEntry<K, V> for TakeableMapValue<'a, K, V> {
+    fn take(self) -> (K, V) {
+        self.take_entry()
+    }
+
+    fn key(&self) -> &K {
+        TakeableMapValue::key(self)
+    }
+
+    fn get(&self) -> &V {
+        TakeableMapValue::get(self)
+    }
+
+    fn get_mut(&mut self) -> &mut V {
+        TakeableMapValue::get_mut(self)
+    }
+}
+
+/// An entry of a map iteration, which can be removed or taken out of the map.
+///
+/// Like [`TakeableMapValue`], it holds its entry and inserts it back when dropped. A reference
+/// to the key or the value borrows the entry, so it cannot outlive it, whether the entry is
+/// dropped at the end of a statement, before the next one is yielded:
+///
+/// ```compile_fail
+/// use std::collections::HashMap;
+/// use inplace_iter::prelude::*;
+///
+/// let mut map = HashMap::from([(1, 10), (2, 20)]);
+/// let mut iter = map.entries_iter();
+/// let value = iter.next().unwrap().get();
+/// iter.next();
+/// println!("{value}");
+/// ```
+///
+/// or removed:
+///
+/// ```compile_fail
+/// use std::collections::HashMap;
+/// use inplace_iter::prelude::*;
+///
+/// let mut map = HashMap::from([(1, 10)]);
+/// for entry in map.entries_iter() {
+///     let key = entry.key();
+///     entry.remove();
+///     println!("{key}");
+/// }
+/// ```
+pub struct MapEntry<'a, K, V> {
+    value: TakeableMapValue<'a, K, V>,
+}
+
+impl<'a, K, V> MapEntry<'a, K, V> {
+    pub(crate) fn new(value: TakeableMapValue<'a, K, V>) -> Self {
+        Self { value }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.value.key()
+    }
+
+    /// Returns a reference to the value.
+    pub fn get(&self) -> &V {
+        self.value.get()
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.value.get_mut()
+    }
+
+    /// Removes the entry from the map.
+    pub fn remove(self) {
+        let _ = self.value.take_entry();
+    }
+
+    /// Takes the entry out of the map and returns its key and value.
+    pub fn take(self) -> (K, V) {
+        self.value.take_entry()
+    }
+}
+
+impl<'a, K, V> RemovableEntry<K, V> for MapEntry<'a, K, V> {
+    fn remove(self) {
+        MapEntry::remove(self)
+    }
+
+    fn key(&self) -> &K {
+        MapEntry::key(self)
+    }
+
+    fn get(&self) -> &V {
+        MapEntry::get(self)
+    }
+
+    fn get_mut(&mut self) -> &mut V {
+        MapEntry::get_mut(self)
+    }
+}
+
+impl<'a, K, V> TakeableEntry<K, V> for MapEntry<'a, K, V> {
+    fn take(self) -> (K, V) {
+        MapEntry::take(self)
+    }
+
+    fn key(&self) -> &K {
+        MapEntry::key(self)
+    }
+
+    fn get(&self) -> &V {
+        MapEntry::get(self)
+    }
+
+    fn get_mut(&mut self) -> &mut V {
+        MapEntry::get_mut(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::prelude::{InplaceMap, RemovableEntry, TakeableEntry};
+
+    #[test]
+    fn test_take_values() {
+        let mut map = (0..10).map(|k| (k, k * 10)).collect::<HashMap<_, _>>();
+        let mut taken = Vec::new();
+        for item in map.takeable_values_iter() {
+            if *item.key() % 2 == 0 {
+                taken.push(item.take());
+            }
+        }
+        taken.sort();
+        assert_eq!(taken, vec![0, 20, 40, 60, 80]);
+        assert_eq!(map.len(), 5);
+        assert!(map.keys().all(|k| k % 2 == 1));
+    }
+
+    #[test]
+    fn test_take_entries_and_modify() {
+        let mut map = HashMap::from([("a", 1), ("b", 2), ("c", 3)]);
+        let mut taken = Vec::new();
+        for mut item in map.takeable_values_iter() {
+            if *item.get() > 2 {
+                taken.push(item.take_entry());
+            } else {
+                *item.get_mut() += 10;
+            }
+        }
+        assert_eq!(taken, vec![("c", 3)]);
+        assert_eq!(map, HashMap::from([("a", 11), ("b", 12)]));
+    }
+
+    #[test]
+    fn test_early_drop_keeps_rest() {
+        let mut map = (0..10).map(|k| (k, k)).collect::<HashMap<_, _>>();
+        let mut iter = map.takeable_values_iter();
+        iter.next().unwrap().take();
+        iter.next();
+        drop(iter);
+        assert_eq!(map.len(), 9);
+    }
+
+    fn drain_entries<E: TakeableEntry<u32, u32>>(items: impl Iterator<Item = E>) -> Vec<(u32, u32)> {
+        items.map(TakeableEntry::take).collect()
+    }
+
+    #[test]
+    fn test_entry_traits() {
+        let mut map = HashMap::from([(1, 10), (2, 20)]);
+        for mut item in map.takeable_values_iter() {
+            *RemovableEntry::get_mut(&mut item) += *RemovableEntry::key(&item);
+        }
+        let mut entries = drain_entries(map.takeable_values_iter());
+        entries.sort();
+        assert_eq!(entries, vec![(1, 11), (2, 22)]);
+        assert!(map.is_empty());
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[test]
+    #[should_panic]
+    fn test_rotten_value() {
+        let mut map = HashMap::from([(1, 1), (2, 2)]);
+        let mut iter = map.takeable_values_iter();
+        let item = iter.next().unwrap();
+        iter.next();
+        item.take();
+    }
+
+    #[test]
+    fn test_entries_remove_take_and_modify() {
+        let mut sessions = HashMap::from([(1, 0u32), (2, 5), (3, 9), (4, 0)]);
+        let mut expired = Vec::new();
+        for mut entry in sessions.entries_iter() {
+            if *entry.key() == 1 {
+                entry.remove();
+            } else if *entry.get() > 8 {
+                expired.push(entry.take());
+            } else {
+                *entry.get_mut() += 1;
+            }
+        }
+        assert_eq!(expired, vec![(3, 9)]);
+        assert_eq!(sessions, HashMap::from([(2, 6), (4, 1)]));
+        assert_eq!(drain_entries(sessions.entries_iter()).len(), 2);
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn test_reference_held_across_next() {
+        let mut map = HashMap::from([(1, "one".to_string())]);
+        let mut iter = map.takeable_values_iter();
+        let item = iter.next().unwrap();
+        let value = item.get();
+        assert!(iter.next().is_none());
+        drop(iter);
+        // the item still holds its entry, and inserts it back when dropped
+        assert_eq!(value, "one");
+        drop(item);
+        assert_eq!(map, HashMap::from([(1, "one".to_string())]));
+    }
+
+    #[test]
+    fn test_entry_kept_over_next() {
+        let mut map = HashMap::from([(1, 10), (2, 20)]);
+        let mut iter = map.entries_iter();
+        let first = iter.next().unwrap();
+        let (key, value) = (first.key(), first.get());
+        let second = iter.next().unwrap();
+        assert_eq!(*key * 10, *value);
+        second.remove();
+        drop(iter);
+        drop(first);
+        assert_eq!(map.len(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+//! In-place iteration over `HashSet` elements.
+//!
+//! Like the iteration over `HashMap` entries, the elements are moved out of the set when the
+//! iteration starts. Each yielded item owns its element and inserts it back into the set when
+//! it is dropped, unless it was taken, and the elements not yet visited are inserted back when
+//! the iterator is dropped. Taking an element costs nothing extra, keeping an element hashes it
+//! once more.
+//!
+//! Leaking the iterator (e.g. with `mem::forget`) loses the elements not yet visited, and
+//! leaking an item loses its element.
+
+use std::collections::hash_set;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use crate::removable_iterator::RemovableItem;
+use crate::takeable_iterator::TakeableItem;
+
+/// An iterator over the elements of a `HashSet`, which allows taking them out of the set.
+pub struct TakeableHashSetIter<'a, T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    /// This tells the borrow checker that the set is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut HashSet<T, S>>,
+    /// The set, it holds the elements outside the iteration. The items insert the kept ones.
+    set: *mut HashSet<T, S>,
+    /// The elements not yet visited.
+    elements: hash_set::IntoIter<T>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T, S> TakeableHashSetIter<'a, T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn new(set: &'a mut HashSet<T, S>) -> Self
+    where
+        S: Clone,
+    {
+        let empty = HashSet::with_hasher(set.hasher().clone());
+        let elements = std::mem::replace(set, empty).into_iter();
+        set.reserve(elements.len());
+        Self {
+            _lifetime_guard: PhantomData,
+            set,
+            elements,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+/// Inserts an element kept by an item back into the `HashSet` behind `set`.
+unsafe fn reinsert<T: Eq + Hash, S: BuildHasher>(set: *mut (), value: T) {
+    unsafe { (*set.cast::<HashSet<T, S>>()).insert(value) };
+}
+
+impl<'a, T, S> Drop for TakeableHashSetIter<'a, T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        unsafe { (*self.set).extend(&mut self.elements) };
+    }
+}
+
+impl<'a, T, S> Iterator for TakeableHashSetIter<'a, T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = TakeableSetItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let value = self.elements.next()?;
+        Some(TakeableSetItem::new(self.set.cast(), reinsert::<T, S>, value, #[cfg(feature = "loop-lifetime-guard")] self.epoch.item()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+/// An item of a set iteration, which can take its element out of the set.
+///
+/// The item holds its element, and inserts it back into the set when dropped, unless it was
+/// taken. There is no mutable access, a changed element would no longer match its hash.
+pub struct TakeableSetItem<'a, T> {
+    /// This ties the item to the borrow of the set by the iterator.
+    _lifetime_guard: PhantomData<&'a mut ()>,
+    /// A raw pointer to the set, for inserting the kept element back.
+    set: *mut (),
+    /// Inserts a kept element into the set behind `set`.
+    reinsert: unsafe fn(*mut (), T),
+    /// The element, `None` once taken.
+    value: Option<T>,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> TakeableSetItem<'a, T> {
+    pub(crate) fn new(set: *mut (), reinsert: unsafe fn(*mut (), T), value: T, #[cfg(feature = "loop-lifetime-guard")] rotten: ItemEpoch) -> Self {
+        Self {
+            _lifetime_guard: PhantomData,
+            set,
+            reinsert,
+            value: Some(value),
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    /// Returns a reference to the element.
+    pub fn get(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.value.as_ref().unwrap()
+    }
+
+    /// Takes the element out of the set.
+    pub fn take(mut self) -> T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.value.take().unwrap()
+    }
+
+    /// Removes the element from the set.
+    pub fn remove(self) {
+        let _ = self.take();
+    }
+}
+
+impl<'a, T> Drop for TakeableSetItem<'a, T> {
+    fn drop(&mut self) {
+        // the set outlives the item, its borrow is held for 'a
+        if let Some(value) = self.value.take() {
+            unsafe { (self.reinsert)(self.set, value) };
+        }
+    }
+}
+
+impl<'a, T> TakeableItem<T> for TakeableSetItem<'a, T> {
+    fn take(self) -> T {
+        TakeableSetItem::take(self)
+    }
+
+    fn get(&self) -> &T {
+        TakeableSetItem::get(self)
+    }
+}
+
+impl<'a, T> RemovableItem<T> for TakeableSetItem<'a, T> {
+    fn remove(self) {
+        TakeableSetItem::remove(self)
+    }
+
+    fn get(&self) -> &T {
+        TakeableSetItem::get(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::prelude::InplaceSet;
+
+    #[test]
+    fn test_migrate_elements() {
+        let mut active = (0..10).collect::<HashSet<u32>>();
+        let mut archived = HashSet::new();
+        for item in active.takeable_iter() {
+            if *item.get() >= 7 {
+                archived.insert(item.take());
+            } else if *item.get() == 0 {
+                item.remove();
+            }
+        }
+        assert_eq!(active, (1..7).collect());
+        assert_eq!(archived, HashSet::from([7, 8, 9]));
+    }
+
+    #[test]
+    fn test_early_drop_keeps_rest() {
+        let mut set = (0..10).map(|x| x.to_string()).collect::<HashSet<_>>();
+        let mut iter = set.takeable_iter();
+        let taken = iter.next().unwrap().take();
+        iter.next();
+        drop(iter);
+        assert_eq!(set.len(), 9);
+        assert!(!set.contains(&taken));
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[test]
+    #[should_panic]
+    fn test_rotten_element() {
+        let mut set = HashSet::from([1, 2]);
+        let mut iter = set.takeable_iter();
+        let item = iter.next().unwrap();
+        iter.next();
+        item.take();
+    }
+
+    #[test]
+    fn test_reference_held_across_next() {
+        let mut set = HashSet::from(["one".to_string()]);
+        let mut iter = set.takeable_iter();
+        let item = iter.next().unwrap();
+        let value = item.get();
+        assert!(iter.next().is_none());
+        drop(iter);
+        // the item still holds its element, and inserts it back when dropped
+        assert_eq!(value, "one");
+        drop(item);
+        assert_eq!(set, HashSet::from(["one".to_string()]));
+    }
+}
@@ -0,0 +1,271 @@
+//! A vector with a key index, kept consistent through the swap removals.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+use crate::inplace_vec_iterator::{InplaceVecItem, InplaceVecIterator};
+use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
+
+/// A vector of keyed elements with a map from the keys to the positions, so an element is
+/// found and removed by its key in O(1).
+///
+/// The map is updated by every swap removal, including the ones done through the items of
+/// [`removable_iter`](IndexedInplaceVec::removable_iter), so there is never a rescan to rebuild
+/// it.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::indexed_vec::IndexedInplaceVec;
+/// use inplace_iter::prelude::RemovableItem;
+///
+/// let mut sessions = IndexedInplaceVec::new();
+/// sessions.insert("alice", 30);
+/// sessions.insert("bob", 5);
+/// sessions.insert("carol", 12);
+/// assert_eq!(sessions.remove_by_key(&"alice"), Some(30));
+///
+/// for item in sessions.removable_iter() {
+///     if *item.get() < 10 {
+///         item.remove();
+///     }
+/// }
+/// assert_eq!(sessions.get(&"carol"), Some(&12));
+/// assert_eq!(sessions.get(&"bob"), None);
+/// assert_eq!(sessions.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedInplaceVec<K, T> {
+    /// The elements with their keys.
+    entries: Vec<(K, T)>,
+    /// The position of every key in `entries`.
+    positions: HashMap<K, usize>,
+}
+
+impl<K, T> Default for IndexedInplaceVec<K, T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T> IndexedInplaceVec<K, T> {
+    /// Creates an empty vector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `value` under `key`, returns the value it replaces.
+    pub fn insert(&mut self, key: K, value: T) -> Option<T> {
+        if let Some(&position) = self.positions.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[position].1, value));
+        }
+        self.positions.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Returns the element of `key`.
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.positions.get(key).map(|&position| &self.entries[position].1)
+    }
+
+    /// Returns the element of `key`.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.positions.get(key).map(|&position| &mut self.entries[position].1)
+    }
+
+    /// Returns `true` if there is an element under `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Removes the element of `key` and returns it.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the last element takes the place of the removed one
+    pub fn remove_by_key(&mut self, key: &K) -> Option<T> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.swap_remove(position);
+        if let Some((moved, _)) = self.entries.get(position) {
+            self.positions.insert(moved.clone(), position);
+        }
+        Some(value)
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the keys and the elements, in the order of the vector.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Returns a swap removing pass, whose items update the key index when removed or taken.
+    pub fn removable_iter(&mut self) -> IndexedIter<'_, K, T> {
+        IndexedIter {
+            positions: &mut self.positions,
+            inner: InplaceVecIterator::new(&mut self.entries),
+        }
+    }
+}
+
+/// The swap removing pass of an [`IndexedInplaceVec`].
+pub struct IndexedIter<'a, K, T> {
+    /// The key index, updated by the items.
+    positions: *mut HashMap<K, usize>,
+    /// The pass over the entries.
+    inner: InplaceVecIterator<'a, (K, T)>,
+}
+
+impl<'a, K, T> Iterator for IndexedIter<'a, K, T> {
+    type Item = IndexedItem<'a, K, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.inner.next()?;
+        Some(IndexedItem { positions: self.positions, inner })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, T> FusedIterator for IndexedIter<'a, K, T> {}
+
+/// An item of the pass of an [`IndexedInplaceVec`].
+pub struct IndexedItem<'a, K, T> {
+    /// The key index of the vector.
+    positions: *mut HashMap<K, usize>,
+    /// The item of the entry.
+    inner: InplaceVecItem<'a, (K, T)>,
+}
+
+impl<'a, K: Hash + Eq + Clone, T> IndexedItem<'a, K, T> {
+    /// Returns the key of the element.
+    pub fn key(&self) -> &K {
+        &self.inner.get_value().0
+    }
+
+    /// Takes the element with its key, and moves the last element of the index into its place.
+    pub fn take_entry(self) -> (K, T) {
+        let index = self.inner.index();
+        let vector = self.inner.vector();
+        let entry = self.inner.take_value();
+        unsafe {
+            // the pass holds the only borrow of the vector and of the index
+            let positions = &mut *self.positions;
+            positions.remove(&entry.0);
+            if let Some((moved, _)) = (&*vector).get(index) {
+                positions.insert(moved.clone(), index);
+            }
+        }
+        entry
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, T> RemovableItem<T> for IndexedItem<'a, K, T> {
+    fn remove(self) {
+        drop(self.take_entry());
+    }
+
+    fn get(&self) -> &T {
+        &self.inner.get_value().1
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, T> RemovableItemMut<T> for IndexedItem<'a, K, T> {
+    fn remove(self) {
+        drop(self.take_entry());
+    }
+
+    fn get(&self) -> &T {
+        &self.inner.get_value().1
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        &mut self.inner.get_value_mut().1
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, T> TakeableItem<T> for IndexedItem<'a, K, T> {
+    fn take(self) -> T {
+        self.take_entry().1
+    }
+
+    fn get(&self) -> &T {
+        &self.inner.get_value().1
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, T> TakeableItemMut<T> for IndexedItem<'a, K, T> {
+    fn take(self) -> T {
+        self.take_entry().1
+    }
+
+    fn get(&self) -> &T {
+        &self.inner.get_value().1
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        &mut self.inner.get_value_mut().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::RemovableItemMut;
+    use super::IndexedInplaceVec;
+
+    /// Asserts that every key maps to the position of its element.
+    fn assert_consistent(v: &IndexedInplaceVec<u32, u32>) {
+        assert_eq!(v.positions.len(), v.entries.len());
+        for (position, (key, _)) in v.entries.iter().enumerate() {
+            assert_eq!(v.positions[key], position);
+        }
+    }
+
+    #[test]
+    fn test_index_follows_swap_removals() {
+        let mut v = IndexedInplaceVec::new();
+        for key in 0..10 {
+            assert_eq!(v.insert(key, key * 10), None);
+        }
+        assert_eq!(v.insert(3, 31), Some(30));
+        assert_eq!(v.remove_by_key(&0), Some(0));
+        assert_eq!(v.remove_by_key(&0), None);
+        assert_consistent(&v);
+
+        let mut taken = Vec::new();
+        for mut item in v.removable_iter() {
+            if item.key() % 3 == 0 {
+                taken.push(item.take_entry());
+            } else if *item.get() > 60 {
+                item.remove();
+            } else {
+                *item.get_mut() += 1;
+            }
+        }
+        taken.sort();
+        assert_eq!(taken, vec![(3, 31), (6, 60), (9, 90)]);
+        assert_consistent(&v);
+        let mut rest = v.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        rest.sort();
+        assert_eq!(rest, vec![(1, 11), (2, 21), (4, 41), (5, 51)]);
+
+        let values = v.removable_iter().map(crate::takeable_iterator::TakeableItem::take).collect::<Vec<_>>();
+        assert_eq!(values.len(), 4);
+        assert!(v.is_empty() && v.positions.is_empty());
+    }
+}
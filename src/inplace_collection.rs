@@ -0,0 +1,202 @@
+//! A container agnostic view of the in-place iteration, for code generic over the containers.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use crate::hash_map_iterator::{TakeableHashMapValues, TakeableMapValue};
+use crate::inplace_deque_iterator::{InplaceDequeIterator, InplaceDequeItem};
+use crate::inplace_vec_iterator::{InplaceVecItem, InplaceVecIterator};
+use crate::predicate::{CauseHistogram, Predicate};
+use crate::takeable_iterator::TakeableItemMut;
+
+/// A trait for the containers supporting in-place iteration, so generic code can be written
+/// once for all of them.
+///
+/// The items give mutable access to the elements and take them out of the container, which
+/// covers removal too. For a map, the elements are its values.
+///
+/// # Implementations
+///
+/// - `Vec<T>`, the taken element is replaced by the last one
+/// - `VecDeque<T>`, the taken element is replaced by the one of the nearer end
+/// - `HashMap<K, V, S>`, taking a value removes its entry
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::{HashMap, VecDeque};
+/// use inplace_iter::prelude::*;
+///
+/// fn expire<C: InplaceCollection<Element = u32>>(c: &mut C, now: u32) -> usize {
+///     c.remove_items_where(|deadline| *deadline < now)
+/// }
+///
+/// let mut list = vec![5, 10, 15];
+/// let mut queue = VecDeque::from([5, 10, 15]);
+/// let mut map = HashMap::from([("a", 5), ("b", 10), ("c", 15)]);
+/// assert_eq!(expire(&mut list, 11), 2);
+/// assert_eq!(expire(&mut queue, 11), 2);
+/// assert_eq!(expire(&mut map, 11), 2);
+/// assert_eq!(map, HashMap::from([("c", 15)]));
+/// ```
+pub trait InplaceCollection {
+    /// The type of the elements yielded by the items.
+    type Element;
+    /// The type of the items, which may borrow the collection.
+    type Item<'a>: TakeableItemMut<Self::Element>
+    where
+        Self: 'a;
+    /// The type of the iterator.
+    type Iter<'a>: Iterator<Item = Self::Item<'a>>
+    where
+        Self: 'a;
+
+    /// Returns an iterator whose items can change or take the elements.
+    fn inplace_items(&mut self) -> Self::Iter<'_>;
+
+    /// Removes the elements for which `pred` returns `true`, returns the number of removed
+    /// elements.
+    fn remove_items_where<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&Self::Element) -> bool,
+    {
+        let mut removed = 0;
+        for item in self.inplace_items() {
+            if pred(item.get()) {
+                drop(item.take());
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Removes the elements matching `pred`, returns the numbers of removals per cause, i.e.
+    /// per label of the matching clause.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut ttls = vec![0, 5, 0, 120, 7200];
+    /// let mut policy = (|t: &u32| *t == 0).labeled("expired").or((|t: &u32| *t > 3600).labeled("too long"));
+    /// let causes = ttls.remove_items_matching(&mut policy);
+    /// assert_eq!((causes.get("expired"), causes.get("too long"), causes.total()), (2, 1, 3));
+    /// ```
+    fn remove_items_matching<P>(&mut self, pred: &mut P) -> CauseHistogram
+    where
+        P: Predicate<Self::Element>,
+    {
+        let mut causes = CauseHistogram::default();
+        for item in self.inplace_items() {
+            let outcome = pred.evaluate(item.get());
+            if outcome.matched {
+                drop(item.take());
+                causes.record(outcome.cause);
+            }
+        }
+        causes
+    }
+
+    /// Takes the elements for which `pred` returns `true` out of the container.
+    fn take_items_where<F>(&mut self, mut pred: F) -> Vec<Self::Element>
+    where
+        F: FnMut(&Self::Element) -> bool,
+    {
+        self.inplace_items().filter(|item| pred(item.get())).map(|item| item.take()).collect()
+    }
+}
+
+impl<T> InplaceCollection for Vec<T> {
+    type Element = T;
+    type Item<'a>
+        = InplaceVecItem<'a, T>
+    where
+        T: 'a;
+    type Iter<'a>
+        = InplaceVecIterator<'a, T>
+    where
+        T: 'a;
+
+    fn inplace_items(&mut self) -> Self::Iter<'_> {
+        InplaceVecIterator::new(self)
+    }
+}
+
+impl<T> InplaceCollection for VecDeque<T> {
+    type Element = T;
+    type Item<'a>
+        = InplaceDequeItem<'a, T>
+    where
+        T: 'a;
+    type Iter<'a>
+        = InplaceDequeIterator<'a, T>
+    where
+        T: 'a;
+
+    fn inplace_items(&mut self) -> Self::Iter<'_> {
+        InplaceDequeIterator::new(self)
+    }
+}
+
+impl<K, V, S> InplaceCollection for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    type Element = V;
+    type Item<'a>
+        = TakeableMapValue<'a, K, V>
+    where
+        K: 'a,
+        V: 'a,
+        S: 'a;
+    type Iter<'a>
+        = TakeableHashMapValues<'a, K, V, S>
+    where
+        K: 'a,
+        V: 'a,
+        S: 'a;
+
+    fn inplace_items(&mut self) -> Self::Iter<'_> {
+        TakeableHashMapValues::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+    use crate::prelude::{InplaceCollection, TakeableItemMut};
+
+    /// Doubles the kept elements and takes the others, for any container.
+    fn double_or_take<C: InplaceCollection<Element = i32>>(c: &mut C) -> Vec<i32> {
+        let mut taken = Vec::new();
+        for mut item in c.inplace_items() {
+            if *item.get() > 2 {
+                taken.push(item.take());
+            } else {
+                *item.get_mut() *= 2;
+            }
+        }
+        taken.sort();
+        taken
+    }
+
+    #[test]
+    fn test_generic_over_containers() {
+        let mut v = vec![1, 2, 3, 4];
+        assert_eq!(double_or_take(&mut v), vec![3, 4]);
+        assert_eq!(v, vec![2, 4]);
+
+        let mut d = VecDeque::from([4, 1, 3, 2]);
+        assert_eq!(double_or_take(&mut d), vec![3, 4]);
+        let mut d = Vec::from(d);
+        d.sort();
+        assert_eq!(d, vec![2, 4]);
+
+        let mut m = HashMap::from([('a', 1), ('b', 3)]);
+        assert_eq!(double_or_take(&mut m), vec![3]);
+        assert_eq!(m, HashMap::from([('a', 2)]));
+        assert_eq!(m.take_items_where(|v| *v == 2), vec![2]);
+        assert!(m.is_empty());
+    }
+}
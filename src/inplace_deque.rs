@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use crate::inplace_deque_iterator::InplaceDequeIterator;
+use crate::inplace_iterator::InplaceIterator;
+use crate::removable_confirm_iterator_deque::InplaceRemovableConfirmDequeIterator;
+use crate::removable_confirm_iterator_vec::{EagerRemovableItem, RemovableConfirmIterator};
+use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
+
+/// A trait that extends double-ended queues with methods for in-place iteration with removal
+/// operations.
+///
+/// # Implementations
+///
+/// - `VecDeque<T>`: Standard library's ring buffer implementation
+///
+/// # Ordering
+///
+/// Removing an element swaps it with the nearer end of the deque, so the ring buffer is never
+/// shifted. In the front half, the first element takes the place of the removed one; it was
+/// already visited and is not visited again. In the back half, the last element takes its
+/// place and is visited next. Every element is visited exactly once.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use inplace_iter::prelude::*;
+///
+/// let mut queue = VecDeque::from([1, 2, 3, 4, 5, 6]);
+/// for item in queue.removable_iter() {
+///     if *item.get() % 2 == 0 {
+///         item.remove();
+///     }
+/// }
+/// assert_eq!(queue, VecDeque::from([1, 3, 5]));
+/// ```
+pub trait InplaceDeque<T> {
+    /// Returns an iterator that allows removing elements during iteration.
+    ///
+    /// The iterator yields items that implement `RemovableItem<T>`, which provides
+    /// a `remove()` method to remove the current element.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    fn removable_iter(&mut self) -> impl InplaceIterator<T, Item = impl RemovableItem<T>>;
+
+    /// Returns an iterator that allows removing elements during iteration.
+    ///
+    /// The iterator yields items that implement `RemovableItemMut<T>`, which provides
+    /// a `remove()` method to remove the current element.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    fn removable_iter_mut(&mut self) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T>>;
+
+    /// Returns an iterator that allows taking ownership of elements during iteration.
+    ///
+    /// The iterator yields items that implement `TakeableItem<T>`, which provides
+    /// a `take()` method to remove and return the current element.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The order of elements is not preserved when taking elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut jobs = VecDeque::from([("a", 3), ("b", 0), ("c", 1), ("d", 0)]);
+    /// let done = jobs.takeable_iter().filter(|job| job.get().1 == 0).map(|job| job.take()).collect::<Vec<_>>();
+    /// assert_eq!(done, vec![("b", 0), ("d", 0)]);
+    /// assert_eq!(jobs, VecDeque::from([("a", 3), ("c", 1)]));
+    /// ```
+    fn takeable_iter(&mut self) -> impl InplaceIterator<T, Item = impl TakeableItem<T>>;
+
+    /// Returns an iterator that allows taking ownership of elements during iteration.
+    ///
+    /// See [`InplaceDeque::takeable_iter`].
+    fn takeable_iter_mut(&mut self) -> impl InplaceIterator<T, Item = impl TakeableItemMut<T>>;
+
+    /// Returns a wrapper around iterator that allows removing elements during iteration.
+    /// The removals are not yet applied.
+    /// The wrapper is then used to confirm or cancel the removals.
+    ///
+    /// Marking an element swaps it with the last element still in the session, so the marked
+    /// elements gather at the back of the deque.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut queue = VecDeque::from([1, 2, 3, 4]);
+    /// let mut confirm = queue.removable_confirm_iter();
+    /// for item in confirm.iter() {
+    ///     if *item.get() % 2 == 0 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// confirm.confirm_removals();
+    /// assert_eq!(queue, VecDeque::from([1, 3]));
+    /// ```
+    fn removable_confirm_iter(&mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>>;
+
+    /// Returns a wrapper around iterator that allows removing and modifying elements during
+    /// iteration. The removals are not yet applied.
+    ///
+    /// See [`InplaceDeque::removable_confirm_iter`].
+    fn removable_confirm_iter_mut(&mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>>;
+}
+
+impl<T> InplaceDeque<T> for VecDeque<T> {
+    fn removable_iter(&mut self) -> impl InplaceIterator<T, Item = impl RemovableItem<T>> {
+        InplaceDequeIterator::new(self)
+    }
+
+    fn removable_iter_mut(&mut self) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T>> {
+        InplaceDequeIterator::new(self)
+    }
+
+    fn takeable_iter(&mut self) -> impl InplaceIterator<T, Item = impl TakeableItem<T>> {
+        InplaceDequeIterator::new(self)
+    }
+
+    fn takeable_iter_mut(&mut self) -> impl InplaceIterator<T, Item = impl TakeableItemMut<T>> {
+        InplaceDequeIterator::new(self)
+    }
+
+    fn removable_confirm_iter(&mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>> {
+        InplaceRemovableConfirmDequeIterator::new(self)
+    }
+
+    fn removable_confirm_iter_mut(&mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>> {
+        InplaceRemovableConfirmDequeIterator::new(self)
+    }
+}
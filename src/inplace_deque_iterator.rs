@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use crate::inplace_iterator::InplaceIterator;
+use crate::prelude::{RemovableItem, TakeableItem};
+use crate::removable_iterator::RemovableItemMut;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// An iterator which allows you to remove or take items from the underlying deque.
+///
+/// Removing swaps the current element with the nearer end of the deque, so the ring buffer is
+/// never shifted:
+///
+/// - In the front half, the first element is moved into the current position. It was already
+///   visited, so it is not visited again.
+/// - In the back half, the last element is moved into the current position and is visited next.
+///
+/// Either way, every element is visited exactly once, and the elements not yet visited keep
+/// their relative order unless they are moved from the back.
+pub struct InplaceDequeIterator<'a, T> {
+    /// This tells the borrow checker that the underlying deque is borrowed and cannot be used otherwise.
+    _lifetime_guard: &'a mut VecDeque<T>,
+    /// A raw pointer to the deque for unsafe access.
+    data: *mut VecDeque<T>,
+    /// A flag indicating whether an item has been removed.
+    removed: bool,
+    /// The current index in the deque, or None if iteration hasn't started.
+    index: Option<usize>,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+#[cfg(feature = "loop-lifetime-guard")]
+impl<'a, T> Drop for InplaceDequeIterator<'a, T> {
+    fn drop(&mut self) {
+        self.rotten_item();
+    }
+}
+
+impl<'a, T> InplaceDequeIterator<'a, T> {
+    pub fn new(v: &'a mut VecDeque<T>) -> Self {
+        let data = v as *mut VecDeque<T>;
+        Self {
+            _lifetime_guard: v,
+            data,
+            removed: false,
+            index: None,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+
+    /// The index of the element the next call to `next()` yields.
+    fn next_index(&self) -> usize {
+        match self.index {
+            // after a removal, the next element to visit is moved to the current index
+            Some(index) if self.removed => index,
+            Some(index) => index + 1,
+            None => 0,
+        }
+    }
+}
+
+impl<'a, T> Iterator for InplaceDequeIterator<'a, T> {
+    type Item = InplaceDequeItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let len = unsafe { (*self.data).len() };
+        let index = self.next_index();
+        self.removed = false;
+        self.index = Some(index);
+        if index < len {
+            #[cfg(feature = "loop-lifetime-guard")]
+            let rotten = self.epoch.item();
+            Some(InplaceDequeItem {
+                _lifetime_guard: PhantomData,
+                data: self.data,
+                index,
+                removed: &mut self.removed,
+                #[cfg(feature = "loop-lifetime-guard")]
+                rotten,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = unsafe { (*self.data).len() };
+        let remaining = len.saturating_sub(self.next_index());
+        (remaining, Some(remaining))
+    }
+
+    fn count(self) -> usize {
+        self.size_hint().0
+    }
+}
+
+impl<'a, T> FusedIterator for InplaceDequeIterator<'a, T> {}
+
+impl<'a, T> InplaceIterator<T> for InplaceDequeIterator<'a, T> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        let v = unsafe { &(*self.data) };
+        v.range(self.next_index().min(v.len())..)
+    }
+}
+
+/// A struct representing an item that can be removed or taken from the underlying deque.
+///
+/// The item borrows the deque, so it cannot be kept after the deque is changed or dropped:
+///
+/// ```compile_fail
+/// use std::collections::VecDeque;
+/// use inplace_iter::inplace_deque_iterator::InplaceDequeIterator;
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = VecDeque::from([1, 2, 3]);
+/// let mut kept = None;
+/// for item in InplaceDequeIterator::new(&mut numbers) {
+///     kept = Some(item);
+/// }
+/// drop(numbers);
+/// RemovableItem::get(&kept.unwrap());
+/// ```
+pub struct InplaceDequeItem<'a, T> {
+    /// This ties the item to the borrow of the deque by the iterator.
+    _lifetime_guard: PhantomData<&'a mut VecDeque<T>>,
+    /// A raw pointer to the deque containing the item.
+    data: *mut VecDeque<T>,
+    /// The index of the item within the deque.
+    index: usize,
+    /// An indicator to the deque that we have removed the item
+    removed: *mut bool,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> InplaceDequeItem<'a, T> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    pub(crate) fn take_value(self) -> T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            *self.removed = true;
+            let v = &mut (*self.data);
+            if self.index < v.len() - 1 - self.index {
+                v.swap_remove_front(self.index).unwrap()
+            } else {
+                v.swap_remove_back(self.index).unwrap()
+            }
+        }
+    }
+
+    pub(crate) fn get_value(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            let v = &(*self.data);
+            &v[self.index]
+        }
+    }
+
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            let v = &mut (*self.data);
+            &mut v[self.index]
+        }
+    }
+}
+
+impl<'a, T> RemovableItem<T> for InplaceDequeItem<'a, T> {
+    /// Remove the current item from the underlying deque.
+    /// The first or the last item, whichever is nearer, is moved to this current place.
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> RemovableItemMut<T> for InplaceDequeItem<'a, T> {
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+impl<'a, T> TakeableItem<T> for InplaceDequeItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> TakeableItemMut<T> for InplaceDequeItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use crate::prelude::{InplaceDeque, InplaceIterator, RemovableItem, RemovableItemMut};
+
+    #[test]
+    fn test_deque_remove_front_and_back_half() {
+        let mut a = VecDeque::from([1, 2, 3, 4, 5, 6]);
+        let mut visited = Vec::new();
+        for item in a.removable_iter() {
+            visited.push(*item.get());
+            if *item.get() == 2 || *item.get() == 5 {
+                item.remove();
+            }
+        }
+        // 2 is in the front half, swapped with the front, 5 in the back half, swapped with the back
+        assert_eq!(visited, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(a, VecDeque::from([1, 3, 4, 6]));
+    }
+
+    #[test]
+    fn test_deque_remove_all() {
+        let mut a = (0..9).collect::<VecDeque<_>>();
+        let mut visited = 0;
+        for item in a.removable_iter() {
+            visited += 1;
+            item.remove();
+        }
+        assert_eq!(visited, 9);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_deque_wrapped_ring_buffer() {
+        let mut a = VecDeque::with_capacity(4);
+        a.extend([0, 0, 1, 2]);
+        a.pop_front();
+        a.pop_front();
+        a.extend([3, 4]);
+        let mut iter = a.removable_iter_mut();
+        assert_eq!(iter.lookahead().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        for mut item in iter.by_ref() {
+            if *item.get() % 2 == 0 {
+                item.remove();
+            } else {
+                *item.get_mut() *= 10;
+            }
+        }
+        drop(iter);
+        assert_eq!(a, VecDeque::from([10, 30]));
+    }
+}
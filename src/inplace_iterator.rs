@@ -0,0 +1,165 @@
+//! Iterator level operations shared by the in-place iterators.
+
+use crate::cancellation::{Cancellable, Cancellation};
+use crate::progress::Progress;
+
+/// A trait for the iterators created by `InplaceVector`.
+///
+/// Only one yielded item is valid at a time: calling `next()` invalidates the previously
+/// yielded item (with the `loop-lifetime-guard` feature it panics on use). This shapes how the
+/// standard adapters behave:
+///
+/// - `enumerate`, `filter`, `map`, `take`, `skip` and `by_ref` work as expected.
+/// - `peekable` calls `next()` to peek, which invalidates the item in use. Use
+///   [`InplaceIterator::peek`] instead, which does not advance the iterator.
+/// - `collect::<Vec<_>>()` of the items invalidates all of them, as collecting calls `next()`
+///   until the iterator is exhausted. Collect the values instead.
+/// - `count()` returns the number of items left, after the pending removal of the current
+///   item, without yielding them. It is O(1) for the vector and deque passes.
+/// - `for_each` and `fold` yield one item at a time, the removals done by the closure apply
+///   before it gets the next item.
+/// - `last()` ends the pass, so the item it returns is invalid like the collected ones. Read
+///   the last element with `lookahead().last()` instead.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3];
+/// let mut iter = numbers.removable_iter();
+/// let item = iter.next().unwrap();
+/// assert_eq!(iter.peek(), Some(&2));
+/// // peeking does not invalidate the current item
+/// item.remove();
+/// // the last element was swapped into the place of the removed one
+/// assert_eq!(iter.peek(), Some(&3));
+/// assert_eq!(iter.next().unwrap().get(), &3);
+/// drop(iter);
+/// assert_eq!(numbers, vec![3, 2]);
+/// ```
+pub trait InplaceIterator<T>: Iterator {
+    /// Returns a reference to the element which will be yielded by the next call to `next()`.
+    ///
+    /// Unlike `Peekable::peek`, this does not advance the iterator, so the item in use stays
+    /// valid. If the current item is removed, this returns the element which was swapped into
+    /// its place.
+    fn peek(&self) -> Option<&T> {
+        self.lookahead().next()
+    }
+
+    /// Returns a read-only iterator over the elements which are not yet visited, starting with
+    /// the one [`InplaceIterator::peek`] returns.
+    ///
+    /// The returned iterator is a cheap snapshot of the current position: it can be cloned to
+    /// scan the remaining elements several times, and it does not invalidate the item in use.
+    /// It cannot remove anything, the removal is still done through the current item.
+    ///
+    /// Called before the first `next()`, it is a read-only pre-scan of the whole pass, without
+    /// items or guard checks and without borrowing the vector again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// // remove the entries superseded by a later entry with a newer version
+    /// let mut entries = vec![("a", 1), ("b", 1), ("a", 2)];
+    /// let mut iter = entries.removable_iter();
+    /// while let Some(item) = iter.next() {
+    ///     let (name, version) = *item.get();
+    ///     if iter.lookahead().any(|(n, v)| *n == name && *v > version) {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// drop(iter);
+    /// assert_eq!(entries, vec![("a", 2), ("b", 1)]);
+    ///
+    /// // a pre-scan deciding the threshold of the destructive phase
+    /// let mut sizes = vec![40, 10, 30, 20];
+    /// let mut iter = sizes.removable_iter();
+    /// let average = iter.lookahead().sum::<u32>() / iter.lookahead().count() as u32;
+    /// for item in iter {
+    ///     if *item.get() < average {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// assert_eq!(sizes, vec![40, 30]);
+    /// ```
+    #[doc(alias = "peek_all")]
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's;
+
+    /// Stops the iteration once `token` is signalled, e.g. on shutdown.
+    ///
+    /// The removals done so far stay applied and the unvisited elements stay in the vector, the
+    /// returned iterator reports how far the pass got.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let shutdown = AtomicBool::new(false);
+    /// let mut jobs = vec![1, 2, 3, 4, 5];
+    /// let mut iter = jobs.ordered_removable_iter().with_cancellation(&shutdown);
+    /// while let Some(item) = iter.next() {
+    ///     if *item.get() == 2 {
+    ///         shutdown.store(true, Ordering::Release);
+    ///     }
+    ///     item.remove();
+    /// }
+    /// assert_eq!(iter.report().visited, 2);
+    /// drop(iter);
+    /// assert_eq!(jobs, vec![3, 4, 5]);
+    /// ```
+    fn with_cancellation<C: Cancellation>(self, token: C) -> Cancellable<Self, C>
+    where
+        Self: Sized,
+    {
+        Cancellable::new(self, token)
+    }
+
+    /// Calls `on_progress(visited, total)` every `every` visited items and once more at the end
+    /// of the pass, e.g. to drive a progress bar or a heartbeat.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut records = (0..1000).collect::<Vec<_>>();
+    /// let mut reports = Vec::new();
+    /// for item in records.removable_iter().with_progress(400, |visited, total| reports.push((visited, total))) {
+    ///     if *item.get() % 2 == 0 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// assert_eq!(reports, vec![(400, 1000), (800, 1000), (1000, 1000)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is 0.
+    fn with_progress<F: FnMut(usize, usize)>(self, every: usize, on_progress: F) -> Progress<Self, F>
+    where
+        Self: Sized,
+    {
+        Progress::new(self, every, on_progress)
+    }
+}
+
+impl<T, I: InplaceIterator<T> + ?Sized> InplaceIterator<T> for &mut I {
+    fn peek(&self) -> Option<&T> {
+        (**self).peek()
+    }
+
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        (**self).lookahead()
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use crate::prelude::InplaceIterator;
+    use crate::prelude::InplaceVector;
+    use crate::prelude::RemovableItem;
+
+    #[test]
+    fn test_peek_does_not_advance() {
+        let mut a = vec![1, 2, 3];
+        let mut iter = a.removable_iter();
+        assert_eq!(iter.peek(), Some(&1));
+        let item = iter.next().unwrap();
+        assert_eq!(iter.peek(), Some(&2));
+        assert_eq!(item.get(), &1);
+        item.remove();
+        assert_eq!(iter.peek(), Some(&3));
+        assert_eq!(iter.next().unwrap().get(), &3);
+        assert_eq!(iter.next().unwrap().get(), &2);
+        assert_eq!(iter.peek(), None);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_lookahead() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut iter = a.removable_iter();
+        assert_eq!(iter.lookahead().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        let item = iter.next().unwrap();
+        let scan = iter.lookahead();
+        assert_eq!(scan.clone().count(), 3);
+        assert_eq!(scan.max(), Some(&4));
+        item.remove();
+        assert_eq!(iter.lookahead().copied().collect::<Vec<_>>(), vec![4, 2, 3]);
+        while iter.next().is_some() {}
+        assert_eq!(iter.lookahead().count(), 0);
+    }
+
+    #[test]
+    fn test_size_hint_with_removals() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let mut iter = a.removable_iter();
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        iter.next().unwrap().remove();
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        iter.next();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        assert_eq!(iter.count(), 3);
+        assert_eq!(a.len(), 4);
+    }
+
+    #[test]
+    fn test_by_ref() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let mut iter = a.removable_iter();
+        for item in iter.by_ref().take(2) {
+            item.remove();
+        }
+        assert_eq!(iter.by_ref().peek(), Some(&4));
+        let rest = iter.map(|item| *item.get()).collect::<Vec<_>>();
+        assert_eq!(rest, vec![4, 2, 3]);
+        assert_eq!(a, vec![4, 2, 3]);
+    }
+
+    #[test]
+    fn test_filter_and_enumerate() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        for (i, item) in a.removable_iter().filter(|item| *item.get() > 2).enumerate() {
+            if i % 2 == 0 {
+                item.remove();
+            }
+        }
+        assert_eq!(a, vec![1, 2, 6, 5]);
+    }
+
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    mod loop_lifetime_guard {
+        use crate::prelude::InplaceVector;
+        use crate::prelude::RemovableItem;
+
+        #[test]
+        #[should_panic]
+        fn test_peekable_invalidates_current_item() {
+            let mut a = vec![1, 2, 3];
+            let mut iter = a.removable_iter().peekable();
+            let item = iter.next().unwrap();
+            iter.peek();
+            item.remove();
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_collect_invalidates_items() {
+            let mut a = vec![1, 2, 3];
+            let items = a.removable_iter().collect::<Vec<_>>();
+            let _ = items[2].get();
+        }
+    }
+}
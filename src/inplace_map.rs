@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::ops::RangeBounds;
+use crate::btree_map_iterator::BTreeMapRangeEntries;
+use crate::hash_map_iterator::{HashMapEntries, MapEntry, TakeableHashMapValues, TakeableMapValue};
+
+/// A trait that extends maps with methods for in-place iteration with take operations.
+///
+/// # Implementations
+///
+/// - `HashMap<K, V, S>`: Standard library's hash map implementation
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use inplace_iter::prelude::*;
+///
+/// let mut stock = HashMap::from([("apple", 3), ("pear", 0), ("plum", 0)]);
+/// let mut sold_out = Vec::new();
+/// for item in stock.takeable_values_iter() {
+///     if *item.get() == 0 {
+///         sold_out.push(item.take_entry().0);
+///     }
+/// }
+/// sold_out.sort();
+/// assert_eq!(sold_out, vec!["pear", "plum"]);
+/// assert_eq!(stock, HashMap::from([("apple", 3)]));
+/// ```
+pub trait InplaceMap<K, V> {
+    /// Returns an iterator that allows taking ownership of the values during iteration.
+    ///
+    /// The iterator yields items that implement `TakeableItem<V>` and `TakeableItemMut<V>`, as
+    /// well as `RemovableEntry<K, V>` and `TakeableEntry<K, V>`. Taking the item removes the
+    /// entry from the map. The items also give access to the key, and `take_entry()` returns
+    /// the key together with the value.
+    ///
+    /// # Performance
+    ///
+    /// - Taking a value is O(1) time complexity
+    /// - The kept entries are inserted back into the map, which hashes their keys once more
+    ///
+    /// The entries are moved out of the map while iterating, so leaking the iterator (e.g.
+    /// with `mem::forget`) loses the entries not yet visited, and leaking an item its entry.
+    fn takeable_values_iter(&mut self) -> impl Iterator<Item = TakeableMapValue<'_, K, V>>;
+
+    /// Returns an iterator over the entries, which allows removing or taking them during
+    /// iteration.
+    ///
+    /// The items give access to the key and the value, `remove()` drops the entry and `take()`
+    /// returns its key and value. They implement `RemovableEntry<K, V>` and
+    /// `TakeableEntry<K, V>`. This drops entries and modifies the kept ones in a single pass,
+    /// without a second lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut retries = HashMap::from([("a", 1), ("b", 3), ("c", 2)]);
+    /// let mut given_up = Vec::new();
+    /// for mut entry in retries.entries_iter() {
+    ///     if *entry.get() == 3 {
+    ///         given_up.push(entry.take());
+    ///     } else {
+    ///         *entry.get_mut() += 1;
+    ///     }
+    /// }
+    /// assert_eq!(given_up, vec![("b", 3)]);
+    /// assert_eq!(retries, HashMap::from([("a", 2), ("c", 3)]));
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// The same as [`takeable_values_iter`](InplaceMap::takeable_values_iter).
+    fn entries_iter(&mut self) -> impl Iterator<Item = MapEntry<'_, K, V>>;
+}
+
+impl<K, V, S> InplaceMap<K, V> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    fn takeable_values_iter(&mut self) -> impl Iterator<Item = TakeableMapValue<'_, K, V>> {
+        TakeableHashMapValues::new(self)
+    }
+
+    fn entries_iter(&mut self) -> impl Iterator<Item = MapEntry<'_, K, V>> {
+        HashMapEntries::new(self)
+    }
+}
+
+/// A trait that extends ordered maps with in-place iteration over a range of keys.
+///
+/// # Implementations
+///
+/// - `BTreeMap<K, V>`: Standard library's ordered map implementation
+pub trait InplaceRangeMap<K, V> {
+    /// Returns an iterator over the entries with keys in `range`, in ascending order, which
+    /// allows removing or taking them during iteration.
+    ///
+    /// The items are the same as of [`InplaceMap::entries_iter`]: `remove()` drops the entry,
+    /// `take()` returns its key and value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// // sessions indexed by their expiry time
+    /// let mut sessions = BTreeMap::from([(10, "a"), (25, "b"), (30, "c"), (70, "d")]);
+    /// let now = 30;
+    /// let mut expired = Vec::new();
+    /// for entry in sessions.removable_range_iter(..=now) {
+    ///     if *entry.get() != "b" {
+    ///         expired.push(entry.take());
+    ///     }
+    /// }
+    /// assert_eq!(expired, vec![(10, "a"), (30, "c")]);
+    /// assert_eq!(sessions, BTreeMap::from([(25, "b"), (70, "d")]));
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - Splitting off the range is O(log n) time complexity
+    /// - Removing an entry is O(1), keeping one is O(log n)
+    /// - The entries after the range are merged back when the iterator is dropped, the smaller
+    ///   part is inserted into the larger one
+    ///
+    /// The range and the entries after it are split off the map while iterating, so leaking the
+    /// iterator (e.g. with `mem::forget`) loses them, and leaking an item its entry.
+    fn removable_range_iter<R: RangeBounds<K>>(&mut self, range: R) -> impl Iterator<Item = MapEntry<'_, K, V>>;
+}
+
+impl<K: Ord, V> InplaceRangeMap<K, V> for BTreeMap<K, V> {
+    fn removable_range_iter<R: RangeBounds<K>>(&mut self, range: R) -> impl Iterator<Item = MapEntry<'_, K, V>> {
+        BTreeMapRangeEntries::new(self, range)
+    }
+}
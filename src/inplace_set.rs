@@ -0,0 +1,65 @@
+use std::collections::{BTreeSet, HashSet};
+use std::hash::{BuildHasher, Hash};
+use crate::btree_set_iterator::TakeableBTreeSetIter;
+use crate::hash_set_iterator::{TakeableHashSetIter, TakeableSetItem};
+
+/// A trait that extends sets with methods for in-place iteration with take operations.
+///
+/// # Implementations
+///
+/// - `HashSet<T, S>`: Standard library's hash set implementation
+/// - `BTreeSet<T>`: Standard library's ordered set implementation, visited in ascending order
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashSet;
+/// use inplace_iter::prelude::*;
+///
+/// let mut pending = HashSet::from(["a.txt", "b.log", "c.txt"]);
+/// let mut texts = HashSet::new();
+/// for item in pending.takeable_iter() {
+///     if item.get().ends_with(".txt") {
+///         texts.insert(item.take());
+///     }
+/// }
+/// assert_eq!(pending, HashSet::from(["b.log"]));
+/// assert_eq!(texts, HashSet::from(["a.txt", "c.txt"]));
+/// ```
+pub trait InplaceSet<T> {
+    /// Returns an iterator that allows taking ownership of the elements during iteration.
+    ///
+    /// The iterator yields items that implement `TakeableItem<T>` and `RemovableItem<T>`.
+    /// Taking the item removes the element from the set. There is no mutable access, as a
+    /// changed element would no longer match its hash.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The kept elements of a `HashSet` are inserted back into the set, which hashes them
+    ///   once more
+    /// - The kept elements of a `BTreeSet` are inserted back into the set in O(log n) each
+    ///
+    /// # Leaking
+    ///
+    /// The elements are moved out of the set while it is iterated, so leaking the iterator
+    /// (e.g. with `mem::forget`) loses the elements not yet visited, and leaking an item loses
+    /// its element. The set stays valid.
+    fn takeable_iter(&mut self) -> impl Iterator<Item = TakeableSetItem<'_, T>>;
+}
+
+impl<T, S> InplaceSet<T> for HashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    fn takeable_iter(&mut self) -> impl Iterator<Item = TakeableSetItem<'_, T>> {
+        TakeableHashSetIter::new(self)
+    }
+}
+
+impl<T: Ord> InplaceSet<T> for BTreeSet<T> {
+    fn takeable_iter(&mut self) -> impl Iterator<Item = TakeableSetItem<'_, T>> {
+        TakeableBTreeSetIter::new(self)
+    }
+}
@@ -1,11 +1,62 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
 #[cfg(feature = "loop-lifetime-guard")]
-use std::cell::RefCell;
-#[cfg(feature = "loop-lifetime-guard")]
-use std::rc::Rc;
+use crate::epoch::{violation, Epoch, ItemEpoch};
+use crate::handle::{PassStamp, ToHandle, VecHandle, VecShape};
+use crate::inplace_iterator::InplaceIterator;
+use crate::item_index::{self, ItemIndex};
+#[cfg(debug_assertions)]
+use crate::ledger::VisitLedger;
 use crate::prelude::{RemovableItem, TakeableItem};
 use crate::removable_iterator::RemovableItemMut;
+use crate::swap_item::SwapItem;
 use crate::takeable_iterator::TakeableItemMut;
 
+/// Which elements a swap removing pass visits after a removal.
+///
+/// A removal moves the last element of the vector into the place of the removed one. With
+/// either policy every element is visited at most once, and the elements which are not
+/// removed stay in their place once visited (except in the wrapped around part of
+/// [`InplaceVector::removable_iter_from`](crate::prelude::InplaceVector::removable_iter_from)).
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3, 4, 5];
+/// let mut visited = Vec::new();
+/// for item in numbers.removable_iter_with(VisitationPolicy::SkipSwapped) {
+///     visited.push(*item.get());
+///     if *item.get() == 2 {
+///         item.remove();
+///     }
+/// }
+/// // 5 was swapped into the place of 2 and was not visited
+/// assert_eq!(visited, vec![1, 2, 3, 4]);
+/// assert_eq!(numbers, vec![1, 5, 3, 4]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisitationPolicy {
+    /// The element swapped into the place of the removed one is visited next, so every element
+    /// is visited exactly once.
+    #[default]
+    RevisitSwapped,
+    /// The element swapped into the place of the removed one is not visited, the pass moves on
+    /// to the next place. Each removal leaves one element unvisited.
+    SkipSwapped,
+}
+
+impl VisitationPolicy {
+    /// Every element present at the start of the pass is visited exactly once, including the
+    /// ones swapped into the place of removed ones and in wrapped around passes.
+    ///
+    /// This is what [`VisitationPolicy::RevisitSwapped`] guarantees: a removal only ever swaps
+    /// in an unvisited element, except in the wrapped around part, where the swapped in element
+    /// is recognised as visited and skipped.
+    pub const EXACTLY_ONCE: Self = Self::RevisitSwapped;
+}
+
 /// An iterator which allows you to take items from the underlying vector.
 ///
 /// It is only valid to take an item if you have not already taken it.
@@ -18,28 +69,90 @@ pub struct InplaceVecIterator<'a, T> {
     removed: bool,
     /// The current index in the vector, or None if iteration hasn't started.
     index: Option<usize>,
-    /// The rotten indicator given to the last generated iterator item.
+    /// The index the iteration starts at.
+    start: usize,
+    /// Whether the iteration continues from the beginning up to `start` after reaching the end.
+    wrap: bool,
+    /// Whether the iteration is in the wrapped around part, before `start`.
+    wrapped: bool,
+    /// Whether the element swapped into the place of a removed one is visited.
+    policy: VisitationPolicy,
+    /// The stamp of the handles given out by this pass, if any.
+    handles: Option<PassStamp>,
+    /// Whether a removal moved an already visited element, which invalidates the handles.
+    moved_visited: bool,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+    /// The state of the vector after the last change done through the items.
     #[cfg(feature = "loop-lifetime-guard")]
-    last_rotten: Option<Rc<RefCell<bool>>>,
+    expected: VecShape,
+    /// The visited elements, checked in debug builds.
+    #[cfg(debug_assertions)]
+    ledger: VisitLedger,
 }
 
-#[cfg(feature = "loop-lifetime-guard")]
 impl<'a, T> Drop for InplaceVecIterator<'a, T> {
     fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
         self.rotten_item();
+        self.check_moved_visited();
+        if let Some(stamp) = self.handles.take()
+            && !self.moved_visited
+        {
+            let v = unsafe { &(*self.data) };
+            let _ = stamp.set(VecShape::of(v));
+        }
     }
 }
 
 #[cfg(feature = "loop-lifetime-guard")]
 impl<'a, T> InplaceVecIterator<'a, T> {
     fn rotten_item(&mut self) {
-        if let Some(rotten) = self.last_rotten.take() {
-            *rotten.borrow_mut() = true;
+        self.epoch.advance();
+    }
+
+    /// Panics if the vector was changed other than through the items of this iterator.
+    fn check_unchanged(&self) {
+        check_unchanged(self.data, &self.expected);
+    }
+}
+
+/// Panics if the vector is not in the `expected` state.
+#[cfg(feature = "loop-lifetime-guard")]
+fn check_unchanged<T>(data: *const Vec<T>, expected: &VecShape) {
+    let v = unsafe { &(*data) };
+    if VecShape::of(v) != *expected {
+        violation("The vector was changed during the iteration other than through the iterator items!");
+    }
+}
+
+/// Removes the element at `index`, moving the last element into its place.
+///
+/// Elements of at most 16 bytes without drop glue (in practice the small `Copy` types) are
+/// moved with plain loads and stores, skipping the checks of `Vec::swap_remove` the pass has
+/// already done. The rest go through `Vec::swap_remove`, where the copy outweighs the checks.
+///
+/// # Safety
+///
+/// `index` must be in bounds of `v`.
+#[inline]
+pub(crate) unsafe fn swap_remove_in_bounds<T>(v: &mut Vec<T>, index: usize) -> T {
+    if size_of::<T>() <= 16 && !std::mem::needs_drop::<T>() {
+        unsafe {
+            let last = v.len() - 1;
+            let base = v.as_mut_ptr();
+            let value = base.add(index).read();
+            base.add(index).write(base.add(last).read());
+            v.set_len(last);
+            value
         }
+    } else {
+        v.swap_remove(index)
     }
 }
 
-impl<T> RemovableItem<T> for InplaceVecItem<T> {
+impl<'a, T> RemovableItem<T> for InplaceVecItem<'a, T> {
     /// Remove the current item from the underlying vector.
     /// The last item is moved to this current place
     fn remove(self) {
@@ -55,7 +168,7 @@ impl<T> RemovableItem<T> for InplaceVecItem<T> {
     }
 }
 
-impl<T> TakeableItem<T> for InplaceVecItem<T> {
+impl<'a, T> TakeableItem<T> for InplaceVecItem<'a, T> {
     fn take(self) -> T {
         self.take_value()
     }
@@ -65,7 +178,7 @@ impl<T> TakeableItem<T> for InplaceVecItem<T> {
     }
 }
 
-impl<T> TakeableItemMut<T> for InplaceVecItem<T> {
+impl<'a, T> TakeableItemMut<T> for InplaceVecItem<'a, T> {
     fn take(self) -> T {
         self.take_value()
     }
@@ -73,12 +186,12 @@ impl<T> TakeableItemMut<T> for InplaceVecItem<T> {
     fn get(&self) -> &T {
         self.get_value()
     }
-    fn get_mut(&self) -> &mut T {
+    fn get_mut(&mut self) -> &mut T {
         self.get_value_mut()
     }
 }
 
-impl<T> RemovableItemMut<T> for InplaceVecItem<T> {
+impl<'a, T> RemovableItemMut<T> for InplaceVecItem<'a, T> {
     fn remove(self) {
         let _ = self.take_value();
     }
@@ -93,11 +206,13 @@ impl<T> RemovableItemMut<T> for InplaceVecItem<T> {
 }
 
 impl<'a, T> Iterator for InplaceVecIterator<'a, T> {
-    type Item = InplaceVecItem<T>;
+    type Item = InplaceVecItem<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         #[cfg(feature = "loop-lifetime-guard")]
         self.rotten_item();
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_unchanged();
         let len = unsafe {
             let v = &mut (*self.data);
             if v.is_empty() {
@@ -105,30 +220,67 @@ impl<'a, T> Iterator for InplaceVecIterator<'a, T> {
             }
             v.len()
         };
-        let index = if self.removed {
-            self.removed = false;
-            self.index.unwrap() // if taken, then index is set and we don't increment to the next
-        } else if let Some(index) = self.index {
-            // move to the next item
-            self.index = Some(index + 1);
-            index + 1
-        } else {
-            // start at 0
-            self.index = Some(0);
-            0
-        };
-        if index < len {
+        #[cfg(debug_assertions)]
+        if let Some(index) = self.index {
+            self.ledger.sync(index, len, self.removed);
+        }
+        self.check_moved_visited();
+        let (index, wrapped) = self.next_position();
+        self.removed = false;
+        self.index = Some(index);
+        self.wrapped = wrapped;
+        if index < self.end(wrapped, len) {
             #[cfg(feature = "loop-lifetime-guard")]
-            let rotten = {
-                let rotten = Rc::new(RefCell::new(false));
-                self.last_rotten = Some(rotten.clone());
-                rotten
-            };
-            Some(InplaceVecItem::new(self.data, index, &mut self.removed, #[cfg(feature = "loop-lifetime-guard")] rotten))
+            let rotten = self.epoch.item();
+            // in the wrapped around part, a removal swaps in an already visited element, unless the
+            // tail is gone
+            let fresh_replacement = !(wrapped && len > self.start);
+            #[cfg(debug_assertions)]
+            self.ledger.visit(index);
+            Some(InplaceVecItem::new(self.data, index, &mut self.removed, &mut self.handles, fresh_replacement, #[cfg(feature = "loop-lifetime-guard")] rotten, #[cfg(feature = "loop-lifetime-guard")] &mut self.expected))
         } else {
+            // skipping the swapped in elements is what the other policy asks for
+            #[cfg(debug_assertions)]
+            if self.policy == VisitationPolicy::RevisitSwapped {
+                self.ledger.assert_complete(self.start, self.wrap);
+            }
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Removing the current item shrinks the vector, but the swapped in element is visited
+        // in its place, so the number of remaining items does not change. When it is skipped,
+        // the position moves on and the remaining items shrink with the vector.
+        let len = unsafe { (*self.data).len() };
+        let (index, wrapped) = self.next_position();
+        let mut remaining = self.end(wrapped, len).saturating_sub(index);
+        if self.wrap && !wrapped {
+            remaining += self.start.min(len);
+        }
+        (remaining, Some(remaining))
+    }
+
+    fn count(self) -> usize {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_unchanged();
+        self.size_hint().0
+    }
+}
+
+impl<'a, T> FusedIterator for InplaceVecIterator<'a, T> {}
+
+impl<'a, T> InplaceIterator<T> for InplaceVecIterator<'a, T> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        let v = unsafe { &(*self.data) };
+        let (index, wrapped) = self.next_position();
+        let end = self.end(wrapped, v.len());
+        let prefix = if self.wrap && !wrapped { self.start.min(v.len()) } else { 0 };
+        v[index.min(end)..end].iter().chain(v[..prefix].iter())
+    }
 }
 
 // impl<'a, T> Iterator for InplaceVecIterator<'a, T> {
@@ -139,91 +291,249 @@ impl<'a, T> Iterator for InplaceVecIterator<'a, T> {
 
 impl<'a, T> InplaceVecIterator<'a, T> {
     pub fn new(v: &'a mut Vec<T>) -> Self {
+        Self::new_from(v, 0, false)
+    }
+
+    /// Creates an iterator which starts at `start`. With `wrap`, the iteration continues from
+    /// the beginning of the vector up to `start` once the end is reached.
+    pub fn new_from(v: &'a mut Vec<T>, start: usize, wrap: bool) -> Self {
+        item_index::check_len(v.len());
+        #[cfg(feature = "loop-lifetime-guard")]
+        let expected = VecShape::of(v);
+        #[cfg(debug_assertions)]
+        let len = v.len();
         let data = v as *mut Vec<T>;
         Self {
             _lifetime_guard: v,
             data,
             removed: false,
             index: None,
+            start,
+            wrap,
+            wrapped: false,
+            policy: VisitationPolicy::default(),
+            handles: None,
+            moved_visited: false,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
             #[cfg(feature = "loop-lifetime-guard")]
-            last_rotten: None,
+            expected,
+            #[cfg(debug_assertions)]
+            ledger: VisitLedger::new(len),
+        }
+    }
+
+    /// Sets the policy for the elements swapped into the place of the removed ones.
+    pub fn with_policy(mut self, policy: VisitationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Records whether the last removal swapped in an element of the already visited tail.
+    fn check_moved_visited(&mut self) {
+        let len = unsafe { (*self.data).len() };
+        if self.removed && self.wrapped && len >= self.start {
+            self.moved_visited = true;
+        }
+    }
+
+    /// The index of the element the next call to `next()` yields, and whether it is in the
+    /// wrapped around part of the iteration.
+    fn next_position(&self) -> (usize, bool) {
+        let len = unsafe { (*self.data).len() };
+        let index = match self.index {
+            // in the wrapped around part, the swapped in element comes from the already visited
+            // tail, unless the tail is gone
+            Some(index) if self.removed && self.wrapped && len >= self.start => index + 1,
+            // the swapped in element is skipped on request
+            Some(index) if self.removed && self.policy == VisitationPolicy::SkipSwapped => index + 1,
+            // if taken, then index is set and we don't increment to the next
+            Some(index) if self.removed => index,
+            // move to the next item
+            Some(index) => index + 1,
+            // start at the given index
+            None => self.start,
+        };
+        if self.wrap && !self.wrapped && index >= len {
+            // the tail is done, continue with the elements before the start
+            (0, true)
+        } else {
+            (index, self.wrapped)
+        }
+    }
+
+    /// The exclusive end of the visited range.
+    fn end(&self, wrapped: bool, len: usize) -> usize {
+        if wrapped {
+            self.start.min(len)
+        } else {
+            len
         }
     }
 }
 
 
 /// A struct representing an item that can be taken from the underlying vector.
-pub struct InplaceVecItem<T> {
+///
+/// The item borrows the vector for as long as the iterator does, so an item kept after the loop
+/// keeps the vector borrowed, and using the vector is a borrow error.
+pub struct InplaceVecItem<'a, T> {
+    /// This ties the item to the borrow of the vector by the iterator.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
     /// A raw pointer to the vector containing the item.
     data: *mut Vec<T>,
     /// The index of the item within the vector.
-    index: usize,
+    index: ItemIndex,
     /// An indicator to the vector that we have removed the item
     removed: *mut bool,
+    /// The stamp of the handles given out by the iterator.
+    handles: *mut Option<PassStamp>,
+    /// Whether the element swapped in on removal is not yet visited.
+    fresh_replacement: bool,
     /// Indicator that this iterator item should no longer be used!
     #[cfg(feature = "loop-lifetime-guard")]
-    rotten: Rc<RefCell<bool>>,
+    rotten: ItemEpoch,
+    /// The state of the vector expected by the iterator, updated on removal.
+    #[cfg(feature = "loop-lifetime-guard")]
+    expected: *mut VecShape,
 }
 
 #[cfg(feature = "loop-lifetime-guard")]
-impl<T> InplaceVecItem<T> {
+impl<'a, T> InplaceVecItem<'a, T> {
     fn check_rotten(&self) {
-        if *self.rotten.borrow() {
-            panic!("This iterator item is no longer valid!");
-        }
+        self.rotten.check();
+        check_unchanged(self.data, unsafe { &*self.expected });
+    }
+
+    /// Records the state of the vector after a removal through this item.
+    fn update_expected(&self) {
+        unsafe { *self.expected = VecShape::of(&*self.data) };
     }
 }
-impl<T> InplaceVecItem<T> {
+impl<'a, T> InplaceVecItem<'a, T> {
     #[cfg(feature = "loop-lifetime-guard")]
-    pub(crate) fn new(data: *mut Vec<T>, index: usize, removed: *mut bool, rotten: Rc<RefCell<bool>>) -> Self {
+    pub(crate) fn new(data: *mut Vec<T>, index: usize, removed: *mut bool, handles: *mut Option<PassStamp>, fresh_replacement: bool, rotten: ItemEpoch, expected: *mut VecShape) -> Self {
         Self {
+            _lifetime_guard: PhantomData,
             data,
-            index,
+            index: item_index::narrow(index),
             removed,
+            handles,
+            fresh_replacement,
             rotten,
+            expected,
         }
     }
     #[cfg(not(feature = "loop-lifetime-guard"))]
-    pub(crate) fn new(data: *mut Vec<T>, index: usize, removed: *mut bool) -> Self {
+    pub(crate) fn new(data: *mut Vec<T>, index: usize, removed: *mut bool, handles: *mut Option<PassStamp>, fresh_replacement: bool) -> Self {
         Self {
+            _lifetime_guard: PhantomData,
             data,
-            index,
+            index: item_index::narrow(index),
             removed,
+            handles,
+            fresh_replacement,
         }
     }
 }
 
-impl<T> InplaceVecItem<T> {
+impl<'a, T> InplaceVecItem<'a, T> {
+    /// The index of the element in the vector.
+    pub(crate) fn index(&self) -> usize {
+        item_index::widen(self.index)
+    }
+
+    /// The vector of the element.
+    pub(crate) fn vector(&self) -> *mut Vec<T> {
+        self.data
+    }
+
+    /// Panics if the element was removed by `remove_and_continue` without a replacement.
+    fn check_removed(&self) {
+        if unsafe { *self.removed } {
+            panic!("This iterator item has already been removed!");
+        }
+    }
+
     pub(crate) fn take_value(self) -> T {
         #[cfg(feature = "loop-lifetime-guard")]
         self.check_rotten();
+        self.check_removed();
         unsafe {
             *self.removed = true;
             let v = &mut (*self.data);
-            if self.index == v.len() {
+            let value = if self.index() == v.len() {
                 // at the last item, no more items
                 v.pop().unwrap()
             } else {
-                v.swap_remove(self.index)
-            }
+                #[cfg(feature = "move-stats")]
+                crate::move_stats::record::<T>(usize::from(self.index() + 1 < v.len()));
+                swap_remove_in_bounds(v, self.index())
+            };
+            #[cfg(feature = "loop-lifetime-guard")]
+            self.update_expected();
+            value
         }
     }
 
     pub(crate) fn get_value(&self) -> &T {
         #[cfg(feature = "loop-lifetime-guard")]
         self.check_rotten();
+        self.check_removed();
+        unsafe {
+            let v = &mut (*self.data);
+            &(*v.as_ptr().add(self.index()))
+        }
+    }
+
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.check_removed();
         unsafe {
             let v = &mut (*self.data);
-            &(*v.as_ptr().add(self.index))
+            &mut (*v.as_mut_ptr().add(self.index()))
         }
     }
+}
+
+impl<'a, T> ToHandle for InplaceVecItem<'a, T> {
+    fn to_handle(self) -> VecHandle {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        let stamp = unsafe { (*self.handles).get_or_insert_with(PassStamp::default) };
+        VecHandle::new(self.index(), stamp.clone())
+    }
+}
+
+impl<'a, T> SwapItem<T> for InplaceVecItem<'a, T> {
+    fn peek_replacement(&self) -> Option<&T> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.check_removed();
+        let v = unsafe { &(*self.data) };
+        if self.index() + 1 < v.len() { v.last() } else { None }
+    }
 
-    pub(crate) fn get_value_mut(&self) -> &mut T {
+    fn remove_and_continue(&mut self) -> Option<&T> {
         #[cfg(feature = "loop-lifetime-guard")]
         self.check_rotten();
+        self.check_removed();
         unsafe {
             let v = &mut (*self.data);
-            &mut (*v.as_mut_ptr().add(self.index))
+            #[cfg(feature = "move-stats")]
+            crate::move_stats::record::<T>(usize::from(self.index() + 1 < v.len()));
+            let value = swap_remove_in_bounds(v, self.index());
+            #[cfg(feature = "loop-lifetime-guard")]
+            self.update_expected();
+            drop(value);
+            if self.fresh_replacement && self.index() < v.len() {
+                // the item is the replacement now, the iterator moves past it
+                Some(&*v.as_ptr().add(self.index()))
+            } else {
+                *self.removed = true;
+                None
+            }
         }
     }
 }
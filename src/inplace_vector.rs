@@ -1,8 +1,33 @@
-use crate::inplace_vec_iterator::InplaceVecIterator;
-use crate::removable_confirm_iterator_vec::{InplaceRemovableConfirmVecIterator, RemovableConfirmIterator};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, TryReserveError};
+use std::hash::Hash;
+use std::thread;
+use crate::append_iterator_vec::{AppendMode, AppendingIterator, AppendingVecIterator};
+use crate::drain_iterator_vec::{DrainAction, DrainUnless, DrainUnordered};
+use crate::handle::ToHandle;
+use crate::inplace_iterator::InplaceIterator;
+use crate::inplace_vec_iterator::{InplaceVecIterator, VisitationPolicy};
+use crate::ordered_iterator_vec::InplaceOrderedVecIterator;
+use crate::priority_iterator_vec::{Prioritized, PriorityVecIterator};
+use crate::pairwise_iterator_vec::{MergeablePair, PairwiseVecIterator};
+use crate::put_back_iterator::PutBackIter;
+use crate::removable_confirm_iterator_vec::{EagerRemovableItem, InplaceRemovableConfirmVecIterator, RemovableConfirmIterator};
 use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+use crate::selection::Selection;
+use crate::swap_item::SwapItem;
 use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
 
+/// What [`InplaceVector::try_retain_inplace`] does with the removals decided before an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnError {
+    /// The removals decided before the error are applied.
+    Apply,
+    /// The removals decided before the error are cancelled, like with
+    /// `RemovableConfirmIterator::cancel_removals`.
+    Rollback,
+}
+
 /// A trait that extends collections with methods for in-place iteration with removal/take operations.
 ///
 /// This trait provides methods to create iterators that can modify the underlying collection
@@ -46,7 +71,26 @@ use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
 /// assert_eq!(names.len(), 1);
 /// assert_eq!(names[0], "Bob");
 /// ```
+///
+/// With the `derive` feature, a struct wrapping its vector can forward to it:
+///
+/// ```ignore
+/// #[derive(InplaceVector)]
+/// struct Inbox {
+///     owner: String,
+///     #[inplace]
+///     messages: Vec<String>,
+/// }
+/// ```
+///
+/// # Implementing
+///
+/// Only [`inplace_vec`](Self::inplace_vec) is required, all the other methods work on the
+/// vector it returns.
 pub trait InplaceVector<T> {
+    /// Returns the vector the other methods work on.
+    fn inplace_vec(&mut self) -> &mut Vec<T>;
+
     /// Returns an iterator that allows taking ownership of elements during iteration.
     ///
     /// The iterator yields items that implement `TakeableItem<T>`, which provides
@@ -56,7 +100,12 @@ pub trait InplaceVector<T> {
     ///
     /// - Taking an element is O(1) time complexity
     /// - The order of elements is not preserved when taking elements
-    fn takeable_iter(&mut self) -> impl Iterator<Item = impl TakeableItem<T>>;
+    fn takeable_iter<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl TakeableItem<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec())
+    }
     
     /// Returns an iterator that allows taking ownership of elements during iteration.
     ///
@@ -67,7 +116,60 @@ pub trait InplaceVector<T> {
     ///
     /// - Taking an element is O(1) time complexity
     /// - The order of elements is not preserved when taking elements
-    fn takeable_iter_mut(&mut self) -> impl Iterator<Item = impl TakeableItemMut<T>>;
+    fn takeable_iter_mut<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl TakeableItemMut<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec())
+    }
+
+    /// Returns an iterator that allows taking ownership of elements during iteration, visiting
+    /// the elements swapped into the place of the taken ones according to `policy`.
+    ///
+    /// With [`VisitationPolicy::SkipSwapped`] each element is looked at at most once per
+    /// position, which suits expensive or non-idempotent decisions: the elements skipped this
+    /// pass are left for the next one.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The order of elements is not preserved when taking elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut queue = vec![10, 20, 30, 40];
+    /// let mut checks = 0;
+    /// let mut taken = Vec::new();
+    /// for item in queue.takeable_iter_with(VisitationPolicy::SkipSwapped) {
+    ///     checks += 1;
+    ///     if *item.get() >= 20 {
+    ///         taken.push(item.take());
+    ///     }
+    /// }
+    /// assert_eq!(checks, 3);
+    /// assert_eq!(taken, vec![20, 30]);
+    /// assert_eq!(queue, vec![10, 40]);
+    /// ```
+    fn takeable_iter_with<'a>(&'a mut self, policy: VisitationPolicy) -> impl InplaceIterator<T, Item = impl TakeableItem<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec()).with_policy(policy)
+    }
+
+    /// Returns an iterator that allows taking ownership of elements during iteration, visiting
+    /// the elements swapped into the place of the taken ones according to `policy`.
+    ///
+    /// See [`InplaceVector::takeable_iter_with`].
+    fn takeable_iter_mut_with<'a>(&'a mut self, policy: VisitationPolicy) -> impl InplaceIterator<T, Item = impl TakeableItemMut<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec()).with_policy(policy)
+    }
     
     /// Returns an iterator that allows removing elements during iteration.
     ///
@@ -78,7 +180,12 @@ pub trait InplaceVector<T> {
     ///
     /// - Removal is O(1) time complexity
     /// - The order of elements is not preserved when removing elements
-    fn removable_iter(&mut self) -> impl Iterator<Item = impl RemovableItem<T>>;
+    fn removable_iter<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl RemovableItem<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec())
+    }
 
     /// Returns an iterator that allows removing elements during iteration.
     ///
@@ -89,7 +196,220 @@ pub trait InplaceVector<T> {
     ///
     /// - Removal is O(1) time complexity
     /// - The order of elements is not preserved when removing elements
-    fn removable_iter_mut(&mut self) -> impl Iterator<Item = impl RemovableItemMut<T>>;
+    fn removable_iter_mut<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec())
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, visiting the elements
+    /// swapped into the place of the removed ones according to `policy`.
+    ///
+    /// See [`VisitationPolicy`] for the visited elements.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    fn removable_iter_with<'a>(&'a mut self, policy: VisitationPolicy) -> impl InplaceIterator<T, Item = impl RemovableItem<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec()).with_policy(policy)
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, visiting the elements
+    /// swapped into the place of the removed ones according to `policy`.
+    ///
+    /// See [`VisitationPolicy`] for the visited elements.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    fn removable_iter_mut_with<'a>(&'a mut self, policy: VisitationPolicy) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new(self.inplace_vec()).with_policy(policy)
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, starting at `start`.
+    ///
+    /// With `wrap`, the iteration continues from the beginning up to `start` once the end is
+    /// reached, so every element is visited. Elements swapped in from the already visited tail
+    /// while in the wrapped around part are not visited again.
+    /// This allows repeated passes over a long-lived vector to resume where the previous pass
+    /// stopped.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5];
+    /// let visited = numbers.removable_iter_from(3, true).map(|i| *i.get()).collect::<Vec<_>>();
+    /// assert_eq!(visited, vec![4, 5, 1, 2, 3]);
+    ///
+    /// for item in numbers.removable_iter_from(3, false) {
+    ///     item.remove();
+    /// }
+    /// assert_eq!(numbers, vec![1, 2, 3]);
+    /// ```
+    fn removable_iter_from<'a>(&'a mut self, start: usize, wrap: bool) -> impl InplaceIterator<T, Item = impl RemovableItem<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new_from(self.inplace_vec(), start, wrap)
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, starting at `start`.
+    ///
+    /// See [`InplaceVector::removable_iter_from`] for the meaning of `wrap`.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    fn removable_iter_mut_from<'a>(&'a mut self, start: usize, wrap: bool) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T> + ToHandle + SwapItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceVecIterator::new_from(self.inplace_vec(), start, wrap)
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, while preserving the
+    /// order of the remaining elements.
+    ///
+    /// The removed elements are dropped immediately, the remaining elements are shifted down as
+    /// the iteration moves on, and the unvisited tail is shifted once the iterator is dropped.
+    /// Use it for vectors whose order matters, e.g. sorted vectors.
+    ///
+    /// The order preserving removal is a mode of the whole pass, not of a single item: an item
+    /// of a swap removing pass removing its element in order would shift the whole tail, O(n)
+    /// per removal, while this pass shifts each remaining element at most once.
+    ///
+    /// # Performance
+    ///
+    /// - The whole pass is O(n), as each remaining element is moved at most once
+    /// - Stopping early still shifts the unvisited tail
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5];
+    /// for item in numbers.ordered_removable_iter() {
+    ///     if *item.get() % 2 == 0 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// assert_eq!(numbers, vec![1, 3, 5]);
+    /// ```
+    fn ordered_removable_iter<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl RemovableItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceOrderedVecIterator::new(self.inplace_vec())
+    }
+
+    /// Returns an iterator that allows removing elements during iteration, while preserving the
+    /// order of the remaining elements.
+    ///
+    /// See [`InplaceVector::ordered_removable_iter`].
+    fn ordered_removable_iter_mut<'a>(&'a mut self) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T>>
+    where
+        T: 'a,
+    {
+        InplaceOrderedVecIterator::new(self.inplace_vec())
+    }
+
+    /// Returns an iterator that visits the elements in descending priority classes and allows
+    /// removing them, for draining the urgent work first.
+    ///
+    /// `classify` is called once per element, at the start of the pass, which buckets the
+    /// elements by their class. The elements of a class are visited in their order in the
+    /// vector, and [`Prioritized::priority`] returns the class of the current one.
+    ///
+    /// # Performance
+    ///
+    /// - Bucketing is O(n) time complexity, the elements are not sorted
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved, they are left grouped by class
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut tasks = vec![("log", 0), ("alarm", 2), ("mail", 1), ("fire", 2)];
+    /// let mut order = Vec::new();
+    /// for item in tasks.priority_removable_iter(|task| task.1) {
+    ///     order.push(item.get().0);
+    ///     if item.priority() > 0 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// assert_eq!(order, vec!["fire", "alarm", "mail", "log"]);
+    /// assert_eq!(tasks, vec![("log", 0)]);
+    /// ```
+    fn priority_removable_iter<'a, F>(&'a mut self, classify: F) -> impl InplaceIterator<T, Item = impl RemovableItem<T> + Prioritized>
+    where
+        T: 'a,
+        F: FnMut(&T) -> u8,
+    {
+        PriorityVecIterator::new(self.inplace_vec(), classify)
+    }
+
+    /// Returns an iterator that visits the elements in descending priority classes and allows
+    /// removing them.
+    ///
+    /// See [`InplaceVector::priority_removable_iter`].
+    fn priority_removable_iter_mut<'a, F>(&'a mut self, classify: F) -> impl InplaceIterator<T, Item = impl RemovableItemMut<T> + Prioritized>
+    where
+        T: 'a,
+        F: FnMut(&T) -> u8,
+    {
+        PriorityVecIterator::new(self.inplace_vec(), classify)
+    }
+
+    /// Returns an iterator that allows removing elements during iteration and appending new
+    /// ones with [`AppendingIterator::try_push`].
+    ///
+    /// `mode` selects whether the appended elements are visited by this pass, or queued and
+    /// pushed to the vector when the pass ends. See [`AppendMode`] for an example. In
+    /// [`AppendMode::CurrentPass`] the elements are only appended into the spare capacity, so
+    /// reserve it before the pass.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, like [`InplaceVector::removable_iter`]
+    /// - Appending is O(1) in [`AppendMode::CurrentPass`] and amortized O(1) in
+    ///   [`AppendMode::NextPass`], whose queue is moved to the vector in one copy
+    fn appending_removable_iter<'a>(&'a mut self, mode: AppendMode) -> impl AppendingIterator<T, Item = impl RemovableItem<T>>
+    where
+        T: 'a,
+    {
+        AppendingVecIterator::new(self.inplace_vec(), mode)
+    }
+
+    /// Returns an iterator that allows removing and modifying elements during iteration and
+    /// appending new ones.
+    ///
+    /// See [`InplaceVector::appending_removable_iter`].
+    fn appending_removable_iter_mut<'a>(&'a mut self, mode: AppendMode) -> impl AppendingIterator<T, Item = impl RemovableItemMut<T>>
+    where
+        T: 'a,
+    {
+        AppendingVecIterator::new(self.inplace_vec(), mode)
+    }
 
     /// Returns a wrapper around iterator that allows removing elements during iteration.
     /// The removals are not yet applied.
@@ -103,7 +423,12 @@ pub trait InplaceVector<T> {
     ///
     /// - Removal is O(1) time complexity
     /// - The order of elements is not preserved when removing elements, even if the removals are cancelled.
-    fn removable_confirm_iter(&mut self) -> impl RemovableConfirmIterator<Item = impl RemovableItem<T>>;
+    fn removable_confirm_iter<'a>(&'a mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceRemovableConfirmVecIterator::new(self.inplace_vec())
+    }
     
     /// Returns a wrapper around mutable iterator that allows removing elements during iteration.
     /// The removals are not yet applied.
@@ -117,31 +442,707 @@ pub trait InplaceVector<T> {
     ///
     /// - Removal is O(1) time complexity
     /// - The order of elements is not preserved when removing elements, even if the removals are cancelled.
-    fn removable_confirm_iter_mut(&mut self) -> impl RemovableConfirmIterator<Item = impl RemovableItemMut<T>>;
-}
+    fn removable_confirm_iter_mut<'a>(&'a mut self) -> impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>>
+    where
+        T: 'a,
+    {
+        InplaceRemovableConfirmVecIterator::new(self.inplace_vec())
+    }
 
-impl<T> InplaceVector<T> for Vec<T> {
-    fn takeable_iter(&mut self) -> impl Iterator<Item = impl TakeableItem<T>> {
-        InplaceVecIterator::new(self)
+    /// Returns an iterator that removes every element and yields it by value.
+    ///
+    /// Unlike `Vec::drain`, the order of the yielded elements is not specified (currently from
+    /// the back). The elements not yet yielded are removed when the iterator is dropped.
+    ///
+    /// # Performance
+    ///
+    /// - Yielding an element is O(1) time complexity, no other element is moved
+    fn drain_unordered<'a>(&'a mut self) -> impl ExactSizeIterator<Item = T>
+    where
+        T: 'a,
+    {
+        DrainUnordered::new(self.inplace_vec())
     }
-    
-    fn takeable_iter_mut(&mut self) -> impl Iterator<Item = impl TakeableItemMut<T>> {
-        InplaceVecIterator::new(self)
+
+    /// Returns an iterator that removes the elements and yields them by value, unless `f`
+    /// returns `DrainAction::Keep` with the element to put it back.
+    ///
+    /// The order of the yielded elements is not specified (currently from the back). The kept
+    /// elements stay in place, yielding an element moves the last element into its place. The
+    /// elements not yet visited are kept when the iterator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut jobs = vec![(1, "build"), (2, "test"), (1, "lint")];
+    /// let ready = jobs
+    ///     .drain_unless(|job| if job.0 == 1 { DrainAction::Yield(job) } else { DrainAction::Keep(job) })
+    ///     .count();
+    /// assert_eq!(ready, 2);
+    /// assert_eq!(jobs, vec![(2, "test")]);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - Each element is visited once and yielding is O(1) time complexity
+    fn drain_unless<'a, F>(&'a mut self, f: F) -> impl Iterator<Item = T>
+    where
+        T: 'a,
+        F: FnMut(T) -> DrainAction<T>,
+    {
+        DrainUnless::new(self.inplace_vec(), f)
     }
-    
-    fn removable_iter(&mut self) -> impl Iterator<Item = impl RemovableItem<T>> {
-        InplaceVecIterator::new(self)
+
+    /// Consumes the vector and returns an iterator over its elements by value, the kept
+    /// elements are returned with `put_back()`.
+    ///
+    /// The vector of the put back elements is rebuilt in the original allocation by
+    /// `finish()`, in the order they were put back.
+    ///
+    /// # Performance
+    ///
+    /// - Yielding and putting back is O(1) time complexity, no element is moved twice
+    fn into_put_back_iter(mut self) -> PutBackIter<T>
+    where
+        Self: Sized,
+    {
+        PutBackIter::new(std::mem::take(self.inplace_vec()))
     }
-    
-    fn removable_iter_mut(&mut self) -> impl Iterator<Item = impl RemovableItemMut<T>> {
-        InplaceVecIterator::new(self)
+
+    /// Returns an iterator over the pairs of adjacent elements, which allows merging the second
+    /// element of a pair into the first one.
+    ///
+    /// After a merge, the next pair is the merged element and the element following the merged
+    /// one, so a run of elements can be coalesced into its first element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut spans = vec![(0, 2), (2, 5), (7, 8), (8, 9)];
+    /// for pair in spans.pairwise_merge_iter() {
+    ///     if pair.first().1 == pair.second().0 {
+    ///         pair.merge(|first, second| first.1 = second.1);
+    ///     }
+    /// }
+    /// assert_eq!(spans, vec![(0, 5), (7, 9)]);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - The whole pass is O(n), the order of the elements is preserved
+    fn pairwise_merge_iter<'a>(&'a mut self) -> impl Iterator<Item = MergeablePair<'a, T>>
+    where
+        T: 'a,
+    {
+        PairwiseVecIterator::new(self.inplace_vec())
+    }
+
+    /// Merges the elements sharing a key into the first of them, in a single pass.
+    ///
+    /// For every element whose key was already seen, `merge` is called with the surviving
+    /// element of that key and the duplicate, which is removed from the vector. Returns the
+    /// number of merged duplicates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut totals = vec![("apple", 2), ("pear", 1), ("apple", 3), ("plum", 4), ("pear", 5)];
+    /// let merged = totals.merge_duplicates_by_key(|t| t.0, |kept, dup| kept.1 += dup.1);
+    /// assert_eq!(merged, 2);
+    /// totals.sort();
+    /// assert_eq!(totals, vec![("apple", 5), ("pear", 6), ("plum", 4)]);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - Removing a duplicate is O(1), it is swapped with the last element
+    /// - The surviving elements never move, the order of the others is not preserved
+    fn merge_duplicates_by_key<K, KF, M>(&mut self, mut key: KF, mut merge: M) -> usize
+    where
+        K: Hash + Eq,
+        KF: FnMut(&T) -> K,
+        M: FnMut(&mut T, T),
+    {
+        let v = self.inplace_vec();
+        let mut survivors = HashMap::new();
+        let mut merged = 0;
+        let mut index = 0;
+        while index < v.len() {
+            match survivors.entry(key(&v[index])) {
+                Entry::Occupied(survivor) => {
+                    // the swapped in element is not visited yet, it is checked in its new place
+                    let duplicate = v.swap_remove(index);
+                    merge(&mut v[*survivor.get()], duplicate);
+                    merged += 1;
+                }
+                Entry::Vacant(survivor) => {
+                    survivor.insert(index);
+                    index += 1;
+                }
+            }
+        }
+        merged
+    }
+
+    /// Keeps the elements for which `pred` returns `true`, evaluating `pred` on up to
+    /// `threads` threads, and returns the number of removed elements.
+    ///
+    /// The vector is split into a chunk per thread, the removals are applied afterwards on the
+    /// calling thread. Worth it for expensive predicates only. With a single thread, or on the
+    /// targets without threads (`wasm32` without the `atomics` target feature), `pred` is
+    /// evaluated on the calling thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = (1..=100u64).collect::<Vec<_>>();
+    /// let removed = numbers.retain_parallel(|n| (2..*n).all(|d| n % d != 0), 4);
+    /// assert_eq!(removed, 74);
+    /// assert_eq!(numbers.len(), 26);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the order of elements is not preserved
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0, or if `pred` panics on any thread.
+    fn retain_parallel<F>(&mut self, pred: F, threads: usize) -> usize
+    where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync,
+    {
+        let v = self.inplace_vec();
+        assert!(threads > 0, "at least one thread is needed");
+        let mut keep = vec![true; v.len()];
+        if threads == 1 || !THREADS_SUPPORTED {
+            for (value, keep) in v.iter().zip(&mut keep) {
+                *keep = pred(value);
+            }
+        } else {
+            let chunk_len = v.len().div_ceil(threads).max(1);
+            thread::scope(|scope| {
+                for (values, keep) in v.chunks(chunk_len).zip(keep.chunks_mut(chunk_len)) {
+                    let pred = &pred;
+                    scope.spawn(move || {
+                        for (value, keep) in values.iter().zip(keep) {
+                            *keep = pred(value);
+                        }
+                    });
+                }
+            });
+        }
+        let mut removed = 0;
+        // removing from the back, the last element is always an already decided one
+        for index in (0..keep.len()).rev() {
+            if !keep[index] {
+                v.swap_remove(index);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Selects the elements matching `pred`, without modifying the vector.
+    ///
+    /// The selection can be narrowed down and then removed or taken at once. Unlike the
+    /// confirm iterator, the vector is not touched until the selection is applied.
+    fn select<F>(&mut self, pred: F) -> Selection<'_, T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        Selection::new(self.inplace_vec(), pred)
+    }
+
+    /// Keeps only the `n` elements with the largest keys, removes the rest and returns the
+    /// number of removed elements.
+    ///
+    /// Bounding a buffer to its newest `n` entries is `keep_top_n(n, |e| e.timestamp)`. The
+    /// ties at the `n`-th key are broken arbitrarily.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut events = vec![(3, "c"), (1, "a"), (5, "e"), (2, "b"), (4, "d")];
+    /// assert_eq!(events.keep_top_n(2, |e| e.0), 3);
+    /// events.sort();
+    /// assert_eq!(events, vec![(4, "d"), (5, "e")]);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - O(len) on average, by selection instead of a sort
+    /// - The order of elements is not preserved
+    fn keep_top_n<K, F>(&mut self, n: usize, key: F) -> usize
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let v = self.inplace_vec();
+        let removed = v.len().saturating_sub(n);
+        if removed > 0 {
+            partition_top_n(v, n, key);
+            v.truncate(n);
+        }
+        removed
+    }
+
+    /// Keeps only the `n` elements with the largest keys, like [`keep_top_n`](Self::keep_top_n),
+    /// and returns the removed elements.
+    fn take_below_top_n<K, F>(&mut self, n: usize, key: F) -> Vec<T>
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let v = self.inplace_vec();
+        if v.len() <= n {
+            return Vec::new();
+        }
+        partition_top_n(v, n, key);
+        v.split_off(n)
     }
 
-    fn removable_confirm_iter(&mut self) -> impl RemovableConfirmIterator<Item=impl RemovableItem<T>> {
-        InplaceRemovableConfirmVecIterator::new(self)
+    /// Keeps only the `n` elements with the largest keys, like [`take_below_top_n`](Self::take_below_top_n),
+    /// but returns an error instead of aborting if the vector of the removed elements cannot
+    /// be allocated.
+    ///
+    /// The allocation is made before anything is moved, on an error the vector is unchanged.
+    fn try_take_below_top_n<K, F>(&mut self, n: usize, key: F) -> Result<Vec<T>, TryReserveError>
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let v = self.inplace_vec();
+        if v.len() <= n {
+            return Ok(Vec::new());
+        }
+        let mut removed = Vec::new();
+        removed.try_reserve_exact(v.len() - n)?;
+        partition_top_n(v, n, key);
+        removed.extend(v.drain(n..));
+        Ok(removed)
     }
 
-    fn removable_confirm_iter_mut(&mut self) -> impl RemovableConfirmIterator<Item=impl RemovableItemMut<T>> {
-        InplaceRemovableConfirmVecIterator::new(self)
+    /// Keeps the elements matching `pred` in their order, then sorts them by `key`, returns the
+    /// number of removed elements.
+    ///
+    /// The compaction pass also checks whether the survivors are already sorted, so a vector
+    /// which stays sorted is not sorted again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut tasks = vec![(3, "c"), (0, "x"), (1, "a"), (2, "b"), (1, "d")];
+    /// assert_eq!(tasks.retain_sorted_by_key(|t| t.0 > 0, |t| t.0), 1);
+    /// assert_eq!(tasks, vec![(1, "a"), (1, "d"), (2, "b"), (3, "c")]);
+    /// ```
+    ///
+    /// # Performance
+    ///
+    /// - The sort is stable, the vector keeps its buffer, but the sort allocates a scratch
+    ///   buffer of up to half the length of the vector, like `slice::sort_by_key`
+    /// - O(len) if the survivors are already sorted, O(len log len) otherwise
+    fn retain_sorted_by_key<K, P, F>(&mut self, mut pred: P, mut key: F) -> usize
+    where
+        K: Ord,
+        P: FnMut(&T) -> bool,
+        F: FnMut(&T) -> K,
+    {
+        let mut removed = 0;
+        let mut sorted = true;
+        let mut last = None;
+        for item in self.ordered_removable_iter() {
+            if !pred(item.get()) {
+                item.remove();
+                removed += 1;
+            } else if sorted {
+                let k = key(item.get());
+                sorted = last.is_none_or(|last| last <= k);
+                last = Some(k);
+            }
+        }
+        if !sorted {
+            self.inplace_vec().sort_by_key(key);
+        }
+        removed
+    }
+
+    /// Removes the elements equal to `value` and returns the number of removed elements.
+    ///
+    /// The elements are compared and swap-removed directly, without the items of an iterator.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut tags = vec!["new", "old", "hot", "old"];
+    /// assert_eq!(tags.remove_all_eq(&"old"), 2);
+    /// assert_eq!(tags, vec!["new", "hot"]);
+    /// ```
+    fn remove_all_eq(&mut self, value: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        let v = self.inplace_vec();
+        let len = v.len();
+        let mut index = 0;
+        while index < v.len() {
+            if v[index] == *value {
+                // the swapped in last element is compared next
+                v.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        len - v.len()
+    }
+
+    /// Takes the elements matching `pred` into `dest`, but only as long as `dest` has spare
+    /// capacity. The pass stops as soon as `dest` is full, so `dest` never reallocates.
+    ///
+    /// Returns the number of elements moved into `dest`.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The order of elements is not preserved when taking elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5, 6];
+    /// let mut evens = Vec::with_capacity(2);
+    /// let moved = numbers.take_where_into_capacity(|n| n % 2 == 0, &mut evens);
+    /// assert_eq!(moved, 2);
+    /// assert_eq!(evens, vec![2, 6]);
+    /// assert_eq!(numbers, vec![1, 5, 3, 4]);
+    /// ```
+    fn take_where_into_capacity<F>(&mut self, mut pred: F, dest: &mut Vec<T>) -> usize
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut moved = 0;
+        let mut iter = self.takeable_iter();
+        while dest.len() < dest.capacity() {
+            let Some(item) = iter.next() else {
+                break;
+            };
+            if pred(item.get()) {
+                dest.push(item.take());
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Replaces the elements matching `pred` with the value returned by `f` for them.
+    ///
+    /// Returns the number of replaced elements. Nothing is removed, so the order of the
+    /// elements is preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut names = vec!["ann".to_string(), "Bob".to_string(), "cid".to_string()];
+    /// let changed = names.replace_where(|n| n.starts_with(char::is_lowercase), |n| n.to_uppercase());
+    /// assert_eq!(changed, 2);
+    /// assert_eq!(names, vec!["ANN", "Bob", "CID"]);
+    /// ```
+    fn replace_where<P, F>(&mut self, mut pred: P, mut f: F) -> usize
+    where
+        P: FnMut(&T) -> bool,
+        F: FnMut(&T) -> T,
+    {
+        let mut replaced = 0;
+        for mut item in self.removable_iter_mut() {
+            if pred(item.get()) {
+                let value = f(item.get());
+                *item.get_mut() = value;
+                replaced += 1;
+            }
+        }
+        replaced
+    }
+
+    /// Removes the elements whose deadline, returned by `deadline`, is not after `now`, and
+    /// returns the number of removed elements.
+    ///
+    /// The deadline can be anything ordered, e.g. an `Instant` or a timestamp.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, only the expired elements cost a move, which suits
+    ///   the usual expiry pass removing few elements
+    /// - The order of elements is not preserved when removing elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut sessions = vec![("a", 100), ("b", 250), ("c", 180)];
+    /// assert_eq!(sessions.expire_where(180, |s| s.1), 2);
+    /// assert_eq!(sessions, vec![("b", 250)]);
+    /// ```
+    fn expire_where<D, F>(&mut self, now: D, mut deadline: F) -> usize
+    where
+        D: PartialOrd,
+        F: FnMut(&T) -> D,
+    {
+        let mut expired = 0;
+        for item in self.removable_iter() {
+            if deadline(item.get()) <= now {
+                item.remove();
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    /// Takes the elements whose deadline, returned by `deadline`, is not after `now`.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The order of elements is not preserved when taking elements
+    fn take_expired_where<D, F>(&mut self, now: D, mut deadline: F) -> Vec<T>
+    where
+        D: PartialOrd,
+        F: FnMut(&T) -> D,
+    {
+        self.takeable_iter().filter(|item| deadline(item.get()) <= now).map(|item| item.take()).collect()
+    }
+
+    /// Takes the elements matching `pred_a` into the first vector and the other elements
+    /// matching `pred_b` into the second one, in a single pass, and keeps the rest.
+    ///
+    /// `pred_b` is only called for the elements not matching `pred_a`.
+    ///
+    /// # Performance
+    ///
+    /// - Taking an element is O(1) time complexity
+    /// - The order of elements is not preserved when taking elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut packets = vec![(1, "ctl"), (2, "data"), (3, "ack"), (4, "data"), (5, "ctl")];
+    /// let (control, acks) = packets.split_take(|p| p.1 == "ctl", |p| p.1 == "ack");
+    /// assert_eq!(control, vec![(1, "ctl"), (5, "ctl")]);
+    /// assert_eq!(acks, vec![(3, "ack")]);
+    /// assert_eq!(packets, vec![(4, "data"), (2, "data")]);
+    /// ```
+    fn split_take<A, B>(&mut self, mut pred_a: A, mut pred_b: B) -> (Vec<T>, Vec<T>)
+    where
+        A: FnMut(&T) -> bool,
+        B: FnMut(&T) -> bool,
+    {
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        for item in self.takeable_iter() {
+            if pred_a(item.get()) {
+                a.push(item.take());
+            } else if pred_b(item.get()) {
+                b.push(item.take());
+            }
+        }
+        (a, b)
+    }
+
+    /// Takes the elements whose deadline is not after `now` into `dest`, like
+    /// [`take_expired_where`](Self::take_expired_where), but returns an error instead of
+    /// aborting if `dest` cannot grow. Returns the number of taken elements.
+    ///
+    /// On an error the elements taken so far are in `dest`, and the element which did not
+    /// fit, with the ones not visited yet, stays in the vector, so nothing is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut sessions = vec![("a", 100), ("b", 250), ("c", 180)];
+    /// let mut expired = Vec::new();
+    /// assert_eq!(sessions.try_take_expired_where(180, |s| s.1, &mut expired), Ok(2));
+    /// assert_eq!(expired, vec![("a", 100), ("c", 180)]);
+    /// ```
+    fn try_take_expired_where<D, F>(&mut self, now: D, mut deadline: F, dest: &mut Vec<T>) -> Result<usize, TryReserveError>
+    where
+        D: PartialOrd,
+        F: FnMut(&T) -> D,
+    {
+        let mut taken = 0;
+        for item in self.takeable_iter() {
+            if deadline(item.get()) <= now {
+                dest.try_reserve(1)?;
+                dest.push(item.take());
+                taken += 1;
+            }
+        }
+        Ok(taken)
+    }
+
+    /// Takes the elements matching `pred_a` into `a` and the other elements matching `pred_b`
+    /// into `b`, like [`split_take`](Self::split_take), but returns an error instead of
+    /// aborting if `a` or `b` cannot grow. Returns the number of taken elements.
+    ///
+    /// On an error the elements taken so far are in `a` and `b`, and the element which did
+    /// not fit, with the ones not visited yet, stays in the vector, so nothing is lost.
+    fn try_split_take<A, B>(&mut self, mut pred_a: A, mut pred_b: B, a: &mut Vec<T>, b: &mut Vec<T>) -> Result<usize, TryReserveError>
+    where
+        A: FnMut(&T) -> bool,
+        B: FnMut(&T) -> bool,
+    {
+        let mut taken = 0;
+        for item in self.takeable_iter() {
+            let dest = if pred_a(item.get()) {
+                &mut *a
+            } else if pred_b(item.get()) {
+                &mut *b
+            } else {
+                continue;
+            };
+            dest.try_reserve(1)?;
+            dest.push(item.take());
+            taken += 1;
+        }
+        Ok(taken)
+    }
+
+    /// Keeps the elements for which `f` returns `true`, threading an accumulator through the
+    /// calls, and returns the final accumulator.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity
+    /// - The order of elements is not preserved when removing elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut files = vec![("a.log", 120), ("b.txt", 4), ("c.log", 80)];
+    /// let freed = files.retain_fold(0, |freed, file| {
+    ///     if file.0.ends_with(".log") {
+    ///         *freed += file.1;
+    ///         false
+    ///     } else {
+    ///         true
+    ///     }
+    /// });
+    /// assert_eq!(freed, 200);
+    /// assert_eq!(files, vec![("b.txt", 4)]);
+    /// ```
+    fn retain_fold<A, F>(&mut self, init: A, mut f: F) -> A
+    where
+        F: FnMut(&mut A, &mut T) -> bool,
+    {
+        let mut acc = init;
+        for mut item in self.removable_iter_mut() {
+            if !f(&mut acc, item.get_mut()) {
+                item.remove();
+            }
+        }
+        acc
+    }
+
+    /// Keeps the elements for which `f` returns `Ok(true)` and returns the number of removed
+    /// elements, or stops at the first error and returns it.
+    ///
+    /// The removals decided before the error are applied or rolled back according to
+    /// `on_error`. A rollback keeps all the elements, but not their order, and the changes
+    /// made through the mutable references stay.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut ports = vec!["80", "0", "443", "http"];
+    /// let result = ports.try_retain_inplace(OnError::Rollback, |p| p.parse::<u16>().map(|p| p != 0));
+    /// assert!(result.is_err());
+    /// assert_eq!(ports.len(), 4);
+    ///
+    /// let mut ports = vec!["80", "0", "443", "http"];
+    /// let result = ports.try_retain_inplace(OnError::Apply, |p| p.parse::<u16>().map(|p| p != 0));
+    /// assert!(result.is_err());
+    /// assert_eq!(ports.len(), 3);
+    /// ```
+    fn try_retain_inplace<E, F>(&mut self, on_error: OnError, mut f: F) -> Result<usize, E>
+    where
+        F: FnMut(&mut T) -> Result<bool, E>,
+    {
+        let mut confirm = self.removable_confirm_iter_mut();
+        let mut removed = 0;
+        let mut error = None;
+        for mut item in confirm.iter() {
+            match f(item.get_mut()) {
+                Ok(true) => {}
+                Ok(false) => {
+                    item.remove();
+                    removed += 1;
+                }
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        match (error, on_error) {
+            (None, _) => {
+                confirm.confirm_removals();
+                Ok(removed)
+            }
+            (Some(e), OnError::Apply) => {
+                confirm.confirm_removals();
+                Err(e)
+            }
+            (Some(e), OnError::Rollback) => {
+                confirm.cancel_removals();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<T> InplaceVector<T> for Vec<T> {
+    fn inplace_vec(&mut self) -> &mut Vec<T> {
+        self
+    }
+}
+
+/// Whether the target can spawn threads.
+const THREADS_SUPPORTED: bool = !cfg!(all(target_family = "wasm", not(target_feature = "atomics")));
+
+/// Moves the `n` elements with the largest keys to the front, `n` must be less than the length.
+fn partition_top_n<T, K: Ord>(v: &mut [T], n: usize, mut key: impl FnMut(&T) -> K) {
+    if n > 0 {
+        v.select_nth_unstable_by_key(n - 1, |value| Reverse(key(value)));
     }
 }
\ No newline at end of file
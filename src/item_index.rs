@@ -0,0 +1,27 @@
+//! The type of the indices stored by the items, `u32` with the feature `u32-indices`.
+//!
+//! The narrower index makes the items smaller on 64-bit platforms, the vectors are checked to
+//! fit it when a pass starts.
+
+/// The index stored by an item.
+#[cfg(feature = "u32-indices")]
+pub(crate) type ItemIndex = u32;
+/// The index stored by an item.
+#[cfg(not(feature = "u32-indices"))]
+pub(crate) type ItemIndex = usize;
+
+/// Panics if the indices of a vector of `len` elements do not fit [`ItemIndex`].
+pub(crate) fn check_len(len: usize) {
+    assert!(ItemIndex::try_from(len).is_ok(), "the vector has more elements than the item indices can address");
+}
+
+/// Narrows an index checked by [`check_len`].
+pub(crate) fn narrow(index: usize) -> ItemIndex {
+    index as ItemIndex
+}
+
+/// Widens a stored index.
+#[allow(clippy::unnecessary_cast)] // a `usize` without the feature
+pub(crate) fn widen(index: ItemIndex) -> usize {
+    index as usize
+}
@@ -0,0 +1,103 @@
+//! A debug build ledger of the elements visited by a swap removing pass or a confirm session.
+//!
+//! The ledger follows every element by its index at the start of the pass, and asserts that
+//! no element is visited twice and that a completed pass visited every element it promises to.
+//! A removal swaps the element with the last one still in the pass, which covers both the swap
+//! removal and the marking of a confirm session.
+
+/// The visited elements of a pass, by their indices at the start of the pass.
+pub(crate) struct VisitLedger {
+    /// The index at the start of the pass of the element at each position.
+    origin: Vec<usize>,
+    /// The number of elements still in the pass, the removed ones are behind them.
+    live: usize,
+    /// Whether the element of each index at the start of the pass was visited.
+    visited: Vec<bool>,
+}
+
+impl VisitLedger {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            origin: (0..len).collect(),
+            live: len,
+            visited: vec![false; len],
+        }
+    }
+
+    /// Replays the removals done at `index` since the last call, so `live` elements are left
+    /// in the pass. If the item continued with the swapped in element instead of being
+    /// removed, that element is recorded as visited.
+    pub(crate) fn sync(&mut self, index: usize, live: usize, removed: bool) {
+        assert!(live <= self.live, "the vector grew during the pass");
+        let removals = self.live - live;
+        for _ in 0..removals {
+            self.live -= 1;
+            self.origin.swap(index, self.live);
+        }
+        if removals > 0 && !removed && index < live {
+            self.visit(index);
+        }
+    }
+
+    /// Replays the pending removals and forgets the visits, for a pass starting over.
+    pub(crate) fn restart(&mut self, index: Option<usize>, live: usize, removed: bool) {
+        if let Some(index) = index {
+            self.sync(index, live, removed);
+        }
+        self.visited.fill(false);
+    }
+
+    /// Records the visit of the element at `index`.
+    pub(crate) fn visit(&mut self, index: usize) {
+        let origin = self.origin[index];
+        assert!(!self.visited[origin], "the element at the start index {origin} was visited twice");
+        self.visited[origin] = true;
+    }
+
+    /// Asserts that every element left in the pass was visited, except those before `start`
+    /// when the pass did not wrap around.
+    pub(crate) fn assert_complete(&self, start: usize, wrap: bool) {
+        for &origin in &self.origin[..self.live] {
+            assert!(self.visited[origin] || (!wrap && origin < start), "the element at the start index {origin} was skipped");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VisitLedger;
+
+    #[test]
+    fn test_replayed_removals() {
+        // [a, b, c, d]: visit a, remove it, d is swapped in and visited through the same item
+        let mut ledger = VisitLedger::new(4);
+        ledger.visit(0);
+        ledger.sync(0, 3, false);
+        ledger.visit(1);
+        ledger.visit(2);
+        ledger.sync(2, 2, true);
+        ledger.assert_complete(0, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "visited twice")]
+    fn test_visited_twice() {
+        let mut ledger = VisitLedger::new(3);
+        ledger.visit(0);
+        ledger.sync(0, 2, true);
+        ledger.visit(0);
+        ledger.visit(1);
+        // the element swapped in by the removal again
+        ledger.visit(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "start index 2 was skipped")]
+    fn test_skipped() {
+        let mut ledger = VisitLedger::new(3);
+        ledger.visit(0);
+        ledger.sync(0, 2, true);
+        ledger.visit(1);
+        ledger.assert_complete(0, true);
+    }
+}
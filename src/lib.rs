@@ -24,6 +24,33 @@
 //! 1. Don't hold multiple mutable references to the same element
 //! 2. Enable the `loop-lifetime-guard` feature during development for additional safety checks
 //!
+//! The items borrow the vector for as long as their iterator does, so an item kept after its
+//! loop makes any later use of the vector a borrow error. The runtime guard covers the rest,
+//! e.g. a kept item used again while the vector is untouched.
+//!
+//! A panic in the loop body unwinds through the iterator, whose drop leaves the vector in a
+//! consistent state: the removals done so far stay applied, an order preserving pass shifts the
+//! unvisited tail into place, and a confirm session keeps its marked elements, like
+//! cancelling. No element is lost or dropped twice. With the guard, the drop also invalidates
+//! every item of the pass, so an item smuggled out of a `catch_unwind` closure panics on use
+//! instead of touching the vector.
+//!
+//! In debug builds, the swap removing passes and the confirm sessions also keep a ledger of the
+//! visited elements, asserting that no element is visited twice and that a completed pass
+//! skipped none (except by [`VisitationPolicy::SkipSwapped`](prelude::VisitationPolicy::SkipSwapped)).
+//!
+//! ```compile_fail
+//! use inplace_iter::prelude::*;
+//!
+//! let mut numbers = vec![1, 2, 3];
+//! let mut kept = None;
+//! for item in numbers.removable_iter() {
+//!     kept = Some(item);
+//! }
+//! numbers.push(4);
+//! drop(kept);
+//! ```
+//!
 //! ## Examples
 //!
 //! ### Removing elements while iterating
@@ -132,24 +159,152 @@
 //! ## Features
 //!
 //! - `loop-lifetime-guard`: Enables additional runtime checks to detect if the item is accessed outside
-//!    the loop. It is enabled by default.
+//!   the loop, or if the length or the allocation of the vector of a pass is changed other
+//!   than through its items. A second pass started through a raw pointer is not detected as
+//!   such, see the `epoch` module.
+//!   It is enabled by default. The checks cost a shared counter per pass and a comparison per
+//!   access, so they can be left on in release builds.
+//! - `guard-abort`: Makes the `loop-lifetime-guard` checks abort the process instead of
+//!   panicking, for deployments where unwinding past a misused item is not acceptable. See
+//!   [`epoch::set_violation_hook`] for a custom reaction.
+//! - `u32-indices`: Stores the indices of the swap removing and confirm items as `u32`, making
+//!   the items smaller on 64-bit platforms. Starting a pass over a vector with more than
+//!   `u32::MAX` elements panics.
+//! - `derive`: Adds `#[derive(InplaceVector)]`, implementing `InplaceVector<T>` for a struct by
+//!   forwarding to its `Vec<T>` field annotated with `#[inplace]`.
+//! - `async`: Adds `retain_async`, removing the elements rejected by asynchronous predicates,
+//!   `poll_take_ready` for vectors of futures, and the [`cleanup_task::CleanupTask`] pruning a
+//!   shared vector in the background.
+//! - `conformance`: Adds the [`conformance`] suite, checking a custom implementation of
+//!   `InplaceCollection` or `InplaceVector` against the semantics of the built-in ones.
+//! - `fuzz`: Adds the [`fuzz`] interpreter of operation scripts, for fuzzing the passes
+//!   with `cargo fuzz` or `proptest`.
+//! - `move-stats`: Adds the [`move_stats`] counters of the element moves done by the passes,
+//!   for comparing the removal modes on a workload.
+//! - `miri-tests`: Internal, enables the adversarial test suite meant for
+//!   `cargo miri test --features miri-tests`, with and without `loop-lifetime-guard`.
+
+// the derive macro refers to this crate by name, also in its own tests
+extern crate self as inplace_iter;
 
 mod removable_iterator;
 mod removable_iterator_vec;
 
 mod removable_confirm_iterator_vec;
+mod removable_confirm_iterator_deque;
+
+mod ordered_iterator_vec;
+pub mod priority_iterator_vec;
+pub mod append_iterator_vec;
+mod drain_iterator_vec;
+pub mod put_back_iterator;
+pub mod pairwise_iterator_vec;
+pub mod chunked_removal;
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+pub mod deadline_confirm;
 
 mod takeable_iterator;
+mod entry_item;
 mod takeable_iterator_vec;
 
+mod inplace_iterator;
+#[cfg(feature = "loop-lifetime-guard")]
+pub mod epoch;
+#[cfg(debug_assertions)]
+mod ledger;
+mod inplace_iterator_vec;
+mod item_index;
+pub mod cancellation;
+pub mod progress;
+#[cfg(feature = "move-stats")]
+pub mod move_stats;
+
 pub mod inplace_vec_iterator;
 mod inplace_vector;
 
+pub mod inplace_deque_iterator;
+mod inplace_deque;
+
+pub mod hash_map_iterator;
+pub mod btree_map_iterator;
+mod inplace_map;
+
+pub mod hash_set_iterator;
+pub mod btree_set_iterator;
+mod inplace_set;
+
+pub mod mark_set;
+pub mod removal_plan;
+pub mod predicate;
+
+pub mod string_segments;
+pub mod path_components;
+pub mod weak_prune;
+pub mod option_compaction;
+pub mod tombstone;
+pub mod indexed_vec;
+pub mod double_buffer;
+pub mod arc_prune;
+pub mod rebalance;
+pub mod constant_time;
+pub mod patch;
+pub mod handle;
+pub mod swap_item;
+pub mod try_inplace;
+pub mod inplace_collection;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+mod delegate;
+pub mod selection;
+#[cfg(feature = "async")]
+pub mod retain_async;
+#[cfg(feature = "async")]
+pub mod cleanup_task;
+
+#[cfg(all(test, feature = "miri-tests"))]
+mod miri_tests;
+
 pub mod prelude {
     pub use crate::removable_iterator::RemovableItem;
     pub use crate::removable_iterator::RemovableItemMut;
     pub use crate::takeable_iterator::TakeableItem;
     pub use crate::takeable_iterator::TakeableItemMut;
-    pub use crate::inplace_vector::InplaceVector;
-    pub use crate::removable_confirm_iterator_vec::RemovableConfirmIterator;
+    pub use crate::takeable_iterator::Verdict;
+    pub use crate::entry_item::{RemovableEntry, TakeableEntry};
+    pub use crate::inplace_vector::{InplaceVector, OnError};
+    pub use crate::inplace_iterator::InplaceIterator;
+    pub use crate::inplace_vec_iterator::VisitationPolicy;
+    pub use crate::cancellation::Cancellation;
+    pub use crate::inplace_deque::InplaceDeque;
+    pub use crate::inplace_map::{InplaceMap, InplaceRangeMap};
+    pub use crate::inplace_set::InplaceSet;
+    pub use crate::removable_confirm_iterator_vec::{ConfirmState, EagerRemovableItem, RemovableConfirmIterator, StaleConfirmState};
+    pub use crate::chunked_removal::ChunkedRemoval;
+    #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+    pub use crate::deadline_confirm::OnExpiry;
+    pub use crate::mark_set::MarkSet;
+    pub use crate::ordered_iterator_vec::SortedVector;
+    pub use crate::removal_plan::{Decision, PlanRemovals, RemovalReport};
+    pub use crate::predicate::{CauseHistogram, Predicate};
+    pub use crate::string_segments::InplaceString;
+    pub use crate::path_components::InplacePath;
+    pub use crate::weak_prune::PruneDeadWeaks;
+    pub use crate::option_compaction::OptionVector;
+    pub use crate::arc_prune::{PruneUnique, SharedSlice};
+    pub use crate::rebalance::Rebalance;
+    pub use crate::constant_time::RetainConstantTime;
+    pub use crate::drain_iterator_vec::DrainAction;
+    pub use crate::patch::{ApplyOps, Op};
+    pub use crate::handle::{RemovalRequest, ToHandle, VecHandle};
+    pub use crate::swap_item::SwapItem;
+    pub use crate::priority_iterator_vec::Prioritized;
+    pub use crate::append_iterator_vec::{AppendMode, AppendingIterator};
+    pub use crate::try_inplace::TryInplaceVector;
+    pub use crate::inplace_collection::InplaceCollection;
+    #[cfg(feature = "derive")]
+    pub use inplace_iter_derive::InplaceVector;
+    #[cfg(feature = "async")]
+    pub use crate::retain_async::{PollTakeReady, RetainAsync};
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+//! Storage for the removal marks of the deferred modes.
+//!
+//! The marks can live in a user provided storage, so repeated passes over the same vector reuse
+//! one allocation, and the marks can be kept or combined after the pass.
+//!
+//! Only [`RemovalPlan`](crate::removal_plan::RemovalPlan) keeps its marks in a [`MarkSet`]. The
+//! confirm sessions have no marks to store: marking an element swaps it to the back of the
+//! vector, and the session only counts the elements in front of it.
+
+/// A set of marks, one for each element of the scanned collection.
+///
+/// Implemented for `Vec<bool>` and `[bool]`, for bit packed `Vec<u64>` and `[u64]`, and for
+/// mutable references to any of them. Other bitsets (e.g. `FixedBitSet` or a `bitvec` slice) can
+/// be used by implementing this trait on a thin wrapper.
+pub trait MarkSet {
+    /// Prepares the storage for `len` elements, with all of them unmarked.
+    ///
+    /// # Panics
+    ///
+    /// Storages which cannot grow panic if they cannot hold `len` marks.
+    fn reset(&mut self, len: usize);
+
+    /// Returns `true` if the element at `index` is marked.
+    fn is_marked(&self, index: usize) -> bool;
+
+    /// Marks or unmarks the element at `index`.
+    fn set_mark(&mut self, index: usize, marked: bool);
+}
+
+impl MarkSet for Vec<bool> {
+    fn reset(&mut self, len: usize) {
+        self.clear();
+        self.resize(len, false);
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        self.as_slice().is_marked(index)
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        self.as_mut_slice().set_mark(index, marked);
+    }
+}
+
+impl MarkSet for [bool] {
+    fn reset(&mut self, len: usize) {
+        assert!(len <= self.len(), "the mark storage holds {} marks, {} needed", self.len(), len);
+        self[..len].fill(false);
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        self[index]
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        self[index] = marked;
+    }
+}
+
+impl MarkSet for Vec<u64> {
+    fn reset(&mut self, len: usize) {
+        self.clear();
+        self.resize(len.div_ceil(u64::BITS as usize), 0);
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        self.as_slice().is_marked(index)
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        self.as_mut_slice().set_mark(index, marked);
+    }
+}
+
+impl MarkSet for [u64] {
+    fn reset(&mut self, len: usize) {
+        let words = len.div_ceil(u64::BITS as usize);
+        assert!(words <= self.len(), "the mark storage holds {} marks, {} needed", self.len() * u64::BITS as usize, len);
+        self[..words].fill(0);
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        let bits = u64::BITS as usize;
+        self[index / bits] & (1 << (index % bits)) != 0
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        let bits = u64::BITS as usize;
+        if marked {
+            self[index / bits] |= 1 << (index % bits);
+        } else {
+            self[index / bits] &= !(1 << (index % bits));
+        }
+    }
+}
+
+impl<M: MarkSet + ?Sized> MarkSet for &mut M {
+    fn reset(&mut self, len: usize) {
+        (**self).reset(len);
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        (**self).is_marked(index)
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        (**self).set_mark(index, marked);
+    }
+}
@@ -0,0 +1,283 @@
+//! Adversarial programs for `cargo miri test --features miri-tests`.
+//!
+//! Each test drives the unsafe code paths with elements which make misuse observable: every
+//! drop is recorded, some drops panic, and some elements are zero sized. Miri reports any
+//! undefined behavior, the assertions catch leaks and double drops. The suite is meant to be
+//! run both with and without the `loop-lifetime-guard` feature.
+
+use std::cell::{Cell, RefCell};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+use crate::prelude::*;
+
+/// Records the drops of the [`Tracked`] elements.
+#[derive(Default, Clone)]
+struct DropLog {
+    dropped: Rc<RefCell<Vec<u32>>>,
+}
+
+impl DropLog {
+    fn make(&self, ids: impl IntoIterator<Item = u32>) -> Vec<Tracked> {
+        ids.into_iter().map(|id| Tracked { id, log: self.clone(), panic_on_drop: false }).collect()
+    }
+
+    /// Panics unless exactly `ids` were dropped, each once.
+    fn assert_dropped(&self, ids: impl IntoIterator<Item = u32>) {
+        let mut dropped = self.dropped.borrow().clone();
+        dropped.sort();
+        let mut expected = ids.into_iter().collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(dropped, expected);
+    }
+}
+
+/// An element which records its drop and detects being dropped twice.
+struct Tracked {
+    id: u32,
+    log: DropLog,
+    panic_on_drop: bool,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let mut dropped = self.log.dropped.borrow_mut();
+        assert!(!dropped.contains(&self.id), "{} dropped twice", self.id);
+        dropped.push(self.id);
+        drop(dropped);
+        if self.panic_on_drop {
+            panic!("drop of {} failed", self.id);
+        }
+    }
+}
+
+thread_local! {
+    static ZST_DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A zero sized element counting its drops.
+struct Zst;
+
+impl Drop for Zst {
+    fn drop(&mut self) {
+        ZST_DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+fn ids(v: &[Tracked]) -> Vec<u32> {
+    let mut ids = v.iter().map(|t| t.id).collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn remove_take_and_keep() {
+    let log = DropLog::default();
+    let mut v = log.make(0..8);
+    let mut taken = Vec::new();
+    for mut item in v.takeable_iter_mut() {
+        match item.get().id % 3 {
+            0 => taken.push(item.take()),
+            1 => item.get_mut().id += 100,
+            _ => {}
+        }
+    }
+    log.assert_dropped([]);
+    assert_eq!(ids(&taken), vec![0, 3, 6]);
+    drop(taken);
+    for item in v.removable_iter() {
+        if item.get().id > 100 {
+            item.remove();
+        }
+    }
+    log.assert_dropped([0, 3, 6, 101, 104, 107]);
+    assert_eq!(ids(&v), vec![2, 5]);
+}
+
+#[test]
+fn remove_and_continue_twice_through_one_item() {
+    let log = DropLog::default();
+    let mut v = log.make(0..3);
+    let mut iter = v.removable_iter_mut();
+    let mut item = iter.next().unwrap();
+    assert_eq!(item.remove_and_continue().map(|t| t.id), Some(2));
+    assert_eq!(item.remove_and_continue().map(|t| t.id), Some(1));
+    assert!(item.remove_and_continue().is_none());
+    // the item is gone, removing it again must not drop anything twice
+    assert!(catch_unwind(AssertUnwindSafe(|| item.remove_and_continue().is_none())).is_err());
+    drop(item);
+    assert!(iter.next().is_none());
+    drop(iter);
+    assert!(v.is_empty());
+    log.assert_dropped(0..3);
+}
+
+#[test]
+fn ordered_and_pairwise_passes() {
+    let log = DropLog::default();
+    let mut v = log.make(0..10);
+    let mut iter = v.ordered_removable_iter();
+    for _ in 0..5 {
+        let item = iter.next().unwrap();
+        if item.get().id % 2 == 0 {
+            item.remove();
+        }
+    }
+    // stopping early shifts the unvisited tail
+    drop(iter);
+    log.assert_dropped([0, 2, 4]);
+    assert_eq!(v.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 3, 5, 6, 7, 8, 9]);
+
+    for pair in v.pairwise_merge_iter() {
+        if pair.second().id > 6 {
+            pair.merge(|_, second| drop(second));
+        }
+    }
+    log.assert_dropped([0, 2, 4, 7, 8, 9]);
+    assert_eq!(ids(&v), vec![1, 3, 5, 6]);
+}
+
+#[test]
+fn panicking_drop_during_removal() {
+    let log = DropLog::default();
+    let mut v = log.make(0..5);
+    v[1].panic_on_drop = true;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        for item in v.removable_iter() {
+            if item.get().id % 2 == 1 {
+                item.remove();
+            }
+        }
+    }));
+    assert!(result.is_err());
+    // the panicking element was taken out of the vector before its drop
+    log.assert_dropped([1]);
+    assert_eq!(ids(&v), vec![0, 2, 3, 4]);
+    drop(v);
+    log.assert_dropped(0..5);
+}
+
+#[test]
+fn panicking_drop_in_ordered_pass() {
+    let log = DropLog::default();
+    let mut v = log.make(0..5);
+    v[2].panic_on_drop = true;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        for item in v.ordered_removable_iter() {
+            if item.get().id >= 1 {
+                item.remove();
+            }
+        }
+    }));
+    assert!(result.is_err());
+    let mut kept = v.iter().map(|t| t.id).collect::<Vec<_>>();
+    kept.sort();
+    drop(v);
+    let mut all = log.dropped.borrow().clone();
+    all.sort();
+    // every element is dropped exactly once, whether by the pass or with the vector
+    assert_eq!(all, (0..5).collect::<Vec<_>>());
+    assert!(kept.contains(&0));
+}
+
+#[test]
+fn confirm_and_cancel() {
+    let log = DropLog::default();
+    let mut v = log.make(0..6);
+    let mut confirm = v.removable_confirm_iter();
+    for item in confirm.iter() {
+        if item.get().id < 3 {
+            item.remove();
+        }
+    }
+    confirm.cancel_removals();
+    log.assert_dropped([]);
+    assert_eq!(ids(&v), (0..6).collect::<Vec<_>>());
+
+    let mut confirm = v.removable_confirm_iter();
+    for item in confirm.iter() {
+        if item.get().id < 3 {
+            item.remove();
+        }
+    }
+    confirm.confirm_removals();
+    log.assert_dropped(0..3);
+    assert_eq!(ids(&v), vec![3, 4, 5]);
+}
+
+#[test]
+#[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+fn items_escaping_their_pass() {
+    let log = DropLog::default();
+    let mut v = log.make(0..4);
+    let mut escaped = None;
+    for item in v.takeable_iter() {
+        if escaped.is_none() {
+            escaped = Some(item);
+        }
+    }
+    let escaped = escaped.unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| escaped.get().id)).is_err());
+    drop(escaped);
+
+    // an item must not be used once its pass is confirmed
+    let mut confirm = v.removable_confirm_iter();
+    let last = confirm.iter().nth(3).unwrap();
+    confirm.confirm_removals();
+    assert!(catch_unwind(AssertUnwindSafe(|| last.get().id)).is_err());
+    drop(last);
+    log.assert_dropped([]);
+    assert_eq!(ids(&v), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn drains_and_put_back() {
+    let log = DropLog::default();
+    let mut v = log.make(0..6);
+    let mut drain = v.drain_unordered();
+    assert_eq!(drain.next().map(|t| t.id), Some(5));
+    drop(drain);
+    log.assert_dropped(0..6);
+    assert!(v.is_empty());
+
+    let log = DropLog::default();
+    let mut v = log.make(0..6);
+    let kept = v
+        .drain_unless(|t| if t.id % 2 == 0 { DrainAction::Yield(t) } else { DrainAction::Keep(t) })
+        .take(2)
+        .map(|t| t.id)
+        .collect::<Vec<_>>();
+    assert_eq!(kept.len(), 2);
+    log.assert_dropped(kept.iter().copied());
+
+    let log = DropLog::default();
+    let mut iter = log.make(0..5).into_put_back_iter();
+    let first = iter.next().unwrap();
+    let second = iter.next().unwrap();
+    iter.put_back(second);
+    drop(first);
+    let v = iter.finish();
+    log.assert_dropped([0]);
+    assert_eq!(v.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn zero_sized_elements() {
+    ZST_DROPS.with(|drops| drops.set(0));
+    let mut v = (0..6).map(|_| Zst).collect::<Vec<_>>();
+    let mut visited = 0;
+    let mut taken = Vec::new();
+    for item in v.takeable_iter() {
+        visited += 1;
+        if visited % 2 == 0 {
+            taken.push(item.take());
+        }
+    }
+    assert_eq!(visited, 6);
+    assert_eq!((v.len(), taken.len()), (3, 3));
+    drop(taken);
+    for item in v.ordered_removable_iter() {
+        item.remove();
+    }
+    assert!(v.is_empty());
+    assert_eq!(ZST_DROPS.with(Cell::get), 6);
+}
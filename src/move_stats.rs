@@ -0,0 +1,113 @@
+//! Counting of the element moves done by the passes, for the `move-stats` feature.
+//!
+//! The swap removing passes count the element swapped into the place of a removed one, the
+//! order preserving passes count every element shifted down, and the confirm sessions count
+//! both elements of the swap which marks an element. The counters are per thread, so a pass is
+//! measured by running it in [`measure`].
+//!
+//! The counts are of the moves inside the vector, taking an element out of it is not counted.
+
+use std::cell::Cell;
+use std::mem;
+
+/// The element moves done on this thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    /// The number of moved elements.
+    pub elements: u64,
+    /// The number of moved bytes, the size of the moved elements.
+    pub bytes: u64,
+}
+
+thread_local! {
+    /// The moves done by this thread since the last reset.
+    static MOVES: Cell<MoveStats> = const { Cell::new(MoveStats { elements: 0, bytes: 0 }) };
+}
+
+/// Records `elements` moved elements of type `T`.
+pub(crate) fn record<T>(elements: usize) {
+    MOVES.with(|moves| {
+        let mut stats = moves.get();
+        stats.elements += elements as u64;
+        stats.bytes += (elements * mem::size_of::<T>()) as u64;
+        moves.set(stats);
+    });
+}
+
+/// Returns the moves done by this thread since the last [`reset`].
+pub fn current() -> MoveStats {
+    MOVES.with(Cell::get)
+}
+
+/// Resets the counters of this thread and returns the moves counted so far.
+pub fn reset() -> MoveStats {
+    MOVES.with(|moves| moves.take())
+}
+
+/// Runs `f` and returns its result with the moves it did on this thread.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::move_stats;
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = (0..100u64).collect::<Vec<_>>();
+/// let (_, stats) = move_stats::measure(|| {
+///     for item in numbers.removable_iter() {
+///         if *item.get() < 10 {
+///             item.remove();
+///         }
+///     }
+/// });
+/// // every removal swapped in the last element
+/// assert_eq!(stats.elements, 10);
+/// assert_eq!(stats.bytes, 80);
+/// ```
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, MoveStats) {
+    let before = current();
+    let result = f();
+    let after = current();
+    let stats = MoveStats {
+        elements: after.elements - before.elements,
+        bytes: after.bytes - before.bytes,
+    };
+    (result, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::measure;
+    use crate::prelude::{InplaceVector, RemovableConfirmIterator, RemovableItem};
+
+    #[test]
+    fn test_moves_per_pass() {
+        let mut v = (0..10u32).collect::<Vec<_>>();
+        let (_, stats) = measure(|| {
+            for item in v.ordered_removable_iter() {
+                if *item.get() == 2 {
+                    item.remove();
+                }
+            }
+        });
+        // 3..10 shifted down
+        assert_eq!((stats.elements, stats.bytes), (7, 28));
+
+        let (_, stats) = measure(|| {
+            let mut session = v.removable_confirm_iter();
+            for item in session.iter() {
+                if *item.get() == 0 {
+                    item.remove();
+                }
+            }
+            session.confirm_removals();
+        });
+        // 0 swapped with 9
+        assert_eq!(stats.elements, 2);
+
+        let (_, stats) = measure(|| v.removable_iter().for_each(|item| item.remove()));
+        // the last element is swapped into the place of the removed one until it is alone
+        assert_eq!(stats.elements, 7);
+        assert!(v.is_empty());
+    }
+}
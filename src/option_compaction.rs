@@ -0,0 +1,97 @@
+//! Compaction of `Option<T>` slot vectors.
+
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use crate::prelude::{InplaceVector, RemovableItem};
+
+/// A trait for vectors of optional slots, which removes the empty slots in place.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut slots = vec![Some(1), None, Some(3), None];
+/// assert_eq!(slots.compact_options(), 2);
+/// assert_eq!(slots, vec![Some(1), Some(3)]);
+///
+/// let slots = vec![None, Some(Box::new(2)), Some(Box::new(1))];
+/// assert_eq!(slots.into_compacted(), vec![Box::new(1), Box::new(2)]);
+/// ```
+pub trait OptionVector<T> {
+    /// Removes the `None`s and returns how many were removed.
+    ///
+    /// The removal swaps with the last element, so the order of the remaining slots is not
+    /// preserved.
+    fn compact_options(&mut self) -> usize;
+
+    /// Removes the `None`s and unwraps the remaining values.
+    ///
+    /// When `Option<T>` has the same layout as `T` (e.g. `Box`, references, `NonZero*`), the
+    /// values are unwrapped in place and the allocation is reused, otherwise they are collected
+    /// into a new vector.
+    fn into_compacted(self) -> Vec<T>;
+}
+
+impl<T> OptionVector<T> for Vec<Option<T>> {
+    fn compact_options(&mut self) -> usize {
+        let len = self.len();
+        for item in self.removable_iter() {
+            if item.get().is_none() {
+                item.remove();
+            }
+        }
+        len - self.len()
+    }
+
+    fn into_compacted(mut self) -> Vec<T> {
+        self.compact_options();
+        if Layout::new::<Option<T>>() != Layout::new::<T>() {
+            return self.into_iter().flatten().collect();
+        }
+        let mut v = ManuallyDrop::new(self);
+        let (data, len, capacity) = (v.as_mut_ptr(), v.len(), v.capacity());
+        unsafe {
+            for index in 0..len {
+                // all the slots are `Some` after the compaction
+                let value = ptr::read(data.add(index)).unwrap_unchecked();
+                ptr::write(data.add(index).cast::<T>(), value);
+            }
+            Vec::from_raw_parts(data.cast::<T>(), len, capacity)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use crate::prelude::OptionVector;
+
+    #[test]
+    fn test_compact_options() {
+        let mut a = vec![None, Some(1), None, None, Some(2), None];
+        assert_eq!(a.compact_options(), 4);
+        assert_eq!(a.len(), 2);
+        assert!(a.iter().all(Option::is_some));
+        assert_eq!(a.compact_options(), 0);
+    }
+
+    #[test]
+    fn test_into_compacted_reuses_allocation() {
+        let a = vec![NonZeroU32::new(1), None, NonZeroU32::new(2)];
+        let capacity = a.capacity();
+        let pointer = a.as_ptr() as usize;
+        let b = a.into_compacted();
+        assert_eq!(b.iter().map(|v| v.get()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!((b.as_ptr() as usize, b.capacity()), (pointer, capacity));
+    }
+
+    #[test]
+    fn test_into_compacted_other_layout() {
+        let a = vec![Some(1u8), None, Some(3), Some(4)];
+        let mut b = a.into_compacted();
+        b.sort();
+        assert_eq!(b, vec![1, 3, 4]);
+    }
+}
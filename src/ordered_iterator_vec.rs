@@ -0,0 +1,385 @@
+//! Order preserving removal, for vectors whose order matters (e.g. sorted vectors).
+//!
+//! The removed elements are dropped in place and the kept elements are shifted down as the
+//! iteration moves on, like `Vec::retain` does. The unvisited tail is shifted once, when the
+//! iterator is dropped.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::{ptr, slice};
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{check_detached, is_detached, Epoch, ItemEpoch};
+use crate::inplace_iterator::InplaceIterator;
+use crate::prelude::{RemovableItem, TakeableItem};
+use crate::removable_iterator::RemovableItemMut;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// An iterator which removes the elements without changing the order of the others.
+pub struct InplaceOrderedVecIterator<'a, T> {
+    /// The underlying vector, its length is 0 while iterating.
+    vector: &'a mut Vec<T>,
+    /// A raw pointer to the vector data for unsafe access.
+    data: *mut T,
+    /// The length of the vector before the iteration.
+    len: usize,
+    /// The index of the next element to visit.
+    read: usize,
+    /// The number of removed elements, the kept elements are shifted down by this much.
+    deleted: usize,
+    /// A flag indicating whether the last visited item has been removed.
+    removed: bool,
+    /// A flag indicating whether the last visited item is not yet moved to its place.
+    pending: bool,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T> InplaceOrderedVecIterator<'a, T> {
+    pub fn new(v: &'a mut Vec<T>) -> Self {
+        Self::new_from(v, 0)
+    }
+
+    /// Creates an iterator which starts at `start`, the elements before it are not visited.
+    pub fn new_from(v: &'a mut Vec<T>, start: usize) -> Self {
+        let len = v.len();
+        let start = start.min(len);
+        // if the iterator is leaked, the elements are leaked too instead of being dropped twice
+        unsafe { v.set_len(0) };
+        Self {
+            data: v.as_mut_ptr(),
+            vector: v,
+            len,
+            read: start,
+            deleted: 0,
+            removed: false,
+            pending: false,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+
+    /// Moves the last visited element to its place, unless it was removed.
+    fn settle_last(&mut self) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+        if self.removed {
+            self.removed = false;
+            self.deleted += 1;
+        } else if self.deleted > 0 {
+            let last = self.read - 1;
+            unsafe { ptr::copy_nonoverlapping(self.data.add(last), self.data.add(last - self.deleted), 1) };
+            #[cfg(feature = "move-stats")]
+            crate::move_stats::record::<T>(1);
+        }
+    }
+}
+
+impl<'a, T> Drop for InplaceOrderedVecIterator<'a, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        // the tail cannot be moved back into a vector changed behind the iterator, leak it
+        #[cfg(feature = "loop-lifetime-guard")]
+        if !is_detached(self.vector, self.data) {
+            return;
+        }
+        self.settle_last();
+        unsafe {
+            if self.deleted > 0 {
+                ptr::copy(self.data.add(self.read), self.data.add(self.read - self.deleted), self.len - self.read);
+                #[cfg(feature = "move-stats")]
+                crate::move_stats::record::<T>(self.len - self.read);
+            }
+            self.vector.set_len(self.len - self.deleted);
+        }
+    }
+}
+
+impl<'a, T> Iterator for InplaceOrderedVecIterator<'a, T> {
+    type Item = InplaceOrderedVecItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        #[cfg(feature = "loop-lifetime-guard")]
+        check_detached(self.vector, self.data);
+        self.settle_last();
+        if self.read >= self.len {
+            return None;
+        }
+        let value = unsafe { self.data.add(self.read) };
+        self.read += 1;
+        self.pending = true;
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(InplaceOrderedVecItem {
+            _lifetime_guard: PhantomData,
+            value,
+            removed: &mut self.removed,
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.read;
+        (remaining, Some(remaining))
+    }
+
+    fn count(self) -> usize {
+        self.len - self.read
+    }
+}
+
+impl<'a, T> FusedIterator for InplaceOrderedVecIterator<'a, T> {}
+
+impl<'a, T> InplaceIterator<T> for InplaceOrderedVecIterator<'a, T> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        unsafe { slice::from_raw_parts(self.data.add(self.read), self.len - self.read) }.iter()
+    }
+}
+
+/// An item of the order preserving iterator, removing or taking it keeps the order of the
+/// other elements.
+pub struct InplaceOrderedVecItem<'a, T> {
+    /// This ties the item to the borrow of the vector by the iterator.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
+    /// A raw pointer to the element.
+    value: *mut T,
+    /// An indicator to the iterator that we have removed the item
+    removed: *mut bool,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> InplaceOrderedVecItem<'a, T> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    pub(crate) fn take_value(self) -> T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            *self.removed = true;
+            ptr::read(self.value)
+        }
+    }
+
+    pub(crate) fn get_value(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &*self.value }
+    }
+
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<'a, T> RemovableItem<T> for InplaceOrderedVecItem<'a, T> {
+    /// Removes the current item, the order of the other elements is preserved.
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> RemovableItemMut<T> for InplaceOrderedVecItem<'a, T> {
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+impl<'a, T> TakeableItem<T> for InplaceOrderedVecItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> TakeableItemMut<T> for InplaceOrderedVecItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+/// A trait for removing ranges of keys from sorted vectors.
+pub trait SortedVector<T> {
+    /// Removes the elements whose key is in `range` and returns how many were removed.
+    ///
+    /// The vector must be sorted by `key`. The affected range is found by binary search and
+    /// removed with a single ordered compaction, so the vector stays sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut events = vec![(1, "a"), (3, "b"), (4, "c"), (7, "d")];
+    /// assert_eq!(events.remove_sorted_where_key_in(2..5, |e| e.0), 2);
+    /// assert_eq!(events, vec![(1, "a"), (7, "d")]);
+    /// ```
+    fn remove_sorted_where_key_in<K, R, F>(&mut self, range: R, key: F) -> usize
+    where
+        K: Ord,
+        R: RangeBounds<K>,
+        F: FnMut(&T) -> K;
+}
+
+impl<T> SortedVector<T> for Vec<T> {
+    fn remove_sorted_where_key_in<K, R, F>(&mut self, range: R, mut key: F) -> usize
+    where
+        K: Ord,
+        R: RangeBounds<K>,
+        F: FnMut(&T) -> K,
+    {
+        let start = self.partition_point(|v| match range.start_bound() {
+            Bound::Included(start) => key(v) < *start,
+            Bound::Excluded(start) => key(v) <= *start,
+            Bound::Unbounded => false,
+        });
+        let end = self.partition_point(|v| match range.end_bound() {
+            Bound::Included(end) => key(v) <= *end,
+            Bound::Excluded(end) => key(v) < *end,
+            Bound::Unbounded => true,
+        });
+        let end = end.max(start);
+        self.drain(start..end);
+        end - start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::{InplaceIterator, InplaceVector, RemovableItem, RemovableItemMut, SortedVector};
+
+    #[test]
+    fn test_ordered_removal() {
+        let mut a = vec![1, 2, 3, 4, 5, 6, 7];
+        for item in a.ordered_removable_iter() {
+            if *item.get() % 3 != 0 {
+                item.remove();
+            }
+        }
+        assert_eq!(a, vec![3, 6]);
+    }
+
+    #[test]
+    fn test_ordered_early_exit_keeps_tail() {
+        let mut a = vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+        let mut iter = a.ordered_removable_iter();
+        iter.next().unwrap().remove();
+        assert_eq!(iter.peek().map(String::as_str), Some("b"));
+        iter.next();
+        assert_eq!(iter.lookahead().count(), 2);
+        drop(iter);
+        assert_eq!(a, vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_retain_sorted_skips_sorted_survivors() {
+        let mut a = vec![1, 4, 3, 6, 5, 8];
+        let mut keys = 0;
+        a.retain_sorted_by_key(|v| v % 2 == 1, |v| {
+            keys += 1;
+            *v
+        });
+        // the survivors were in order, each key is computed once by the compaction pass
+        assert_eq!((a, keys), (vec![1, 3, 5], 3));
+
+        let mut b = vec![9, 4, 8, 1, 7];
+        assert_eq!(b.retain_sorted_by_key(|v| *v != 4, |v| *v), 1);
+        assert_eq!(b, vec![1, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_ordered_mut() {
+        let mut a = vec![1, 2, 3, 4];
+        for mut item in a.ordered_removable_iter_mut() {
+            if *item.get() < 3 {
+                item.remove();
+            } else {
+                *item.get_mut() *= 10;
+            }
+        }
+        assert_eq!(a, vec![30, 40]);
+    }
+
+    #[test]
+    fn test_ordered_leak_does_not_double_drop() {
+        let mut a = vec!["a".to_string(), "b".to_string()];
+        let mut iter = a.ordered_removable_iter();
+        iter.next().unwrap().remove();
+        std::mem::forget(iter);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_remove_sorted_where_key_in() {
+        let mut a = vec![1, 2, 2, 3, 5, 8];
+        assert_eq!(a.remove_sorted_where_key_in(2..=3, |v| *v), 3);
+        assert_eq!(a, vec![1, 5, 8]);
+        assert_eq!(a.remove_sorted_where_key_in(6.., |v| *v), 1);
+        assert_eq!(a.remove_sorted_where_key_in(6..=6, |v| *v), 0);
+        assert_eq!(a.remove_sorted_where_key_in(.., |v| *v), 2);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[cfg_attr(miri, ignore)]
+    fn test_rejects_growth_during_the_pass() {
+        let mut a = vec![1, 2, 3];
+        let alias = &mut a as *mut Vec<i32>;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut iter = a.ordered_removable_iter();
+            iter.next().unwrap().remove();
+            // simulates a bug growing the vector into a new allocation behind the iterator
+            unsafe { (*alias).reserve(100) };
+            unsafe { (*alias).push(4) };
+            iter.next();
+        }));
+        assert!(result.is_err());
+        // the elements of the pass are leaked rather than moved into the changed vector
+        assert_eq!(a, vec![4]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Pairwise iteration over adjacent elements, with merging of the pairs.
+//!
+//! Like the order preserving iterator, the vector is compacted as the iteration moves on: a
+//! merged element is moved out of the vector into the merge closure, the kept elements are
+//! shifted down and the unvisited tail is shifted once, when the iterator is dropped.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{check_detached, is_detached, Epoch, ItemEpoch};
+
+/// An iterator over the pairs of adjacent elements, which allows merging the second element
+/// of a pair into the first one.
+///
+/// After a merge, the next pair is the merged element and the element following the merged
+/// one, so runs of elements can be coalesced into one.
+pub struct PairwiseVecIterator<'a, T> {
+    /// The underlying vector, its length is 0 while iterating.
+    vector: &'a mut Vec<T>,
+    /// A raw pointer to the vector data for unsafe access.
+    data: *mut T,
+    /// The length of the vector before the iteration.
+    len: usize,
+    /// The index of the first element of the next pair, it is already at its place.
+    write: usize,
+    /// The index of the second element of the next pair.
+    read: usize,
+    /// A flag indicating whether the last pair has been merged.
+    merged: bool,
+    /// A flag indicating whether the last pair is not yet settled.
+    pending: bool,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T> PairwiseVecIterator<'a, T> {
+    pub fn new(v: &'a mut Vec<T>) -> Self {
+        let len = v.len();
+        // if the iterator is leaked, the elements are leaked too instead of being dropped twice
+        unsafe { v.set_len(0) };
+        Self {
+            data: v.as_mut_ptr(),
+            vector: v,
+            len,
+            write: 0,
+            read: 1,
+            merged: false,
+            pending: false,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+
+    /// Moves past the last pair: a merged second element is gone, otherwise the second
+    /// element becomes the first of the next pair.
+    fn settle_last(&mut self) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+        if !std::mem::take(&mut self.merged) {
+            self.write += 1;
+            if self.write != self.read {
+                unsafe { ptr::copy_nonoverlapping(self.data.add(self.read), self.data.add(self.write), 1) };
+            }
+        }
+        self.read += 1;
+    }
+}
+
+impl<'a, T> Drop for PairwiseVecIterator<'a, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        // the tail cannot be moved back into a vector changed behind the iterator, leak it
+        #[cfg(feature = "loop-lifetime-guard")]
+        if !is_detached(self.vector, self.data) {
+            return;
+        }
+        self.settle_last();
+        if self.len == 0 {
+            return;
+        }
+        let rest = self.len - self.read;
+        unsafe {
+            ptr::copy(self.data.add(self.read), self.data.add(self.write + 1), rest);
+            self.vector.set_len(self.write + 1 + rest);
+        }
+    }
+}
+
+impl<'a, T> Iterator for PairwiseVecIterator<'a, T> {
+    type Item = MergeablePair<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        #[cfg(feature = "loop-lifetime-guard")]
+        check_detached(self.vector, self.data);
+        self.settle_last();
+        if self.read >= self.len {
+            return None;
+        }
+        self.pending = true;
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(MergeablePair {
+            _lifetime_guard: PhantomData,
+            first: unsafe { self.data.add(self.write) },
+            second: unsafe { self.data.add(self.read) },
+            merged: &mut self.merged,
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len.saturating_sub(self.read + usize::from(self.pending));
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T> FusedIterator for PairwiseVecIterator<'a, T> {}
+
+/// A pair of adjacent elements, the second one can be merged into the first one.
+///
+/// The pair borrows the vector, so it cannot be kept after the vector is changed or dropped:
+///
+/// ```compile_fail
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3];
+/// let mut kept = None;
+/// for pair in numbers.pairwise_merge_iter() {
+///     kept = Some(pair);
+/// }
+/// drop(numbers);
+/// kept.unwrap().first();
+/// ```
+pub struct MergeablePair<'a, T> {
+    /// This ties the pair to the borrow of the vector by the iterator.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
+    /// A raw pointer to the first element.
+    first: *mut T,
+    /// A raw pointer to the second element.
+    second: *mut T,
+    /// An indicator to the iterator that we have merged the pair
+    merged: *mut bool,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> MergeablePair<'a, T> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    /// Returns a reference to the first element.
+    pub fn first(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &*self.first }
+    }
+
+    /// Returns a reference to the second element.
+    pub fn second(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &*self.second }
+    }
+
+    /// Returns a mutable reference to the first element.
+    pub fn first_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &mut *self.first }
+    }
+
+    /// Returns a mutable reference to the second element.
+    pub fn second_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &mut *self.second }
+    }
+
+    /// Removes the second element from the vector and lets `f` combine it into the first one.
+    pub fn merge<F>(self, f: F)
+    where
+        F: FnOnce(&mut T, T),
+    {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            // the second element is owned by `f` from now on, even if it panics
+            *self.merged = true;
+            f(&mut *self.first, ptr::read(self.second));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::prelude::InplaceVector;
+
+    #[test]
+    fn test_coalesce_runs() {
+        let mut a = vec![(1, 1), (1, 2), (2, 1), (3, 4), (3, 1), (3, 1)];
+        for pair in a.pairwise_merge_iter() {
+            if pair.first().0 == pair.second().0 {
+                pair.merge(|first, second| first.1 += second.1);
+            }
+        }
+        assert_eq!(a, vec![(1, 3), (2, 1), (3, 6)]);
+    }
+
+    #[test]
+    fn test_early_drop_keeps_tail() {
+        let mut a = vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+        let mut iter = a.pairwise_merge_iter();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        iter.next().unwrap().merge(|first, second| first.push_str(&second));
+        let mut pair = iter.next().unwrap();
+        pair.second_mut().push('!');
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        drop(iter);
+        assert_eq!(a, vec!["ab", "c!", "d"]);
+    }
+
+    #[test]
+    fn test_short_vectors() {
+        let mut a: Vec<i32> = Vec::new();
+        assert_eq!(a.pairwise_merge_iter().count(), 0);
+        let mut a = vec![1];
+        assert_eq!(a.pairwise_merge_iter().count(), 0);
+        assert_eq!(a, vec![1]);
+    }
+
+    #[test]
+    fn test_merge_panic_does_not_double_drop() {
+        let counter = Rc::new(());
+        let mut a = vec![counter.clone(), counter.clone(), counter.clone()];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            for pair in a.pairwise_merge_iter() {
+                pair.merge(|_, _| panic!("merge failed"));
+            }
+        }));
+        assert!(result.is_err());
+        assert_eq!(a.len(), 2);
+        assert_eq!(Rc::strong_count(&counter), 3);
+    }
+}
@@ -0,0 +1,120 @@
+//! Applying a list of operations addressed by index, e.g. decisions computed elsewhere.
+
+use crate::ordered_iterator_vec::InplaceOrderedVecIterator;
+
+/// An operation on the element at an index, see [`ApplyOps::apply_ops`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Op<'a, T> {
+    /// Removes the element.
+    Remove,
+    /// Replaces the element with the value, the old element is dropped.
+    Replace(T),
+    /// Removes the element and stores it in the slot.
+    TakeInto(&'a mut Option<T>),
+}
+
+/// A trait for applying a patch list of operations in a single pass.
+pub trait ApplyOps<T> {
+    /// Applies the operations and returns the number of removed elements.
+    ///
+    /// The indices refer to the positions before any operation is applied, so the caller does
+    /// not need to account for the shifts caused by the removals. The operations can be given
+    /// in any order. The remaining elements keep their order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds or appears more than once. The vector is not
+    /// modified in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut v = vec!['a', 'b', 'c', 'd'];
+    /// let mut taken = None;
+    /// let removed = v.apply_ops([(3, Op::TakeInto(&mut taken)), (0, Op::Remove), (1, Op::Replace('B'))]);
+    /// assert_eq!(removed, 2);
+    /// assert_eq!(v, vec!['B', 'c']);
+    /// assert_eq!(taken, Some('d'));
+    /// ```
+    fn apply_ops<'a, I>(&mut self, ops: I) -> usize
+    where
+        T: 'a,
+        I: IntoIterator<Item = (usize, Op<'a, T>)>;
+}
+
+impl<T> ApplyOps<T> for Vec<T> {
+    fn apply_ops<'a, I>(&mut self, ops: I) -> usize
+    where
+        T: 'a,
+        I: IntoIterator<Item = (usize, Op<'a, T>)>,
+    {
+        let mut ops = ops.into_iter().collect::<Vec<_>>();
+        ops.sort_by_key(|(index, _)| *index);
+        if let Some((last, _)) = ops.last() {
+            assert!(*last < self.len(), "operation index {last} out of bounds");
+        }
+        assert!(ops.windows(2).all(|w| w[0].0 != w[1].0), "more than one operation for an index");
+        let mut ops = ops.into_iter().peekable();
+        let mut removed = 0;
+        for (index, mut item) in InplaceOrderedVecIterator::new(self).enumerate() {
+            let Some((_, op)) = ops.next_if(|(i, _)| *i == index) else {
+                if ops.peek().is_none() {
+                    break;
+                }
+                continue;
+            };
+            match op {
+                Op::Remove => {
+                    item.take_value();
+                    removed += 1;
+                }
+                Op::Replace(value) => *item.get_value_mut() = value,
+                Op::TakeInto(slot) => {
+                    *slot = Some(item.take_value());
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::prelude::{ApplyOps, Op};
+
+    #[test]
+    fn test_apply_ops() {
+        let mut a = (0..8).collect::<Vec<_>>();
+        let mut first = None;
+        let removed = a.apply_ops(vec![(5, Op::Remove), (2, Op::Replace(20)), (1, Op::Remove), (0, Op::TakeInto(&mut first))]);
+        assert_eq!(removed, 3);
+        assert_eq!(first, Some(0));
+        assert_eq!(a, vec![20, 3, 4, 6, 7]);
+        assert_eq!(a.apply_ops([]), 0);
+        assert_eq!(a, vec![20, 3, 4, 6, 7]);
+    }
+
+    #[test]
+    fn test_replace_drops_old() {
+        let old = Rc::new(());
+        let mut a = vec![old.clone()];
+        a.apply_ops([(0, Op::Replace(Rc::new(())))]);
+        assert_eq!(Rc::strong_count(&old), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_index() {
+        vec![1, 2].apply_ops([(0, Op::Remove), (0, Op::Replace(3))]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        vec![1, 2].apply_ops([(2, Op::Remove)]);
+    }
+}
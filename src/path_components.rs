@@ -0,0 +1,228 @@
+//! In-place filtering of the components of a `PathBuf`.
+//!
+//! A path cannot be edited component by component, so the components are split off when the
+//! iteration starts and the path is rebuilt from the kept ones when the iterator is dropped,
+//! if any was removed. The split components are shared by the iterator and its items, so an
+//! item never points into a buffer freed with the iterator.
+
+use std::cell::Cell;
+use std::ffi::{OsStr, OsString};
+use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+
+/// A trait that extends paths with methods for in-place removal of components.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Component, PathBuf};
+/// use inplace_iter::prelude::*;
+///
+/// let mut path = PathBuf::from("uploads/../secret/./file.txt");
+/// for item in path.removable_components() {
+///     if matches!(item.get(), Component::ParentDir | Component::CurDir) || item.as_os_str() == "secret" {
+///         item.remove();
+///     }
+/// }
+/// assert_eq!(path, PathBuf::from("uploads/file.txt"));
+/// ```
+pub trait InplacePath {
+    /// Returns an iterator over the components, which allows removing them during iteration.
+    ///
+    /// The path is rebuilt from the kept components when the iterator is dropped, if any
+    /// component was removed. The rebuilt path is normalized like [`Path::components`], e.g. a
+    /// `.` inside the path and a trailing separator are gone. A pass which removes nothing
+    /// leaves the path untouched.
+    fn removable_components(&mut self) -> RemovableComponents<'_>;
+}
+
+impl InplacePath for PathBuf {
+    fn removable_components(&mut self) -> RemovableComponents<'_> {
+        RemovableComponents::new(self)
+    }
+}
+
+/// An iterator over the components of a path, which allows removing them.
+pub struct RemovableComponents<'a> {
+    /// The path, it is rebuilt when the iterator is dropped.
+    path: &'a mut PathBuf,
+    /// The components of the path and whether they are kept, shared with the items.
+    components: Rc<[(OsString, Cell<bool>)]>,
+    /// The index of the next component to visit.
+    index: usize,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a> RemovableComponents<'a> {
+    fn new(path: &'a mut PathBuf) -> Self {
+        let components = path.components().map(|c| (c.as_os_str().to_owned(), Cell::new(true))).collect();
+        Self {
+            path,
+            components,
+            index: 0,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+impl<'a> Drop for RemovableComponents<'a> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        if self.components.iter().any(|(_, kept)| !kept.get()) {
+            *self.path = self.components.iter().filter(|(_, kept)| kept.get()).map(|(c, _)| c).collect();
+        }
+    }
+}
+
+impl<'a> Iterator for RemovableComponents<'a> {
+    type Item = RemovableComponent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        if self.index >= self.components.len() {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(RemovableComponent {
+            _lifetime_guard: PhantomData,
+            components: self.components.clone(),
+            index,
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.components.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+/// A component of a path, which can be removed from it.
+///
+/// The component borrows the path, so it cannot be kept after the path is changed or dropped:
+///
+/// ```compile_fail
+/// use std::path::PathBuf;
+/// use inplace_iter::prelude::*;
+///
+/// let mut path = PathBuf::from("a/b");
+/// let mut kept = None;
+/// for item in path.removable_components() {
+///     kept = Some(item);
+/// }
+/// drop(path);
+/// kept.unwrap().as_os_str();
+/// ```
+pub struct RemovableComponent<'a> {
+    /// This ties the component to the borrow of the path by the iterator.
+    _lifetime_guard: PhantomData<&'a mut PathBuf>,
+    /// The components of the path shared with the iterator.
+    components: Rc<[(OsString, Cell<bool>)]>,
+    /// The index of the component.
+    index: usize,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a> RemovableComponent<'a> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    /// Returns the component as a raw string.
+    pub fn as_os_str(&self) -> &OsStr {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        &self.components[self.index].0
+    }
+
+    /// Returns the component.
+    pub fn get(&self) -> Component<'_> {
+        // a single component parses back to itself
+        Path::new(self.as_os_str()).components().next().unwrap()
+    }
+
+    /// Removes the component from the path.
+    ///
+    /// Without the `loop-lifetime-guard` feature, removing a component after the iterator is
+    /// dropped has no effect, the path is already rebuilt.
+    pub fn remove(self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        self.components[self.index].1.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Component, PathBuf};
+    use crate::prelude::InplacePath;
+
+    #[test]
+    fn test_remove_parent_dirs() {
+        let mut path = PathBuf::from("/srv/../www/../../index.html");
+        for item in path.removable_components() {
+            if item.get() == Component::ParentDir {
+                item.remove();
+            }
+        }
+        assert_eq!(path, PathBuf::from("/srv/www/index.html"));
+    }
+
+    #[test]
+    fn test_remove_root_makes_relative() {
+        let mut path = PathBuf::from("/etc/passwd");
+        let mut iter = path.removable_components();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        iter.next().unwrap().remove();
+        drop(iter);
+        assert_eq!(path, PathBuf::from("etc/passwd"));
+    }
+
+    #[test]
+    fn test_no_removal_keeps_the_path() {
+        let mut path = PathBuf::from("a/./b/");
+        for item in path.removable_components() {
+            assert_ne!(item.get(), Component::CurDir);
+        }
+        assert_eq!(path.as_os_str(), "a/./b/");
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let mut path = PathBuf::from("a/b");
+        path.removable_components().for_each(|item| item.remove());
+        assert_eq!(path, PathBuf::new());
+    }
+
+    #[test]
+    fn test_component_kept_after_the_iterator() {
+        let mut path = PathBuf::from("a/b");
+        let mut iter = path.removable_components();
+        let item = iter.next().unwrap();
+        drop(iter);
+        #[cfg(not(feature = "loop-lifetime-guard"))]
+        assert_eq!(item.as_os_str(), "a");
+        drop(item);
+        assert_eq!(path, PathBuf::from("a/b"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Composable predicates for the removal passes, which can tell which clause matched.
+
+use std::collections::BTreeMap;
+
+/// The result of testing a value with a [`Predicate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// Whether the value matches.
+    pub matched: bool,
+    /// The label of the innermost labeled clause which decided the match, `None` if the value
+    /// does not match or the deciding clause is not labeled.
+    pub cause: Option<&'static str>,
+}
+
+impl Outcome {
+    const NO: Outcome = Outcome { matched: false, cause: None };
+}
+
+/// The statistics of a labeled clause of a [`Predicate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClauseStats {
+    /// The label of the clause.
+    pub label: &'static str,
+    /// The number of values the clause was evaluated for, short-circuited evaluations are not
+    /// counted.
+    pub evaluated: usize,
+    /// The number of values the clause matched.
+    pub matched: usize,
+}
+
+/// The number of removals per cause, the labels of the clauses which matched the removed
+/// elements.
+///
+/// Returned by [`InplaceCollection::remove_items_matching`](crate::prelude::InplaceCollection::remove_items_matching),
+/// or filled by [`record`](CauseHistogram::record) in a custom pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CauseHistogram {
+    /// The number of removals per label.
+    labeled: BTreeMap<&'static str, usize>,
+    /// The number of removals matched by no labeled clause.
+    unlabeled: usize,
+}
+
+impl CauseHistogram {
+    /// Counts a removal with the cause of its [`Outcome`].
+    pub fn record(&mut self, cause: Option<&'static str>) {
+        match cause {
+            Some(label) => *self.labeled.entry(label).or_default() += 1,
+            None => self.unlabeled += 1,
+        }
+    }
+
+    /// Returns the number of removals caused by the clause labeled `label`.
+    pub fn get(&self, label: &str) -> usize {
+        self.labeled.get(label).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of removals matched by no labeled clause.
+    pub fn unlabeled(&self) -> usize {
+        self.unlabeled
+    }
+
+    /// Returns the total number of removals.
+    pub fn total(&self) -> usize {
+        self.unlabeled + self.labeled.values().sum::<usize>()
+    }
+
+    /// Returns the labels with their numbers of removals, ordered by the labels.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.labeled.iter().map(|(label, count)| (*label, *count))
+    }
+}
+
+/// A predicate over the elements, which can be combined with others into a cleanup policy.
+///
+/// Any `FnMut(&T) -> bool` closure is a predicate. The combinators short-circuit like `&&` and
+/// `||`, and the clauses wrapped in [`labeled`](Predicate::labeled) count their evaluations and
+/// name the cause of a match.
+///
+/// The removal passes taking a closure accept a predicate through [`as_fn`](Predicate::as_fn).
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut sessions = vec![(1, 900), (2, 10), (3, 5000), (4, 20)];
+/// let mut policy = (|s: &(u32, u32)| s.1 > 3600)
+///     .labeled("expired")
+///     .or((|s: &(u32, u32)| s.0 % 2 == 0).labeled("even"));
+/// assert_eq!(policy.evaluate(&(3, 5000)).cause, Some("expired"));
+///
+/// let removed = sessions.remove_items_where(policy.as_fn());
+/// assert_eq!(removed, 3);
+/// assert_eq!(sessions, vec![(1, 900)]);
+/// let stats = policy.clause_stats();
+/// assert_eq!((stats[0].label, stats[0].evaluated, stats[0].matched), ("expired", 5, 2));
+/// assert_eq!((stats[1].label, stats[1].evaluated, stats[1].matched), ("even", 3, 2));
+/// ```
+pub trait Predicate<T> {
+    /// Tests `value`, returning whether it matches and the cause of the match.
+    fn evaluate(&mut self, value: &T) -> Outcome;
+
+    /// Passes the statistics of the labeled clauses to `f`, in the order they are written.
+    fn visit_clauses(&self, f: &mut dyn FnMut(&ClauseStats)) {
+        let _ = f;
+    }
+
+    /// Returns `true` if `value` matches.
+    fn test(&mut self, value: &T) -> bool {
+        self.evaluate(value).matched
+    }
+
+    /// Returns the statistics of the labeled clauses, in the order they are written.
+    fn clause_stats(&self) -> Vec<ClauseStats> {
+        let mut stats = Vec::new();
+        self.visit_clauses(&mut |clause| stats.push(*clause));
+        stats
+    }
+
+    /// Matches if both predicates match, `other` is only evaluated if `self` matches.
+    fn and<P>(self, other: P) -> And<Self, P>
+    where
+        Self: Sized,
+        P: Predicate<T>,
+    {
+        And(self, other)
+    }
+
+    /// Matches if either predicate matches, `other` is only evaluated if `self` does not.
+    fn or<P>(self, other: P) -> Or<Self, P>
+    where
+        Self: Sized,
+        P: Predicate<T>,
+    {
+        Or(self, other)
+    }
+
+    /// Matches if the predicate does not.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+
+    /// Names the predicate as a cause of the matches and counts its evaluations.
+    fn labeled(self, label: &'static str) -> Labeled<Self>
+    where
+        Self: Sized,
+    {
+        Labeled {
+            inner: self,
+            stats: ClauseStats { label, evaluated: 0, matched: 0 },
+        }
+    }
+
+    /// Returns a closure testing the values, for the passes taking a `FnMut(&T) -> bool`.
+    fn as_fn<'s>(&'s mut self) -> impl FnMut(&T) -> bool + 's
+    where
+        Self: Sized,
+        T: 's,
+    {
+        move |value| self.test(value)
+    }
+}
+
+impl<T, F> Predicate<T> for F
+where
+    F: FnMut(&T) -> bool,
+{
+    fn evaluate(&mut self, value: &T) -> Outcome {
+        Outcome { matched: self(value), cause: None }
+    }
+}
+
+/// A predicate matching if both predicates match, created by [`Predicate::and`].
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<T, A: Predicate<T>, B: Predicate<T>> Predicate<T> for And<A, B> {
+    fn evaluate(&mut self, value: &T) -> Outcome {
+        let first = self.0.evaluate(value);
+        if !first.matched {
+            return Outcome::NO;
+        }
+        let second = self.1.evaluate(value);
+        if !second.matched {
+            return Outcome::NO;
+        }
+        Outcome { matched: true, cause: second.cause.or(first.cause) }
+    }
+
+    fn visit_clauses(&self, f: &mut dyn FnMut(&ClauseStats)) {
+        self.0.visit_clauses(f);
+        self.1.visit_clauses(f);
+    }
+}
+
+/// A predicate matching if either predicate matches, created by [`Predicate::or`].
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<T, A: Predicate<T>, B: Predicate<T>> Predicate<T> for Or<A, B> {
+    fn evaluate(&mut self, value: &T) -> Outcome {
+        let first = self.0.evaluate(value);
+        if first.matched {
+            return first;
+        }
+        self.1.evaluate(value)
+    }
+
+    fn visit_clauses(&self, f: &mut dyn FnMut(&ClauseStats)) {
+        self.0.visit_clauses(f);
+        self.1.visit_clauses(f);
+    }
+}
+
+/// A predicate matching if the inner one does not, created by [`Predicate::not`].
+#[derive(Debug, Clone)]
+pub struct Not<P>(P);
+
+impl<T, P: Predicate<T>> Predicate<T> for Not<P> {
+    fn evaluate(&mut self, value: &T) -> Outcome {
+        // the labels of the inner clauses describe the opposite, they are no cause
+        Outcome { matched: !self.0.evaluate(value).matched, cause: None }
+    }
+
+    fn visit_clauses(&self, f: &mut dyn FnMut(&ClauseStats)) {
+        self.0.visit_clauses(f);
+    }
+}
+
+/// A labeled predicate counting its evaluations, created by [`Predicate::labeled`].
+#[derive(Debug, Clone)]
+pub struct Labeled<P> {
+    inner: P,
+    stats: ClauseStats,
+}
+
+impl<T, P: Predicate<T>> Predicate<T> for Labeled<P> {
+    fn evaluate(&mut self, value: &T) -> Outcome {
+        let outcome = self.inner.evaluate(value);
+        self.stats.evaluated += 1;
+        if !outcome.matched {
+            return Outcome::NO;
+        }
+        self.stats.matched += 1;
+        Outcome { matched: true, cause: outcome.cause.or(Some(self.stats.label)) }
+    }
+
+    fn visit_clauses(&self, f: &mut dyn FnMut(&ClauseStats)) {
+        f(&self.stats);
+        self.inner.visit_clauses(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use crate::prelude::{InplaceCollection, Predicate};
+    use super::Outcome;
+
+    #[test]
+    fn test_causes_and_short_circuit() {
+        let big = |v: &i32| *v > 10;
+        let odd = |v: &i32| v % 2 != 0;
+        let mut p = big.labeled("big").and(odd.labeled("odd")).or((|v: &i32| *v < 0).labeled("negative").not().not());
+
+        assert_eq!(p.evaluate(&11), Outcome { matched: true, cause: Some("odd") });
+        assert_eq!(p.evaluate(&12), Outcome { matched: false, cause: None });
+        // `not` hides the inner labels
+        assert_eq!(p.evaluate(&-1), Outcome { matched: true, cause: None });
+        assert!(!p.test(&3));
+
+        let stats = p.clause_stats().into_iter().map(|s| (s.label, s.evaluated, s.matched)).collect::<Vec<_>>();
+        // `odd` is not evaluated for the values which are not big
+        assert_eq!(stats, vec![("big", 4, 2), ("odd", 2, 1), ("negative", 3, 1)]);
+    }
+
+    #[test]
+    fn test_cause_histogram() {
+        let mut queue = (0..10).collect::<VecDeque<u32>>();
+        let mut policy = (|v: &u32| *v < 2).labeled("low").or(|v: &u32| *v > 7).or((|v: &u32| *v == 5).labeled("five"));
+        let causes = queue.remove_items_matching(&mut policy);
+        assert_eq!(causes.iter().collect::<Vec<_>>(), vec![("five", 1), ("low", 2)]);
+        assert_eq!((causes.unlabeled(), causes.total()), (2, 5));
+        assert_eq!(queue.len(), 5);
+    }
+}
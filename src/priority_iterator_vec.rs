@@ -0,0 +1,256 @@
+//! Removal in priority classes, for schedulers which drain the urgent work first.
+//!
+//! The elements are bucketed by their class once, at the start of the pass, into ascending
+//! runs, and the pass walks the vector from the end. A removal swaps in the last element, which
+//! is already visited, so the unvisited elements keep their places and the classes are visited
+//! from the highest down without a sort.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::slice;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use crate::inplace_iterator::InplaceIterator;
+use crate::inplace_vec_iterator::swap_remove_in_bounds;
+use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
+
+/// An item which knows the priority class of its element.
+pub trait Prioritized {
+    /// Returns the class the element was bucketed into at the start of the pass.
+    fn priority(&self) -> u8;
+}
+
+/// An iterator which visits the elements in descending priority classes and allows removing
+/// them, see [`InplaceVector::priority_removable_iter`](crate::prelude::InplaceVector::priority_removable_iter).
+pub struct PriorityVecIterator<'a, T> {
+    /// This tells the borrow checker that the underlying vector is borrowed and cannot be used otherwise.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
+    /// A raw pointer to the vector for unsafe access.
+    data: *mut Vec<T>,
+    /// The class of every element, ascending, as bucketed at the start of the pass.
+    classes: Vec<u8>,
+    /// The number of unvisited elements, they are the ones before this index.
+    next: usize,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T> PriorityVecIterator<'a, T> {
+    /// Buckets the elements of `v` by `classify` and creates the pass over them.
+    pub fn new<F: FnMut(&T) -> u8>(v: &'a mut Vec<T>, mut classify: F) -> Self {
+        // classified before anything is moved, so a panicking `classify` leaves the vector as is
+        let unsorted = v.iter().map(&mut classify).collect::<Vec<_>>();
+        let mut buckets: Vec<Vec<T>> = (0..=u8::MAX).map(|_| Vec::new()).collect();
+        for (value, class) in v.drain(..).zip(&unsorted) {
+            buckets[*class as usize].push(value);
+        }
+        let mut classes = Vec::with_capacity(unsorted.len());
+        for (class, bucket) in buckets.into_iter().enumerate() {
+            classes.resize(classes.len() + bucket.len(), class as u8);
+            v.extend(bucket);
+        }
+        Self {
+            _lifetime_guard: PhantomData,
+            next: v.len(),
+            data: v,
+            classes,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+#[cfg(feature = "loop-lifetime-guard")]
+impl<'a, T> Drop for PriorityVecIterator<'a, T> {
+    fn drop(&mut self) {
+        self.rotten_item();
+    }
+}
+
+impl<'a, T> Iterator for PriorityVecIterator<'a, T> {
+    type Item = PriorityVecItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        if self.next == 0 {
+            return None;
+        }
+        self.next -= 1;
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(PriorityVecItem {
+            _lifetime_guard: PhantomData,
+            data: self.data,
+            index: self.next,
+            priority: self.classes[self.next],
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.next, Some(self.next))
+    }
+
+    fn count(self) -> usize {
+        self.next
+    }
+}
+
+impl<'a, T> FusedIterator for PriorityVecIterator<'a, T> {}
+
+impl<'a, T> InplaceIterator<T> for PriorityVecIterator<'a, T> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        unsafe { slice::from_raw_parts((*self.data).as_ptr(), self.next) }.iter().rev()
+    }
+}
+
+/// An item of the priority pass, removing or taking it swaps in an already visited element.
+pub struct PriorityVecItem<'a, T> {
+    /// This ties the item to the borrow of the vector by the iterator.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
+    /// A raw pointer to the vector.
+    data: *mut Vec<T>,
+    /// The index of the element.
+    index: usize,
+    /// The class of the element.
+    priority: u8,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> PriorityVecItem<'a, T> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    pub(crate) fn take_value(self) -> T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            #[cfg(feature = "move-stats")]
+            crate::move_stats::record::<T>(usize::from(self.index + 1 < (&*self.data).len()));
+            swap_remove_in_bounds(&mut *self.data, self.index)
+        }
+    }
+
+    pub(crate) fn get_value(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &(&*self.data)[self.index] }
+    }
+
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &mut (&mut *self.data)[self.index] }
+    }
+}
+
+impl<'a, T> Prioritized for PriorityVecItem<'a, T> {
+    fn priority(&self) -> u8 {
+        self.priority
+    }
+}
+
+impl<'a, T> RemovableItem<T> for PriorityVecItem<'a, T> {
+    /// Removes the current item, the last element takes its place.
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> RemovableItemMut<T> for PriorityVecItem<'a, T> {
+    fn remove(self) {
+        let _ = self.take_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+impl<'a, T> TakeableItem<T> for PriorityVecItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> TakeableItemMut<T> for PriorityVecItem<'a, T> {
+    fn take(self) -> T {
+        self.take_value()
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inplace_iterator::InplaceIterator;
+    use crate::prelude::{InplaceVector, Prioritized, RemovableItemMut};
+
+    #[test]
+    fn test_descending_classes() {
+        let mut jobs = (0..20).collect::<Vec<u32>>();
+        let iter = jobs.priority_removable_iter_mut(|job| (job % 4) as u8);
+        assert_eq!(iter.peek(), Some(&19));
+        let mut visited = Vec::new();
+        for mut item in iter {
+            visited.push((item.priority(), *item.get()));
+            if item.get() % 3 == 0 {
+                item.remove();
+            } else {
+                *item.get_mut() += 100;
+            }
+        }
+        let priorities = visited.iter().map(|(priority, _)| *priority).collect::<Vec<_>>();
+        assert!(priorities.is_sorted_by(|a, b| a >= b));
+        assert_eq!(visited.len(), 20);
+        let mut values = visited.iter().map(|(_, job)| *job).collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+        jobs.sort();
+        assert_eq!(jobs, (0..20).filter(|job| job % 3 != 0).map(|job| job + 100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_panicking_classifier_keeps_the_elements() {
+        let mut jobs = vec![1, 2, 3];
+        let caught = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            jobs.priority_removable_iter(|job| if *job == 3 { panic!("unclassified") } else { 0 }).count()
+        }));
+        assert!(caught.is_err());
+        assert_eq!(jobs, vec![1, 2, 3]);
+    }
+}
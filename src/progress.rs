@@ -0,0 +1,124 @@
+//! Reporting the progress of long passes.
+
+use std::iter::FusedIterator;
+use crate::inplace_iterator::InplaceIterator;
+
+/// An iterator which calls a hook every `every` visited items, created by
+/// [`InplaceIterator::with_progress`].
+///
+/// The hook gets the number of visited items and the total number of items of the pass. It is
+/// called when the next item is requested, so the reported items are done with.
+pub struct Progress<I, F> {
+    /// The underlying iterator.
+    iter: I,
+    /// The hook.
+    on_progress: F,
+    /// The number of items between the calls of the hook.
+    every: usize,
+    /// The number of yielded items.
+    visited: usize,
+    /// The number of items of the whole pass.
+    total: usize,
+    /// The number of visited items the hook was last called with.
+    reported: usize,
+}
+
+impl<I: Iterator, F> Progress<I, F> {
+    pub(crate) fn new(iter: I, every: usize, on_progress: F) -> Self {
+        assert!(every > 0, "the progress must be reported at least every item");
+        Self {
+            total: iter.size_hint().0,
+            iter,
+            on_progress,
+            every,
+            visited: 0,
+            reported: 0,
+        }
+    }
+
+    /// The number of yielded items.
+    pub fn visited(&self) -> usize {
+        self.visited
+    }
+}
+
+impl<I: Iterator, F: FnMut(usize, usize)> Progress<I, F> {
+    fn report(&mut self) {
+        if self.reported != self.visited {
+            self.reported = self.visited;
+            (self.on_progress)(self.visited, self.total);
+        }
+    }
+}
+
+impl<I: Iterator, F: FnMut(usize, usize)> Iterator for Progress<I, F> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.visited.is_multiple_of(self.every) {
+            self.report();
+        }
+        match self.iter.next() {
+            Some(item) => {
+                self.visited += 1;
+                Some(item)
+            }
+            None => {
+                // the last report, unless it was just done
+                self.report();
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I: FusedIterator, F: FnMut(usize, usize)> FusedIterator for Progress<I, F> {}
+
+impl<T, I: InplaceIterator<T>, F: FnMut(usize, usize)> InplaceIterator<T> for Progress<I, F> {
+    fn lookahead<'s>(&'s self) -> impl Iterator<Item = &'s T> + Clone
+    where
+        T: 's,
+    {
+        self.iter.lookahead()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::{InplaceIterator, InplaceVector, RemovableItem, TakeableItem};
+
+    #[test]
+    fn test_progress_with_removals() {
+        let mut a = (0..10).collect::<Vec<_>>();
+        let mut reports = Vec::new();
+        for item in a.removable_iter().with_progress(4, |visited, total| reports.push((visited, total))) {
+            if *item.get() % 3 == 0 {
+                item.remove();
+            }
+        }
+        assert_eq!(reports, vec![(4, 10), (8, 10), (10, 10)]);
+        assert_eq!(a.len(), 6);
+    }
+
+    #[test]
+    fn test_progress_reports_once_at_the_end() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut reports = Vec::new();
+        let taken = a
+            .takeable_iter()
+            .with_progress(2, |visited, total| reports.push((visited, total)))
+            .map(|item| item.take())
+            .sum::<i32>();
+        assert_eq!(taken, 10);
+        assert_eq!(reports, vec![(2, 4), (4, 4)]);
+
+        let mut empty: Vec<i32> = Vec::new();
+        let mut called = false;
+        assert_eq!(empty.removable_iter().with_progress(1, |_, _| called = true).count(), 0);
+        assert!(!called);
+    }
+}
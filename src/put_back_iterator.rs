@@ -0,0 +1,158 @@
+//! Owned iteration over a consumed vector, where the kept elements are put back.
+
+use std::iter::FusedIterator;
+use std::{mem, ptr};
+
+/// An iterator which yields the elements of a consumed vector by value.
+///
+/// The yielded values which should stay are returned with [`put_back`](Self::put_back), and
+/// [`finish`](Self::finish) rebuilds the vector of the put back elements in the original
+/// allocation.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let words = vec!["keep".to_string(), "drop".to_string(), "shout".to_string()];
+/// let mut iter = words.into_put_back_iter();
+/// while let Some(word) = iter.next() {
+///     match word.as_str() {
+///         "drop" => {}
+///         "shout" => iter.put_back(word.to_uppercase()),
+///         _ => iter.put_back(word),
+///     }
+/// }
+/// assert_eq!(iter.finish(), vec!["keep", "SHOUT"]);
+/// ```
+pub struct PutBackIter<T> {
+    /// The consumed vector, its length is 0 while iterating.
+    vector: Vec<T>,
+    /// The length of the vector before the iteration.
+    len: usize,
+    /// The index of the next element to yield.
+    read: usize,
+    /// The number of elements put back, they are at the front of the vector.
+    write: usize,
+}
+
+impl<T> PutBackIter<T> {
+    pub fn new(mut v: Vec<T>) -> Self {
+        let len = v.len();
+        // if the iterator is leaked, the elements are leaked too instead of being dropped twice
+        unsafe { v.set_len(0) };
+        Self { vector: v, len, read: 0, write: 0 }
+    }
+
+    /// Puts a value back into the vector, after the values put back before.
+    ///
+    /// The value does not have to be the yielded one, e.g. it can be a modified version of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more values are put back than were yielded.
+    pub fn put_back(&mut self, value: T) {
+        assert!(self.write < self.read, "cannot put back more values than were yielded");
+        unsafe { ptr::write(self.vector.as_mut_ptr().add(self.write), value) };
+        self.write += 1;
+    }
+
+    /// Returns the vector of the put back elements, followed by the elements not yet yielded.
+    ///
+    /// The vector reuses the original allocation.
+    pub fn finish(mut self) -> Vec<T> {
+        let data = self.vector.as_mut_ptr();
+        let rest = self.len - self.read;
+        let mut v = mem::take(&mut self.vector);
+        unsafe {
+            ptr::copy(data.add(self.read), data.add(self.write), rest);
+            v.set_len(self.write + rest);
+        }
+        // the elements are owned by the returned vector now
+        self.len = 0;
+        self.read = 0;
+        self.write = 0;
+        v
+    }
+}
+
+impl<T> Drop for PutBackIter<T> {
+    fn drop(&mut self) {
+        let data = self.vector.as_mut_ptr();
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(data, self.write));
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(data.add(self.read), self.len - self.read));
+        }
+    }
+}
+
+impl<T> Iterator for PutBackIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.read >= self.len {
+            return None;
+        }
+        self.read += 1;
+        Some(unsafe { ptr::read(self.vector.as_ptr().add(self.read - 1)) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.read;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for PutBackIter<T> {}
+
+impl<T> FusedIterator for PutBackIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::prelude::InplaceVector;
+
+    #[test]
+    fn test_put_back_reuses_allocation() {
+        let a = (0..10).collect::<Vec<_>>();
+        let pointer = a.as_ptr();
+        let mut iter = a.into_put_back_iter();
+        while let Some(v) = iter.next() {
+            if v % 3 == 0 {
+                iter.put_back(v * 10);
+            }
+        }
+        let a = iter.finish();
+        assert_eq!(a, vec![0, 30, 60, 90]);
+        assert_eq!(a.as_ptr(), pointer);
+    }
+
+    #[test]
+    fn test_finish_keeps_unvisited() {
+        let mut iter = vec![1, 2, 3, 4].into_put_back_iter();
+        iter.next();
+        let second = iter.next().unwrap();
+        iter.put_back(second);
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.finish(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_drop_drops_owned_elements() {
+        let counter = Rc::new(());
+        let mut iter = vec![counter.clone(), counter.clone(), counter.clone()].into_put_back_iter();
+        let first = iter.next().unwrap();
+        iter.put_back(first);
+        let second = iter.next().unwrap();
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 2);
+        drop(second);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_put_back_too_many() {
+        let mut iter = vec![1].into_put_back_iter();
+        iter.put_back(2);
+    }
+}
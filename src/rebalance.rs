@@ -0,0 +1,101 @@
+//! Rebalancing of vectors of vectors, e.g. sharded in-memory buckets.
+
+use crate::inplace_vector::InplaceVector;
+use crate::takeable_iterator::TakeableItem;
+
+/// A trait for vectors of vectors, moving elements between the inner vectors to reach target
+/// sizes.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut shards = vec![vec![1, 2, 3, 4, 5], vec![6], vec![], vec![7, 8]];
+/// let moved = shards.rebalance_even();
+/// assert_eq!(moved, 3);
+/// assert_eq!(shards.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 2, 2]);
+/// ```
+pub trait Rebalance<T> {
+    /// Moves elements from the inner vectors longer than their target length into the ones
+    /// shorter than theirs, and returns the number of moved elements.
+    ///
+    /// `target` is called once per inner vector, before anything is moved. Only as many
+    /// elements move as the shorter vectors are missing, or as the longer ones have in excess,
+    /// whichever is less, so each moved element moves once. If the targets do not add up to the
+    /// number of elements, the excess stays where it is, or the shortage stays unfilled.
+    ///
+    /// # Performance
+    ///
+    /// - Moving an element is O(1) time complexity
+    /// - The order of elements in the inner vectors is not preserved
+    fn rebalance<F>(&mut self, target: F) -> usize
+    where
+        F: FnMut(&[T]) -> usize;
+
+    /// Rebalances the inner vectors to equal lengths, the first ones get one more element
+    /// if the elements do not divide evenly.
+    ///
+    /// See [`Rebalance::rebalance`].
+    fn rebalance_even(&mut self) -> usize;
+}
+
+impl<T> Rebalance<T> for Vec<Vec<T>> {
+    fn rebalance<F>(&mut self, mut target: F) -> usize
+    where
+        F: FnMut(&[T]) -> usize,
+    {
+        let targets = self.iter().map(|inner| target(inner)).collect::<Vec<_>>();
+        let missing = self.iter().zip(&targets).map(|(inner, &target)| target.saturating_sub(inner.len())).sum::<usize>();
+        let mut moving = Vec::with_capacity(missing);
+        for (inner, &target) in self.iter_mut().zip(&targets) {
+            let excess = inner.len().saturating_sub(target).min(missing - moving.len());
+            moving.extend(inner.takeable_iter().take(excess).map(|item| item.take()));
+        }
+        let moved = moving.len();
+        for (inner, &target) in self.iter_mut().zip(&targets) {
+            let fill = target.saturating_sub(inner.len()).min(moving.len());
+            inner.extend(moving.drain(moving.len() - fill..));
+        }
+        moved
+    }
+
+    fn rebalance_even(&mut self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let total = self.iter().map(Vec::len).sum::<usize>();
+        let (base, extra) = (total / self.len(), total % self.len());
+        let mut index = 0;
+        self.rebalance(|_| {
+            index += 1;
+            base + usize::from(index <= extra)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rebalance;
+
+    #[test]
+    fn test_rebalance_moves_only_the_difference() {
+        let mut shards = vec![(0..10).collect::<Vec<_>>(), vec![10], vec![11, 12, 13]];
+        let moved = shards.rebalance(|inner| if inner.len() == 10 { 4 } else { 5 });
+        assert_eq!(moved, 6);
+        assert_eq!(shards.iter().map(Vec::len).collect::<Vec<_>>(), vec![4, 5, 5]);
+        let mut all = shards.concat();
+        all.sort();
+        assert_eq!(all, (0..14).collect::<Vec<_>>());
+
+        // the targets ask for more than there is, the shortage stays unfilled
+        assert_eq!(shards.rebalance(|_| 6), 0);
+        assert_eq!(shards.rebalance(|inner| if inner.len() == 4 { 8 } else { 3 }), 4);
+        assert_eq!(shards.iter().map(Vec::len).collect::<Vec<_>>(), vec![8, 3, 3]);
+
+        let mut uneven = vec![vec![1, 2, 3, 4, 5, 6, 7], vec![], vec![]];
+        assert_eq!(uneven.rebalance_even(), 4);
+        assert_eq!(uneven.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 2, 2]);
+        assert_eq!(Vec::<Vec<u8>>::new().rebalance_even(), 0);
+    }
+}
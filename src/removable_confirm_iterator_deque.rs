@@ -0,0 +1,261 @@
+//! The confirm sessions of double-ended queues, marking the items for removal and removing them
+//! on confirmation.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use crate::removable_confirm_iterator_vec::{ConfirmState, EagerRemovableItem, RemovableConfirmIterator};
+use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+
+/// A confirm session over a deque.
+///
+/// Marking an element swaps it with the last element still in the session, like the confirm
+/// session of a vector, so the marked elements gather at the back of the deque.
+pub struct InplaceRemovableConfirmDequeIterator<'a, T> {
+    /// This tells the borrow checker that the underlying deque is borrowed and cannot be used otherwise.
+    deque: &'a mut VecDeque<T>,
+    /// A raw pointer to the deque for unsafe access.
+    data: *mut VecDeque<T>,
+    /// A flag indicating whether an item has been marked for removal.
+    removed: bool,
+    /// The current index in the deque, or None if iteration hasn't started.
+    index: Option<usize>,
+    /// The current size after removals.
+    size: usize,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, T> InplaceRemovableConfirmDequeIterator<'a, T> {
+    pub fn new(v: &'a mut VecDeque<T>) -> Self {
+        let data = v as *mut VecDeque<T>;
+        Self {
+            size: v.len(),
+            deque: v,
+            data,
+            removed: false,
+            index: None,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+
+    /// Creates the session of a suspended one, with `size` elements not marked for removal.
+    pub(crate) fn with_size(v: &'a mut VecDeque<T>, size: usize) -> Self {
+        let mut iter = Self::new(v);
+        iter.size = size;
+        iter
+    }
+
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+}
+
+#[cfg(feature = "loop-lifetime-guard")]
+impl<'a, T> Drop for InplaceRemovableConfirmDequeIterator<'a, T> {
+    fn drop(&mut self) {
+        // the items must not be used after confirming or cancelling
+        self.rotten_item();
+    }
+}
+
+impl<'a, T> RemovableConfirmIterator for InplaceRemovableConfirmDequeIterator<'a, T> {
+    type Element = T;
+    type Item = InplaceRemovableConfirmDequeItem<'a, T>;
+
+    fn iter(&mut self) -> impl Iterator<Item = Self::Item> {
+        self.index = None;
+        self.removed = false;
+        self
+    }
+
+    fn confirm_removals(self) {
+        let len = self.deque.len();
+        if self.size < len {
+            // each marked element is swapped just below the previously marked one, so the back
+            // is in the reverse order of marking
+            for offset in 0..(len - self.size) / 2 {
+                self.deque.swap(self.size + offset, len - 1 - offset);
+            }
+            self.deque.truncate(self.size);
+        }
+    }
+
+    fn cancel_removals(self) {
+        // do nothing
+    }
+
+    fn cancel_removals_with<F>(self, f: F)
+    where
+        F: FnMut(&T),
+    {
+        self.deque.range(self.size..).rev().for_each(f);
+    }
+
+    fn suspend(self) -> ConfirmState {
+        ConfirmState::of_deque(self.size, self.deque)
+    }
+}
+
+impl<'a, T> Iterator for InplaceRemovableConfirmDequeIterator<'a, T> {
+    type Item = InplaceRemovableConfirmDequeItem<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        let index = match self.index {
+            // the element swapped in by the marking is visited in its place
+            Some(index) if self.removed => index,
+            Some(index) => index + 1,
+            None => 0,
+        };
+        self.removed = false;
+        self.index = Some(index);
+        if index >= self.size {
+            return None;
+        }
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(InplaceRemovableConfirmDequeItem {
+            _lifetime_guard: PhantomData,
+            data: self.data,
+            index,
+            removed: &mut self.removed,
+            size: &mut self.size,
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let next = match self.index {
+            Some(index) if self.removed => index,
+            Some(index) => index + 1,
+            None => 0,
+        };
+        let remaining = self.size.saturating_sub(next);
+        (remaining, Some(remaining))
+    }
+}
+
+/// An item of a confirm session over a deque.
+pub struct InplaceRemovableConfirmDequeItem<'a, T> {
+    /// This ties the item to the borrow of the deque by the session.
+    _lifetime_guard: PhantomData<&'a mut VecDeque<T>>,
+    /// A raw pointer to the deque containing the item.
+    data: *mut VecDeque<T>,
+    /// The index of the item within the deque.
+    index: usize,
+    /// An indicator to the session that we have marked the item.
+    removed: *mut bool,
+    /// The number of elements not marked for removal.
+    size: *mut usize,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a, T> InplaceRemovableConfirmDequeItem<'a, T> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    pub(crate) fn remove_value(self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe {
+            *self.removed = true;
+            *self.size -= 1;
+            if self.index < *self.size {
+                // swap with the last element still in the session
+                (*self.data).swap(self.index, *self.size);
+            }
+        }
+    }
+
+    pub(crate) fn get_value(&self) -> &T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &(&*self.data)[self.index] }
+    }
+
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { &mut (&mut *self.data)[self.index] }
+    }
+}
+
+impl<'a, T> EagerRemovableItem<T> for InplaceRemovableConfirmDequeItem<'a, T> {
+    fn remove_now_with(mut self, placeholder: T) {
+        let value = std::mem::replace(self.get_value_mut(), placeholder);
+        self.remove_value();
+        drop(value);
+    }
+}
+
+impl<'a, T> RemovableItem<T> for InplaceRemovableConfirmDequeItem<'a, T> {
+    fn remove(self) {
+        self.remove_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+}
+
+impl<'a, T> RemovableItemMut<T> for InplaceRemovableConfirmDequeItem<'a, T> {
+    fn remove(self) {
+        self.remove_value();
+    }
+
+    fn get(&self) -> &T {
+        self.get_value()
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        self.get_value_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use crate::prelude::{InplaceDeque, RemovableConfirmIterator, RemovableItem, RemovableItemMut};
+
+    #[test]
+    fn test_deque_confirm_and_cancel() {
+        let mut a = (1..=6).collect::<VecDeque<_>>();
+        let mut confirm = a.removable_confirm_iter();
+        for item in confirm.iter() {
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+        }
+        let mut marked = Vec::new();
+        confirm.cancel_removals_with(|x| marked.push(*x));
+        // 6 was swapped into the place of 2 and marked next
+        assert_eq!(marked, vec![2, 6, 4]);
+        assert_eq!(a.len(), 6);
+
+        let mut confirm = a.removable_confirm_iter_mut();
+        for mut item in confirm.iter() {
+            if *item.get() > 4 {
+                item.remove();
+            } else {
+                *item.get_mut() *= 10;
+            }
+        }
+        let state = confirm.suspend();
+        assert_eq!(state.marked(), 2);
+        state.resume_deque(&mut a).unwrap().confirm_removals();
+        let mut kept = a.into_iter().collect::<Vec<_>>();
+        kept.sort();
+        assert_eq!(kept, vec![10, 20, 30, 40]);
+    }
+}
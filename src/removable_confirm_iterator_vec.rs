@@ -1,11 +1,24 @@
 //! This will mark the items for removal, but only perform the removal on confirmation.
 
-use std::cell::RefCell;
-use std::rc::Rc;
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{Epoch, ItemEpoch};
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+use std::time::Instant;
+#[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+use crate::deadline_confirm::{DeadlineConfirm, OnExpiry};
+use crate::item_index::{self, ItemIndex};
+#[cfg(debug_assertions)]
+use crate::ledger::VisitLedger;
 use crate::prelude::RemovableItem;
+use crate::removable_confirm_iterator_deque::InplaceRemovableConfirmDequeIterator;
 use crate::removable_iterator::RemovableItemMut;
 
 pub trait RemovableConfirmIterator {
+    /// The type of the elements of the vector.
+    type Element;
     type Item;
     /// Create an iterator that iterates over the elements.
     /// Subsequent calls to this method will iterate over not yet removed elements.
@@ -13,13 +26,217 @@ pub trait RemovableConfirmIterator {
     /// iterate over the modified elements.
     fn iter(&mut self) -> impl Iterator<Item = Self::Item>;
     /// Confirm removals of the elements marked for removal and return the container.
+    ///
+    /// The removed elements are dropped in the order they were marked for removal. If a drop
+    /// panics, the remaining removed elements are still dropped.
     fn confirm_removals(self);
     /// Cancel removals, but the order of the elements might not be preserved.
     /// If used on mutable iterator, the modified items will stay modified, no cancellation on
     /// the changes. Cancellation is only applicable to the size of the container!
     fn cancel_removals(self);
+
+    /// Cancel removals like [`cancel_removals`](Self::cancel_removals), after passing the
+    /// elements marked for removal to `f`, in the order they were marked.
+    ///
+    /// Useful for a shadow run of a new cleanup policy, logging what it would have removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4];
+    /// let mut confirm = numbers.removable_confirm_iter();
+    /// for item in confirm.iter() {
+    ///     if *item.get() > 2 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// let mut would_remove = Vec::new();
+    /// confirm.cancel_removals_with(|n| would_remove.push(*n));
+    /// assert_eq!(would_remove, vec![3, 4]);
+    /// assert_eq!(numbers.len(), 4);
+    /// ```
+    fn cancel_removals_with<F>(self, f: F)
+    where
+        F: FnMut(&Self::Element);
+
+    /// Detaches the session from the vector, keeping the marked removals pending.
+    ///
+    /// The returned state is resumed with [`ConfirmState::resume`] on the same vector, so a
+    /// session can span several calls without holding the borrow of the vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4];
+    /// let mut confirm = numbers.removable_confirm_iter();
+    /// for item in confirm.iter() {
+    ///     if *item.get() == 2 {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// let state = confirm.suspend();
+    /// // the vector can be read meanwhile, the marked elements are at its end
+    /// assert_eq!(numbers.len(), 4);
+    /// let mut confirm = state.resume(&mut numbers).unwrap();
+    /// assert_eq!(confirm.iter().count(), 3);
+    /// confirm.confirm_removals();
+    /// assert_eq!(numbers, vec![1, 4, 3]);
+    /// ```
+    fn suspend(self) -> ConfirmState;
+
+    /// Wraps the session so that, when dropped after `deadline` without being confirmed or
+    /// cancelled, it settles the removals as chosen by `on_expiry`.
+    ///
+    /// Not available on `wasm32-unknown-unknown`, which has no clock.
+    #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+    fn with_deadline(self, deadline: Instant, on_expiry: OnExpiry) -> DeadlineConfirm<Self>
+    where
+        Self: Sized,
+    {
+        DeadlineConfirm::new(self, deadline, on_expiry)
+    }
 }
 
+/// A trait for the items of a confirm pass which can release their element before the
+/// removals are confirmed.
+///
+/// The element is dropped at once and a placeholder takes its slot until the pass is confirmed
+/// (the placeholder is dropped) or cancelled (the placeholder stays in the vector). Use it for
+/// elements holding resources, e.g. sockets or locks, which must not be held for the whole
+/// pass.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut connections = vec![Some("a".to_string()), Some("b".to_string()), Some("c".to_string())];
+/// let mut confirm = connections.removable_confirm_iter();
+/// for item in confirm.iter() {
+///     if item.get().as_deref() == Some("b") {
+///         // the connection is closed now, `None` holds its slot
+///         item.remove_now();
+///     }
+/// }
+/// confirm.cancel_removals();
+/// assert_eq!(connections, vec![Some("a".to_string()), Some("c".to_string()), None]);
+/// ```
+pub trait EagerRemovableItem<T> {
+    /// Drops the element now and marks its slot, holding `placeholder`, for removal.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn remove_now_with(self, placeholder: T);
+
+    /// Drops the element now and marks its slot, holding `T::default()`, for removal.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn remove_now(self)
+    where
+        T: Default,
+        Self: Sized,
+    {
+        self.remove_now_with(T::default());
+    }
+}
+
+/// The state of a suspended confirm session: the number of the elements not marked for
+/// removal, and the vector it belongs to.
+///
+/// The marked elements stay at the end of the vector while the session is suspended. Changing
+/// the vector meanwhile makes the state stale, which is detected if the length or the
+/// allocation of the vector changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmState {
+    /// The size of the vector after the marked removals.
+    size: usize,
+    /// The length of the vector when suspended.
+    len: usize,
+    /// The address of the vector data when suspended.
+    data: usize,
+}
+
+impl ConfirmState {
+    /// Returns the number of the elements marked for removal.
+    pub fn marked(&self) -> usize {
+        self.len - self.size
+    }
+
+    /// Resumes the session on the vector it was suspended from.
+    ///
+    /// Fails if the vector was changed since, as far as it can be detected.
+    pub fn resume<T>(self, v: &mut Vec<T>) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>>, StaleConfirmState> {
+        self.attach(v)
+    }
+
+    /// Resumes the session on the vector it was suspended from, with mutable access.
+    ///
+    /// Fails if the vector was changed since, as far as it can be detected.
+    pub fn resume_mut<T>(self, v: &mut Vec<T>) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>>, StaleConfirmState> {
+        self.attach(v)
+    }
+
+    /// Resumes the session on the deque it was suspended from.
+    ///
+    /// Fails if the deque was changed since, as far as it can be detected.
+    pub fn resume_deque<T>(self, v: &mut VecDeque<T>) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>>, StaleConfirmState> {
+        self.attach_deque(v)
+    }
+
+    /// Resumes the session on the deque it was suspended from, with mutable access.
+    ///
+    /// Fails if the deque was changed since, as far as it can be detected.
+    pub fn resume_deque_mut<T>(self, v: &mut VecDeque<T>) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>>, StaleConfirmState> {
+        self.attach_deque(v)
+    }
+
+    /// The state of a deque session with `size` elements not marked for removal.
+    pub(crate) fn of_deque<T>(size: usize, v: &VecDeque<T>) -> Self {
+        Self { size, len: v.len(), data: v.as_slices().0.as_ptr() as usize }
+    }
+
+    fn attach_deque<T>(self, v: &mut VecDeque<T>) -> Result<InplaceRemovableConfirmDequeIterator<'_, T>, StaleConfirmState> {
+        if self != Self::of_deque(self.size, v) {
+            return Err(StaleConfirmState);
+        }
+        Ok(InplaceRemovableConfirmDequeIterator::with_size(v, self.size))
+    }
+
+    fn attach<T>(self, v: &mut Vec<T>) -> Result<InplaceRemovableConfirmVecIterator<'_, T>, StaleConfirmState> {
+        if v.len() != self.len || v.as_ptr() as usize != self.data {
+            return Err(StaleConfirmState);
+        }
+        let mut iter = InplaceRemovableConfirmVecIterator::new(v);
+        iter.size = self.size;
+        #[cfg(debug_assertions)]
+        {
+            iter.ledger = VisitLedger::new(self.size);
+        }
+        Ok(iter)
+    }
+}
+
+/// The error of resuming a [`ConfirmState`] on a vector changed since it was suspended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleConfirmState;
+
+impl fmt::Display for StaleConfirmState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the vector was changed since the confirm session was suspended")
+    }
+}
+
+impl std::error::Error for StaleConfirmState {}
+
 pub struct InplaceRemovableConfirmVecIterator<'a, T> {
     /// This tells the borrow checker that the underlying vector is borrowed and cannot be used otherwise.
     vector: &'a mut Vec<T>,
@@ -31,20 +248,29 @@ pub struct InplaceRemovableConfirmVecIterator<'a, T> {
     index: Option<usize>,
     /// The current size after removals.
     size: usize,
-    /// The rotten indicator given to the last generated iterator item.
+    /// The epoch of the yielded items, advanced to invalidate them.
     #[cfg(feature = "loop-lifetime-guard")]
-    last_rotten: Option<Rc<RefCell<bool>>>,
+    epoch: Epoch,
+    /// The visited elements, checked in debug builds.
+    #[cfg(debug_assertions)]
+    ledger: VisitLedger,
 }
 
 impl<'a, T> RemovableConfirmIterator for InplaceRemovableConfirmVecIterator<'a, T> {
-    type Item = InplaceRemovableConfirmVecItem<T>;
+    type Element = T;
+    type Item = InplaceRemovableConfirmVecItem<'a, T>;
     
     fn iter(&mut self) -> impl Iterator<Item = Self::Item> {
+        #[cfg(debug_assertions)]
+        self.ledger.restart(self.index, self.size, self.removed);
         self.index = None; // reset iterator
         self
     }
     fn confirm_removals(self) {
         if self.size < self.vector.len() {
+            // each marked element is swapped just below the previously marked one, so the tail
+            // is in the reverse order of marking
+            self.vector[self.size..].reverse();
             self.vector.truncate(self.size);
         }
     }
@@ -52,35 +278,61 @@ impl<'a, T> RemovableConfirmIterator for InplaceRemovableConfirmVecIterator<'a,
     fn cancel_removals(self) {
         // do nothing
     }
+
+    fn cancel_removals_with<F>(self, f: F)
+    where
+        F: FnMut(&T),
+    {
+        // the tail is in the reverse order of marking, see `confirm_removals`
+        self.vector[self.size..].iter().rev().for_each(f);
+    }
+
+    fn suspend(self) -> ConfirmState {
+        ConfirmState {
+            size: self.size,
+            len: self.vector.len(),
+            data: self.vector.as_ptr() as usize,
+        }
+    }
 }
 
 impl<'a, T> InplaceRemovableConfirmVecIterator<'a, T> {
     pub fn new(v: &'a mut Vec<T>) -> Self {
+        item_index::check_len(v.len());
         let data = v as *mut Vec<T>;
+        let size = v.len();
         Self {
-            size: v.len(),
+            size,
             vector: v,
             index: None,
             data,
             removed: false,
             #[cfg(feature = "loop-lifetime-guard")]
-            last_rotten: None
+            epoch: Epoch::default(),
+            #[cfg(debug_assertions)]
+            ledger: VisitLedger::new(size),
         }
     }
 }
 
 trait BuildItem<T> {
-    fn build_new(data: *mut Vec<T>, index: usize, size: *mut usize, removed: *mut bool, #[cfg(feature = "loop-lifetime-guard")] rotten: Rc<RefCell<bool>>) -> Self;
+    fn build_new(data: *mut Vec<T>, index: usize, size: *mut usize, removed: *mut bool, #[cfg(feature = "loop-lifetime-guard")] rotten: ItemEpoch) -> Self;
 }
 
 
+#[cfg(feature = "loop-lifetime-guard")]
+impl<'a, T> Drop for InplaceRemovableConfirmVecIterator<'a, T> {
+    fn drop(&mut self) {
+        // the items must not be used after confirming or cancelling
+        self.rotten_item();
+    }
+}
+
 impl<'a, T> InplaceRemovableConfirmVecIterator<'a, T> {
     
     #[cfg(feature = "loop-lifetime-guard")]
     fn rotten_item(&mut self) {
-        if let Some(rotten) = self.last_rotten.take() {
-            *rotten.borrow_mut() = true;
-        }
+        self.epoch.advance();
     }
     
     fn next_item<I: BuildItem<T>>(&mut self) -> Option<I> {
@@ -93,6 +345,10 @@ impl<'a, T> InplaceRemovableConfirmVecIterator<'a, T> {
             }
             v.len()
         };
+        #[cfg(debug_assertions)]
+        if let Some(index) = self.index {
+            self.ledger.sync(index, self.size, self.removed);
+        }
         let index = if self.removed {
             self.removed = false;
             self.index.unwrap() // if taken, then index is set and we don't increment to the next
@@ -107,15 +363,15 @@ impl<'a, T> InplaceRemovableConfirmVecIterator<'a, T> {
         };
         if index >= self.size {
             // we have reached the end of the vector after removals
+            #[cfg(debug_assertions)]
+            self.ledger.assert_complete(0, true);
             return None;
         }
         if index < len {
             #[cfg(feature = "loop-lifetime-guard")]
-            let rotten = {
-                let rotten = Rc::new(RefCell::new(false));
-                self.last_rotten = Some(rotten.clone());
-                rotten
-            };
+            let rotten = self.epoch.item();
+            #[cfg(debug_assertions)]
+            self.ledger.visit(index);
             Some(I::build_new(self.data, index, &mut self.size, &mut self.removed, #[cfg(feature = "loop-lifetime-guard")] rotten))
         } else {
             None
@@ -124,34 +380,37 @@ impl<'a, T> InplaceRemovableConfirmVecIterator<'a, T> {
 }
 
 impl<'a, T> Iterator for InplaceRemovableConfirmVecIterator<'a, T> {
-    type Item = InplaceRemovableConfirmVecItem<T>;
+    type Item = InplaceRemovableConfirmVecItem<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next_item()
     }
 }
 
-pub struct InplaceRemovableConfirmVecItem<T> {
+pub struct InplaceRemovableConfirmVecItem<'a, T> {
+    /// This ties the item to the borrow of the vector by the iterator.
+    _lifetime_guard: PhantomData<&'a mut Vec<T>>,
     /// A raw pointer to the vector containing the item.
     data: *mut Vec<T>,
     /// The index of the item within the vector.
-    index: usize,
+    index: ItemIndex,
     /// An indicator to the vector that we have removed the item
     removed: *mut bool,
     /// The current size of the vector
     size: *mut usize,
     /// Indicator that this iterator item should no longer be used!
     #[cfg(feature = "loop-lifetime-guard")]
-    rotten: Rc<RefCell<bool>>,
+    rotten: ItemEpoch,
 }
 
-impl<T> BuildItem<T> for InplaceRemovableConfirmVecItem<T> {
+impl<'a, T> BuildItem<T> for InplaceRemovableConfirmVecItem<'a, T> {
     fn build_new(data: *mut Vec<T>, index: usize, size: *mut usize, removed: *mut bool,
                  #[cfg(feature = "loop-lifetime-guard")]
-                 rotten: Rc<RefCell<bool>>) -> Self {
+                 rotten: ItemEpoch) -> Self {
         Self {
+            _lifetime_guard: PhantomData,
             data,
-            index,
+            index: item_index::narrow(index),
             removed,
             size,
             #[cfg(feature = "loop-lifetime-guard")]
@@ -161,14 +420,16 @@ impl<T> BuildItem<T> for InplaceRemovableConfirmVecItem<T> {
 }
 
 #[cfg(feature = "loop-lifetime-guard")]
-impl<T> InplaceRemovableConfirmVecItem<T> {
+impl<'a, T> InplaceRemovableConfirmVecItem<'a, T> {
     fn check_rotten(&self) {
-        if *self.rotten.borrow() {
-            panic!("This iterator item is no longer valid!");
-        }
+        self.rotten.check();
     }
 }
-impl<T> InplaceRemovableConfirmVecItem<T> {
+impl<'a, T> InplaceRemovableConfirmVecItem<'a, T> {
+    fn index(&self) -> usize {
+        item_index::widen(self.index)
+    }
+
     pub(crate) fn remove_value(self) {
         #[cfg(feature = "loop-lifetime-guard")]
         self.check_rotten();
@@ -176,9 +437,11 @@ impl<T> InplaceRemovableConfirmVecItem<T> {
             *self.removed = true;
             *self.size -= 1;
             let v = &mut (*self.data);
-            if self.index < *self.size {
+            if self.index() < *self.size {
                 // swap with the last item, but our last item
-                v.swap(self.index, *self.size);
+                v.swap(self.index(), *self.size);
+                #[cfg(feature = "move-stats")]
+                crate::move_stats::record::<T>(2);
             }
         }
     }
@@ -188,21 +451,29 @@ impl<T> InplaceRemovableConfirmVecItem<T> {
         self.check_rotten();
         unsafe {
             let v = &mut (*self.data);
-            &(*v.as_ptr().add(self.index))
+            &(*v.as_ptr().add(self.index()))
         }
     }
 
-    pub(crate) fn get_value_mut(&self) -> &mut T {
+    pub(crate) fn get_value_mut(&mut self) -> &mut T {
         #[cfg(feature = "loop-lifetime-guard")]
         self.check_rotten();
         unsafe {
             let v = &mut (*self.data);
-            &mut (*v.as_mut_ptr().add(self.index))
+            &mut (*v.as_mut_ptr().add(self.index()))
         }
     }
 }
 
-impl<T> RemovableItem<T> for InplaceRemovableConfirmVecItem<T> {
+impl<'a, T> EagerRemovableItem<T> for InplaceRemovableConfirmVecItem<'a, T> {
+    fn remove_now_with(mut self, placeholder: T) {
+        let value = std::mem::replace(self.get_value_mut(), placeholder);
+        self.remove_value();
+        drop(value);
+    }
+}
+
+impl<'a, T> RemovableItem<T> for InplaceRemovableConfirmVecItem<'a, T> {
     fn remove(self) {
         self.remove_value();
     }
@@ -212,7 +483,7 @@ impl<T> RemovableItem<T> for InplaceRemovableConfirmVecItem<T> {
     }
 }
 
-impl<T> RemovableItemMut<T> for InplaceRemovableConfirmVecItem<T> {
+impl<'a, T> RemovableItemMut<T> for InplaceRemovableConfirmVecItem<'a, T> {
     fn remove(self) {
         self.remove_value();
     }
@@ -226,3 +497,96 @@ impl<T> RemovableItemMut<T> for InplaceRemovableConfirmVecItem<T> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use crate::prelude::{EagerRemovableItem, InplaceVector, RemovableConfirmIterator, RemovableItem};
+    use super::StaleConfirmState;
+
+    struct Logged<'a>(u32, &'a RefCell<Vec<u32>>);
+
+    impl Drop for Logged<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_confirm_drops_in_mark_order() {
+        let log = RefCell::new(Vec::new());
+        let mut a = (0..8).map(|i| Logged(i, &log)).collect::<Vec<_>>();
+        let mut confirm = a.removable_confirm_iter();
+        for item in confirm.iter() {
+            if item.get().0 % 3 != 1 {
+                item.remove();
+            }
+        }
+        // a second pass marks more elements
+        for item in confirm.iter() {
+            if item.get().0 == 4 {
+                item.remove();
+            }
+        }
+        confirm.confirm_removals();
+        assert_eq!(*log.borrow(), vec![0, 2, 6, 5, 3, 4]);
+        let mut kept = a.iter().map(|l| l.0).collect::<Vec<_>>();
+        kept.sort();
+        assert_eq!(kept, vec![1, 7]);
+    }
+
+    #[test]
+    fn test_remove_now_drops_at_mark_time() {
+        let log = RefCell::new(Vec::new());
+        let mut a = (1..=4).map(|i| Logged(i, &log)).collect::<Vec<_>>();
+        let mut confirm = a.removable_confirm_iter();
+        for item in confirm.iter() {
+            match item.get().0 {
+                2 => item.remove_now_with(Logged(20, &log)),
+                3 => item.remove(),
+                _ => {}
+            }
+            if log.borrow().is_empty() {
+                continue;
+            }
+            // the eagerly removed element is gone before the pass ends
+            assert_eq!(*log.borrow(), vec![2]);
+        }
+        confirm.confirm_removals();
+        assert_eq!(*log.borrow(), vec![2, 20, 3]);
+        assert_eq!(a.iter().map(|l| l.0).collect::<Vec<_>>(), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_resume_across_calls() {
+        fn mark(v: &mut Vec<i32>, odd: i32) -> super::ConfirmState {
+            let mut confirm = v.removable_confirm_iter();
+            for item in confirm.iter() {
+                if *item.get() == odd {
+                    item.remove();
+                }
+            }
+            confirm.suspend()
+        }
+
+        let mut a = vec![1, 2, 3, 4, 5];
+        let state = mark(&mut a, 1);
+        let mut confirm = state.resume(&mut a).unwrap();
+        for item in confirm.iter() {
+            if *item.get() == 3 {
+                item.remove();
+            }
+        }
+        let state = confirm.suspend();
+        assert_eq!(state.marked(), 2);
+        state.resume(&mut a).unwrap().confirm_removals();
+        assert_eq!(a, vec![5, 2, 4]);
+
+        let state = mark(&mut a, 2);
+        a.push(6);
+        assert_eq!(state.clone().resume(&mut a).err(), Some(StaleConfirmState));
+        a.pop();
+        // the same length and allocation cannot be told apart from an unchanged vector
+        state.resume(&mut a).unwrap().cancel_removals();
+        assert_eq!(a.len(), 3);
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::{Deref, DerefMut};
+
 /// A trait for items that can be removed from their container during iteration.
 ///
 /// This trait is implemented by iterator items that allow in-place removal of elements
@@ -44,6 +46,20 @@ pub trait RemovableItem<T> {
     /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
     /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
     fn get(&self) -> &T;
+
+    /// Returns a reference to the target of the current item, e.g. `&dyn Trait` for
+    /// `Box<dyn Trait>` elements.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn<'s, U: ?Sized>(&'s self) -> &'s U
+    where
+        T: Deref<Target = U> + 's,
+    {
+        self.get()
+    }
 }
 
 /// A trait for items that can be removed from their container during iteration, with mutable access.
@@ -104,4 +120,32 @@ pub trait RemovableItemMut<T> {
     /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
     /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
     fn get_mut(&mut self) -> &mut T;
+
+    /// Returns a reference to the target of the current item, e.g. `&dyn Trait` for
+    /// `Box<dyn Trait>` elements.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn<'s, U: ?Sized>(&'s self) -> &'s U
+    where
+        T: Deref<Target = U> + 's,
+    {
+        self.get()
+    }
+
+    /// Returns a mutable reference to the target of the current item, e.g. `&mut dyn Trait`
+    /// for `Box<dyn Trait>` elements.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn_mut<'s, U: ?Sized>(&'s mut self) -> &'s mut U
+    where
+        T: DerefMut<Target = U> + 's,
+    {
+        self.get_mut()
+    }
 }   
\ No newline at end of file
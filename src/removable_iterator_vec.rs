@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use crate::prelude::InplaceVector;
+    use crate::prelude::{InplaceVector, OnError, VisitationPolicy};
+    use crate::prelude::InplaceIterator;
     use crate::prelude::RemovableItem;
+    use crate::prelude::RemovableItemMut;
 
     #[test]
     fn test_removable_iterator_basic() {
@@ -30,6 +32,7 @@ mod tests {
         assert!(a.is_empty());
     }
     #[test]
+    #[allow(clippy::while_let_on_iterator)]
     fn test_removable_iterator_remove_all_in_while() {
         let mut a = vec![1, 2, 3, 4, 5];
         let mut iter = a.removable_iter();
@@ -59,4 +62,418 @@ mod tests {
         drop(iter);
         assert_eq!(a, vec![1, 2, 3, 4, 5]);
     }
+
+    #[test]
+    fn test_removable_iterator_from() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let visited = a.removable_iter_from(2, false).map(|i| *i.get()).collect::<Vec<_>>();
+        assert_eq!(visited, vec![3, 4, 5]);
+        assert!(a.removable_iter_from(5, false).next().is_none());
+        assert!(a.removable_iter_from(7, false).next().is_none());
+    }
+
+    #[test]
+    fn test_removable_iterator_from_wrapping() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let mut visited = Vec::new();
+        let iter = a.removable_iter_from(4, true);
+        assert_eq!(iter.size_hint(), (6, Some(6)));
+        for item in iter {
+            visited.push(*item.get());
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+        }
+        visited.sort();
+        assert_eq!(visited, vec![1, 2, 3, 4, 5, 6]);
+        a.sort();
+        assert_eq!(a, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_removable_iterator_from_wrapping_remove_all() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let mut visited = 0;
+        for item in a.removable_iter_from(3, true) {
+            visited += 1;
+            item.remove();
+        }
+        assert_eq!(visited, 6);
+        assert!(a.is_empty());
+
+        let mut a = vec![1, 2, 3];
+        let iter = a.removable_iter_from(5, true);
+        assert_eq!(iter.lookahead().count(), 3);
+        assert_eq!(iter.map(|i| *i.get()).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    trait Shape {
+        fn area(&self) -> u32;
+        fn grow(&mut self);
+    }
+
+    struct Square(u32);
+
+    impl Shape for Square {
+        fn area(&self) -> u32 {
+            self.0 * self.0
+        }
+
+        fn grow(&mut self) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_removable_boxed_trait_objects() {
+        let mut a: Vec<Box<dyn Shape>> = (1..=4).map(|s| Box::new(Square(s)) as Box<dyn Shape>).collect();
+        for item in a.removable_iter() {
+            if item.get_dyn().area() > 4 {
+                item.remove();
+            }
+        }
+        for mut item in a.removable_iter_mut() {
+            item.get_dyn_mut().grow();
+        }
+        assert_eq!(a.iter().map(|s| s.area()).collect::<Vec<_>>(), vec![4, 9]);
+    }
+
+    #[test]
+    fn test_replace_where() {
+        let mut a = vec![1, -2, 3, -4];
+        assert_eq!(a.replace_where(|v| *v < 0, |v| -v), 2);
+        assert_eq!(a, vec![1, 2, 3, 4]);
+        assert_eq!(a.replace_where(|v| *v > 10, |_| 0), 0);
+    }
+
+    #[test]
+    fn test_merge_duplicates_by_key() {
+        let mut a = vec![3, 1, 13, 21, 5, 11, 2];
+        let merged = a.merge_duplicates_by_key(|v| v % 10, |kept, dup| *kept += dup);
+        assert_eq!(merged, 3);
+        a.sort();
+        assert_eq!(a, vec![2, 5, 16, 33]);
+    }
+
+    #[test]
+    fn test_retain_fold() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let (count, sum) = a.retain_fold((0, 0), |(count, sum), v| {
+            *v *= 10;
+            if *v > 30 {
+                *count += 1;
+                *sum += *v;
+                return false;
+            }
+            true
+        });
+        assert_eq!((count, sum), (3, 150));
+        assert_eq!(a, vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn test_try_retain_inplace() {
+        let mut a = vec![1, 2, 3, 4];
+        assert_eq!(a.try_retain_inplace(OnError::Rollback, |v| Ok::<_, ()>(*v % 2 == 1)), Ok(2));
+        assert_eq!(a, vec![1, 3]);
+
+        let mut a = vec![1, 2, 3, 4];
+        let result = a.try_retain_inplace(OnError::Rollback, |v| if *v < 3 { Ok(false) } else { Err(*v) });
+        assert_eq!(result, Err(4));
+        a.sort();
+        assert_eq!(a, vec![1, 2, 3, 4]);
+
+        // removing 1 swaps in 4, which fails before 2 is visited
+        let result = a.try_retain_inplace(OnError::Apply, |v| if *v < 3 { Ok(false) } else { Err(*v) });
+        assert_eq!(result, Err(4));
+        assert_eq!(a, vec![4, 2, 3]);
+    }
+
+    #[test]
+    fn test_retain_parallel() {
+        let mut a = (0..1000).collect::<Vec<_>>();
+        assert_eq!(a.retain_parallel(|v| v % 7 == 0, 3), 857);
+        a.sort();
+        assert_eq!(a, (0..1000).step_by(7).collect::<Vec<_>>());
+        let mut empty: Vec<u8> = Vec::new();
+        assert_eq!(empty.retain_parallel(|_| false, 8), 0);
+        // a single thread evaluates on the calling one
+        let caller = std::thread::current().id();
+        assert_eq!(a.retain_parallel(|v| std::thread::current().id() == caller && v % 2 == 0, 1), 71);
+    }
+
+    /// Removes the even elements and returns the visited ones, in the visiting order.
+    fn visit_removing_even(a: &mut Vec<i32>, policy: VisitationPolicy) -> Vec<i32> {
+        let mut visited = Vec::new();
+        let mut iter = a.removable_iter_with(policy);
+        while let Some(item) = iter.next() {
+            visited.push(*item.get());
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+            assert_eq!(iter.size_hint().0, iter.lookahead().count());
+        }
+        visited
+    }
+
+    #[test]
+    fn test_revisit_swapped_visits_each_element_once() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let visited = visit_removing_even(&mut a, VisitationPolicy::RevisitSwapped);
+        // 6 is swapped into the place of 2 and visited there, then 5 into its place
+        assert_eq!(visited, vec![1, 2, 6, 5, 3, 4]);
+        assert_eq!(a, vec![1, 5, 3]);
+    }
+
+    #[test]
+    fn test_skip_swapped_does_not_visit_swapped_elements() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let visited = visit_removing_even(&mut a, VisitationPolicy::SkipSwapped);
+        // 6 is swapped into the place of 2 and skipped, 5 into the place of 4
+        assert_eq!(visited, vec![1, 2, 3, 4]);
+        assert_eq!(a, vec![1, 6, 3, 5]);
+    }
+
+    #[test]
+    fn test_skip_swapped_removing_the_last_element() {
+        let mut a = vec![2, 4];
+        let visited = visit_removing_even(&mut a, VisitationPolicy::SkipSwapped);
+        assert_eq!(visited, vec![2]);
+        assert_eq!(a, vec![4]);
+        let mut a = vec![1, 4];
+        assert_eq!(visit_removing_even(&mut a, VisitationPolicy::SkipSwapped), vec![1, 4]);
+        assert_eq!(a, vec![1]);
+    }
+
+    #[test]
+    fn test_exactly_once_over_permutations() {
+        // the elements are their original indices, so the visits can be traced back to them
+        let mut seed = 0x2545_f491_u64;
+        let mut random = move |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+        for len in 0..40 {
+            for _ in 0..10 {
+                let mut a = (0..len).collect::<Vec<_>>();
+                let start = random(len + 1);
+                let wrap = random(2) == 0;
+                let mut visited = Vec::new();
+                let mut removed = Vec::new();
+                for item in a.removable_iter_from(start, wrap) {
+                    visited.push(*item.get());
+                    if random(3) == 0 {
+                        removed.push(*item.get());
+                        item.remove();
+                    }
+                }
+                visited.sort();
+                let expected = if wrap { (0..len).collect::<Vec<_>>() } else { (start..len).collect() };
+                assert_eq!(visited, expected, "len {len}, start {start}, wrap {wrap}");
+                a.extend(removed);
+                a.sort();
+                assert_eq!(a, (0..len).collect::<Vec<_>>());
+            }
+        }
+        assert_eq!(VisitationPolicy::EXACTLY_ONCE, VisitationPolicy::default());
+    }
+
+    #[test]
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[cfg_attr(miri, ignore)]
+    #[should_panic(expected = "changed during the iteration")]
+    fn test_detects_changes_outside_the_items() {
+        let mut a = vec![1, 2, 3];
+        let alias = &mut a as *mut Vec<i32>;
+        let mut iter = a.removable_iter();
+        iter.next().unwrap().remove();
+        // simulates a bug changing the vector behind the iterator's back
+        unsafe { (*alias).push(4) };
+        iter.next();
+    }
+
+    #[test]
+    fn test_zero_sized_elements() {
+        use crate::prelude::RemovableConfirmIterator;
+
+        let mut a = vec![(); 10];
+        let mut visited = 0;
+        for item in a.removable_iter() {
+            visited += 1;
+            if visited % 3 == 0 {
+                item.remove();
+            }
+        }
+        assert_eq!((visited, a.len()), (10, 7));
+
+        let mut visited = 0;
+        for item in a.removable_iter_from(4, true) {
+            visited += 1;
+            if visited % 2 == 0 {
+                item.remove();
+            }
+        }
+        assert_eq!((visited, a.len()), (7, 4));
+
+        let mut visited = 0;
+        for item in a.ordered_removable_iter() {
+            visited += 1;
+            if visited == 2 {
+                item.remove();
+            }
+        }
+        assert_eq!((visited, a.len()), (4, 3));
+
+        let mut confirm = a.removable_confirm_iter();
+        for item in confirm.iter() {
+            item.remove();
+        }
+        confirm.confirm_removals();
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_expire_where_instants() {
+        use std::time::{Duration, Instant};
+
+        let now = Instant::now();
+        let mut entries = (0..6u64).map(|i| (i, now + Duration::from_secs(i * 10))).collect::<Vec<_>>();
+        let later = now + Duration::from_secs(25);
+        let mut expired = entries.take_expired_where(later, |e| e.1).into_iter().map(|e| e.0).collect::<Vec<_>>();
+        expired.sort();
+        assert_eq!(expired, vec![0, 1, 2]);
+        // the deadline equal to `now` is expired
+        assert_eq!(entries.expire_where(now + Duration::from_secs(30), |e| e.1), 1);
+        assert_eq!(entries.expire_where(now, |e| e.1), 0);
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn test_keep_top_n() {
+        let mut a = vec![7, 3, 9, 1, 9, 4];
+        let mut below = a.take_below_top_n(3, |v| *v);
+        below.sort();
+        a.sort();
+        assert_eq!((a.clone(), below), (vec![7, 9, 9], vec![1, 3, 4]));
+        assert_eq!(a.keep_top_n(5, |v| *v), 0);
+        assert_eq!(a.keep_top_n(0, |v| *v), 3);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_remove_all_eq() {
+        let mut a = vec![2, 2, 1, 2, 3, 2, 2];
+        assert_eq!(a.remove_all_eq(&2), 5);
+        a.sort();
+        assert_eq!(a, vec![1, 3]);
+        assert_eq!(a.remove_all_eq(&4), 0);
+        assert_eq!(a.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "u32-indices")]
+    #[should_panic(expected = "more elements than the item indices can address")]
+    fn test_u32_indices_checked() {
+        let mut a = Vec::<()>::new();
+        // zero sized elements need no memory, nor initialization
+        #[allow(clippy::uninit_vec)]
+        unsafe {
+            a.set_len(u32::MAX as usize + 1)
+        };
+        a.removable_iter().count();
+    }
+
+    #[test]
+    fn test_split_take() {
+        let mut a = (0..20).collect::<Vec<_>>();
+        let mut b_calls = 0;
+        let (mut threes, mut fives) = a.split_take(|v| v % 3 == 0, |v| {
+            b_calls += 1;
+            v % 5 == 0
+        });
+        threes.sort();
+        fives.sort();
+        assert_eq!((threes, fives), (vec![0, 3, 6, 9, 12, 15, 18], vec![5, 10]));
+        assert_eq!(b_calls, 13);
+        assert_eq!(a.len(), 11);
+    }
+
+    #[test]
+    fn test_try_bulk_variants() {
+        let mut v = (0..10).collect::<Vec<_>>();
+        let mut expired = Vec::new();
+        assert_eq!(v.try_take_expired_where(2, |x| *x, &mut expired), Ok(3));
+        expired.sort();
+        assert_eq!(expired, vec![0, 1, 2]);
+
+        let (mut even, mut big) = (Vec::new(), Vec::new());
+        assert_eq!(v.try_split_take(|x| x % 2 == 0, |x| *x > 6, &mut even, &mut big), Ok(5));
+        even.sort();
+        big.sort();
+        assert_eq!((even, big), (vec![4, 6, 8], vec![7, 9]));
+
+        let mut below = v.try_take_below_top_n(1, |x| *x).unwrap();
+        below.sort();
+        assert_eq!((v, below), (vec![5], vec![3]));
+    }
+
+    #[test]
+    fn test_count_after_pending_removal() {
+        let mut v = (0..10).collect::<Vec<_>>();
+        let mut iter = v.removable_iter();
+        iter.next().unwrap().remove();
+        // the swapped in element is still to be visited
+        assert_eq!(iter.count(), 9);
+        let mut iter = v.removable_iter_with(VisitationPolicy::SkipSwapped);
+        iter.next().unwrap().remove();
+        assert_eq!(iter.count(), 7);
+        let mut iter = v.removable_iter_from(5, true);
+        iter.next().unwrap();
+        assert_eq!(iter.count(), 7);
+
+        let mut iter = v.ordered_removable_iter();
+        iter.next().unwrap().remove();
+        assert_eq!(iter.count(), 7);
+        assert_eq!(v.len(), 7);
+
+        let mut visited = 0;
+        v.removable_iter().for_each(|item| {
+            visited += 1;
+            if *item.get() % 2 == 0 {
+                item.remove();
+            }
+        });
+        assert_eq!(visited, 7);
+        assert!(v.iter().all(|x| x % 2 == 1));
+    }
+
+    #[test]
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
+    #[should_panic]
+    fn test_last_item_is_invalid() {
+        let mut v = vec![1, 2, 3];
+        let last = v.removable_iter().last().unwrap();
+        last.remove();
+    }
+
+    #[test]
+    fn test_small_copy_and_droppable_removal() {
+        let mut wide = (0..8u128).map(|x| x << 64).collect::<Vec<_>>();
+        for item in wide.removable_iter() {
+            if (*item.get() >> 64) % 3 != 1 {
+                item.remove();
+            }
+        }
+        assert_eq!(wide, vec![7 << 64, 1 << 64, 4 << 64]);
+
+        let mut names = (0..8).map(|x| x.to_string()).collect::<Vec<_>>();
+        for item in names.removable_iter() {
+            if item.get().parse::<u32>().unwrap() % 3 != 1 {
+                item.remove();
+            }
+        }
+        assert_eq!(names, vec!["7", "1", "4"]);
+    }
 }
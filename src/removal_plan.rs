@@ -0,0 +1,380 @@
+//! Two-phase removal: the decisions are recorded in a scan without touching the vector, and
+//! applied later in bulk.
+//!
+//! Unlike the confirm iterator, the vector is never permuted before the plan is applied, so the
+//! recorded decisions can be inspected by the index of the element they belong to.
+//!
+//! The same holds for combining the decisions of several criteria, with
+//! [`RemovalPlan::intersect`] and [`RemovalPlan::union`]. Confirm sessions cannot be combined:
+//! a session holds the vector borrowed until it is confirmed or cancelled, so a second one
+//! cannot run beside it, and cancelling it does not restore the order its marks were made in.
+
+use crate::mark_set::MarkSet;
+
+/// The decision made for an element during the scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The element stays in the vector.
+    Keep,
+    /// The element is removed when the plan is applied.
+    Remove,
+}
+
+/// The decisions recorded by [`PlanRemovals::plan_removals`], one for each scanned element.
+///
+/// The marks are kept in `M`, which can be a storage borrowed from the caller, see
+/// [`PlanRemovals::plan_removals_into`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalPlan<M = Vec<bool>> {
+    /// The elements marked for removal.
+    marks: M,
+    /// The number of scanned elements.
+    len: usize,
+    /// The number of elements marked for removal.
+    removals: usize,
+}
+
+impl<M: MarkSet> RemovalPlan<M> {
+    /// The number of scanned elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the scanned vector was empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements marked for removal.
+    pub fn removals(&self) -> usize {
+        self.removals
+    }
+
+    /// The decision for the element at `index`, or `None` if `index` is out of the scanned range.
+    pub fn decision(&self, index: usize) -> Option<Decision> {
+        if index >= self.len {
+            return None;
+        }
+        Some(if self.marks.is_marked(index) { Decision::Remove } else { Decision::Keep })
+    }
+
+    /// The decisions in the order of the scanned elements.
+    pub fn decisions(&self) -> impl Iterator<Item = Decision> + '_ {
+        (0..self.len()).filter_map(|index| self.decision(index))
+    }
+
+    /// The indices of the elements marked for removal, in ascending order.
+    pub fn marked(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&index| self.marks.is_marked(index))
+    }
+
+    /// Keeps only the marks also set by `other`, so only the elements marked by both scans are
+    /// removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plans were made for vectors of different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5, 6];
+    /// let mut even = numbers.plan_removals(|n| if n % 2 == 0 { Decision::Remove } else { Decision::Keep });
+    /// let large = numbers.plan_removals(|n| if *n > 3 { Decision::Remove } else { Decision::Keep });
+    /// even.intersect(&large);
+    /// assert_eq!(even.apply(&mut numbers), 2);
+    /// assert_eq!(numbers, vec![1, 2, 3, 5]);
+    /// ```
+    pub fn intersect<N: MarkSet>(&mut self, other: &RemovalPlan<N>) {
+        self.combine(other, |marked, other| marked && other);
+    }
+
+    /// Adds the marks set by `other`, so the elements marked by either scan are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plans were made for vectors of different lengths.
+    pub fn union<N: MarkSet>(&mut self, other: &RemovalPlan<N>) {
+        self.combine(other, |marked, other| marked || other);
+    }
+
+    fn combine<N: MarkSet>(&mut self, other: &RemovalPlan<N>, op: impl Fn(bool, bool) -> bool) {
+        assert_eq!(self.len, other.len, "the plans were made for vectors of different lengths");
+        self.removals = 0;
+        for index in 0..self.len {
+            let marked = op(self.marks.is_marked(index), other.marks.is_marked(index));
+            self.marks.set_mark(index, marked);
+            self.removals += usize::from(marked);
+        }
+    }
+
+    /// Returns the mark storage, so it can be reused for the next scan.
+    pub fn into_marks(self) -> M {
+        self.marks
+    }
+
+    /// Removes the marked elements from `v` in a single pass and returns how many were removed.
+    ///
+    /// The order of the remaining elements is preserved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` does not have the same length as the scanned vector.
+    pub fn apply<T>(&self, v: &mut Vec<T>) -> usize {
+        assert_eq!(v.len(), self.len, "the plan was made for a vector of a different length");
+        let mut index = 0;
+        v.retain(|_| {
+            index += 1;
+            !self.marks.is_marked(index - 1)
+        });
+        self.removals
+    }
+}
+
+/// What a removal pass would do, computed by [`PlanRemovals::evaluate_removals`] without
+/// modifying the collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalReport {
+    /// The number of scanned elements.
+    pub scanned: usize,
+    /// The number of elements which would be removed.
+    pub removals: usize,
+    /// The estimate of the freed bytes, the sum of the sizes of the removed elements.
+    pub bytes: usize,
+    /// The indices of the first (at most [`RemovalReport::SAMPLES`]) elements which would be
+    /// removed, in ascending order.
+    pub samples: Vec<usize>,
+}
+
+impl RemovalReport {
+    /// The maximum number of sampled indices.
+    pub const SAMPLES: usize = 8;
+}
+
+/// A mark storage keeping only the first marked indices, for the report.
+struct SampledMarks(Vec<usize>);
+
+impl MarkSet for SampledMarks {
+    fn reset(&mut self, _len: usize) {
+        self.0.clear();
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        self.0.contains(&index)
+    }
+
+    fn set_mark(&mut self, index: usize, marked: bool) {
+        if marked && self.0.len() < RemovalReport::SAMPLES {
+            self.0.push(index);
+        }
+    }
+}
+
+/// A trait for collections which can be scanned for removals without being modified.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3, 4, 5];
+/// let plan = numbers.plan_removals(|n| if n % 2 == 0 { Decision::Remove } else { Decision::Keep });
+/// // nothing is changed until the plan is applied
+/// assert_eq!(numbers, vec![1, 2, 3, 4, 5]);
+/// assert_eq!(plan.marked().collect::<Vec<_>>(), vec![1, 3]);
+///
+/// assert_eq!(plan.apply(&mut numbers), 2);
+/// assert_eq!(numbers, vec![1, 3, 5]);
+/// ```
+pub trait PlanRemovals<T> {
+    /// Records a decision for every element, without modifying the collection.
+    fn plan_removals<F>(&self, f: F) -> RemovalPlan
+    where
+        F: FnMut(&T) -> Decision,
+    {
+        self.plan_removals_into(Vec::new(), f)
+    }
+
+    /// Records a decision for every element into the given mark storage, without modifying the
+    /// collection.
+    ///
+    /// The storage is reset before the scan. Passing `&mut marks` keeps the storage with the
+    /// caller, so it can be reused by the next scan.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5];
+    /// let mut marks: Vec<u64> = Vec::new();
+    /// let plan = numbers.plan_removals_into(&mut marks, |n| if *n > 3 { Decision::Remove } else { Decision::Keep });
+    /// assert_eq!(plan.apply(&mut numbers), 2);
+    /// assert_eq!(marks, vec![0b11000]);
+    /// ```
+    fn plan_removals_into<M, F>(&self, marks: M, f: F) -> RemovalPlan<M>
+    where
+        M: MarkSet,
+        F: FnMut(&T) -> Decision;
+
+    /// Reports what removing the elements matching `pred` would do, without modifying the
+    /// collection. `size` estimates the bytes freed by removing an element.
+    ///
+    /// The scan is the one of [`plan_removals`](Self::plan_removals), so the report matches
+    /// the plan made with the same predicate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let names = vec!["ann".to_string(), "bob".to_string(), "christopher".to_string()];
+    /// let report = names.evaluate_removals(|n| n.len() > 2, |n| n.capacity());
+    /// assert_eq!((report.removals, report.bytes), (3, 17));
+    /// assert_eq!(report.samples, vec![0, 1, 2]);
+    /// ```
+    fn evaluate_removals<F, S>(&self, mut pred: F, mut size: S) -> RemovalReport
+    where
+        F: FnMut(&T) -> bool,
+        S: FnMut(&T) -> usize,
+    {
+        let mut bytes = 0;
+        let plan = self.plan_removals_into(SampledMarks(Vec::new()), |value| {
+            if pred(value) {
+                bytes += size(value);
+                Decision::Remove
+            } else {
+                Decision::Keep
+            }
+        });
+        RemovalReport {
+            scanned: plan.len(),
+            removals: plan.removals(),
+            bytes,
+            samples: plan.into_marks().0,
+        }
+    }
+}
+
+impl<T> PlanRemovals<T> for Vec<T> {
+    fn plan_removals_into<M, F>(&self, marks: M, f: F) -> RemovalPlan<M>
+    where
+        M: MarkSet,
+        F: FnMut(&T) -> Decision,
+    {
+        self.as_slice().plan_removals_into(marks, f)
+    }
+}
+
+impl<T> PlanRemovals<T> for [T] {
+    fn plan_removals_into<M, F>(&self, mut marks: M, mut f: F) -> RemovalPlan<M>
+    where
+        M: MarkSet,
+        F: FnMut(&T) -> Decision,
+    {
+        marks.reset(self.len());
+        let mut removals = 0;
+        for (index, value) in self.iter().enumerate() {
+            if f(value) == Decision::Remove {
+                marks.set_mark(index, true);
+                removals += 1;
+            }
+        }
+        RemovalPlan { marks, len: self.len(), removals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::{Decision, PlanRemovals};
+    use super::RemovalReport;
+
+    #[test]
+    fn test_plan_decisions() {
+        let a = vec![1, 2, 3, 4];
+        let plan = a.plan_removals(|v| if *v > 2 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(plan.len(), 4);
+        assert_eq!(plan.removals(), 2);
+        assert_eq!(
+            plan.decisions().collect::<Vec<_>>(),
+            vec![Decision::Keep, Decision::Keep, Decision::Remove, Decision::Remove]
+        );
+        assert_eq!(plan.decision(4), None);
+    }
+
+    #[test]
+    fn test_plan_apply_keeps_order() {
+        let mut a = vec![5, 1, 4, 2, 3];
+        let plan = a.plan_removals(|v| if v % 2 == 0 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(plan.apply(&mut a), 2);
+        assert_eq!(a, vec![5, 1, 3]);
+    }
+
+    #[test]
+    fn test_plan_reuses_marks() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let mut marks = [true; 8];
+        let plan = a.plan_removals_into(&mut marks[..], |v| if *v == 3 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(plan.marked().collect::<Vec<_>>(), vec![2]);
+        plan.apply(&mut a);
+        assert_eq!(marks[..5], [false, false, true, false, false]);
+
+        let mut bits = vec![u64::MAX; 2];
+        let plan = a.plan_removals_into(&mut bits, |v| if *v > 4 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(plan.removals(), 2);
+        assert_eq!(plan.apply(&mut a), 2);
+        assert_eq!(a, vec![1, 2, 4]);
+        assert_eq!(bits, vec![0b11000]);
+    }
+
+    #[test]
+    fn test_plan_union() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        let mut small = a.plan_removals(|v| if *v < 2 { Decision::Remove } else { Decision::Keep });
+        let large = a.plan_removals_into(Vec::<u64>::new(), |v| if *v > 4 { Decision::Remove } else { Decision::Keep });
+        small.union(&large);
+        assert_eq!(small.removals(), 3);
+        small.intersect(&large);
+        assert_eq!(small.removals(), 2);
+        small.union(&small.clone());
+        assert_eq!(small.apply(&mut a), 2);
+        assert_eq!(a, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_evaluate_matches_plan() {
+        let a = (0..40).collect::<Vec<u32>>();
+        let report = a.evaluate_removals(|v| v % 3 == 0, |_| 4);
+        let plan = a.plan_removals(|v| if v % 3 == 0 { Decision::Remove } else { Decision::Keep });
+        assert_eq!(report.scanned, 40);
+        assert_eq!(report.removals, plan.removals());
+        assert_eq!(report.bytes, 4 * plan.removals());
+        assert_eq!(report.samples, plan.marked().take(RemovalReport::SAMPLES).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_plan_combine_other_length() {
+        let mut plan = vec![1, 2].plan_removals(|_| Decision::Keep);
+        plan.union(&vec![1].plan_removals(|_| Decision::Remove));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_plan_into_too_small_storage() {
+        let a = vec![1, 2, 3];
+        let mut marks = [false; 2];
+        a.plan_removals_into(&mut marks[..], |_| Decision::Keep);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_plan_apply_other_length() {
+        let mut a = vec![1, 2, 3];
+        let plan = a.plan_removals(|_| Decision::Keep);
+        a.push(4);
+        plan.apply(&mut a);
+    }
+}
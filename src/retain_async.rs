@@ -0,0 +1,248 @@
+//! Retaining elements by asynchronous predicates, and vectors of futures.
+//!
+//! The returned future is runtime agnostic: it polls the predicate futures itself, at most
+//! `limit` of them at once, and removes the rejected elements when all of them resolved.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::inplace_vector::InplaceVector;
+use crate::takeable_iterator::TakeableItemMut;
+
+/// A trait that extends vectors with asynchronous retain.
+pub trait RetainAsync<T> {
+    /// Returns a future which evaluates `f` for every element, with at most `limit` futures
+    /// in flight, and then removes the elements whose future resolved to `false`. The future
+    /// resolves to the number of removed elements.
+    ///
+    /// The futures must not borrow the element, clone what they need from it.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the order of elements is not preserved
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    fn retain_async<F, Fut>(&mut self, limit: usize, f: F) -> RetainAsyncFuture<'_, T, F, Fut>
+    where
+        F: FnMut(&T) -> Fut,
+        Fut: Future<Output = bool>;
+}
+
+impl<T> RetainAsync<T> for Vec<T> {
+    fn retain_async<F, Fut>(&mut self, limit: usize, f: F) -> RetainAsyncFuture<'_, T, F, Fut>
+    where
+        F: FnMut(&T) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        assert!(limit > 0, "at least one future must be allowed in flight");
+        RetainAsyncFuture {
+            keep: vec![true; self.len()],
+            vector: self,
+            f,
+            limit,
+            started: 0,
+            in_flight: Vec::new(),
+        }
+    }
+}
+
+/// A trait that extends vectors of futures with polling them in place.
+///
+/// A lightweight alternative to a `FuturesUnordered`, for a few futures: every poll polls all
+/// of them.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::{ready, Future};
+/// use std::pin::Pin;
+/// use std::task::{Context, Waker};
+/// use inplace_iter::prelude::*;
+///
+/// let mut jobs: Vec<Pin<Box<dyn Future<Output = u32>>>> = vec![Box::pin(ready(1)), Box::pin(std::future::pending()), Box::pin(ready(3))];
+/// let mut cx = Context::from_waker(Waker::noop());
+/// let mut done = jobs.poll_take_ready(&mut cx);
+/// done.sort();
+/// assert_eq!(done, vec![1, 3]);
+/// assert_eq!(jobs.len(), 1);
+/// ```
+pub trait PollTakeReady<Fut: Future> {
+    /// Polls every future once and takes the completed ones out of the vector, returning their
+    /// outputs.
+    ///
+    /// The futures are moved when others are taken, so they must be `Unpin`, e.g. boxed with
+    /// `Box::pin`. The waker of `cx` is registered by the pending futures, as usual.
+    ///
+    /// # Performance
+    ///
+    /// - Taking a future is O(1) time complexity, the order of the futures is not preserved
+    fn poll_take_ready(&mut self, cx: &mut Context<'_>) -> Vec<Fut::Output>;
+}
+
+impl<Fut> PollTakeReady<Fut> for Vec<Fut>
+where
+    Fut: Future + Unpin,
+{
+    fn poll_take_ready(&mut self, cx: &mut Context<'_>) -> Vec<Fut::Output> {
+        let mut outputs = Vec::new();
+        for mut item in self.takeable_iter_mut() {
+            if let Poll::Ready(output) = Pin::new(item.get_mut()).poll(cx) {
+                // a completed future must not be polled again, it is dropped
+                drop(item.take());
+                outputs.push(output);
+            }
+        }
+        outputs
+    }
+}
+
+/// The future returned by [`RetainAsync::retain_async`].
+pub struct RetainAsyncFuture<'a, T, F, Fut> {
+    /// The vector, it is not modified until all the futures resolved.
+    vector: &'a mut Vec<T>,
+    /// Creates the future for an element.
+    f: F,
+    /// The maximal number of futures in flight.
+    limit: usize,
+    /// The number of elements whose future was created.
+    started: usize,
+    /// The futures in flight, with the index of their element.
+    in_flight: Vec<(usize, Pin<Box<Fut>>)>,
+    /// The resolved verdicts, `true` until the future of the element resolves to `false`.
+    keep: Vec<bool>,
+}
+
+impl<'a, T, F, Fut> Future for RetainAsyncFuture<'a, T, F, Fut>
+where
+    F: FnMut(&T) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    type Output = usize;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // nothing is pinned structurally, the futures are boxed
+        let this = unsafe { self.get_unchecked_mut() };
+        loop {
+            while this.in_flight.len() < this.limit && this.started < this.vector.len() {
+                let future = (this.f)(&this.vector[this.started]);
+                this.in_flight.push((this.started, Box::pin(future)));
+                this.started += 1;
+            }
+            let before = this.in_flight.len();
+            let keep = &mut this.keep;
+            this.in_flight.retain_mut(|(index, future)| match future.as_mut().poll(cx) {
+                Poll::Ready(verdict) => {
+                    keep[*index] = verdict;
+                    false
+                }
+                Poll::Pending => true,
+            });
+            if this.in_flight.is_empty() && this.started == this.vector.len() {
+                break;
+            }
+            if this.in_flight.len() == before {
+                return Poll::Pending;
+            }
+            // some futures resolved, start the next ones
+        }
+        let mut removed = 0;
+        // removing from the back, the last element is always an already decided one
+        for index in (0..this.keep.len()).rev() {
+            if !this.keep[index] {
+                this.vector.swap_remove(index);
+                removed += 1;
+            }
+        }
+        this.keep.clear();
+        Poll::Ready(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use crate::prelude::{PollTakeReady, RetainAsync};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Resolves to `value` after being polled `polls` times.
+    async fn delayed(value: bool, polls: usize) -> bool {
+        let mut remaining = polls;
+        std::future::poll_fn(|cx| {
+            if remaining == 0 {
+                return Poll::Ready(());
+            }
+            remaining -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await;
+        value
+    }
+
+    #[test]
+    fn test_retain_async() {
+        let mut peers = vec![1, 2, 3, 4, 5, 6, 7];
+        let removed = block_on(peers.retain_async(3, |p| delayed(p % 2 == 1, *p)));
+        assert_eq!(removed, 3);
+        peers.sort();
+        assert_eq!(peers, vec![1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_retain_async_limit() {
+        let mut a = (0..10).collect::<Vec<_>>();
+        let in_flight = std::cell::Cell::new(0);
+        let max = std::cell::Cell::new(0);
+        let removed = block_on(a.retain_async(2, |v| {
+            let v = *v;
+            let (in_flight, max) = (&in_flight, &max);
+            in_flight.set(in_flight.get() + 1);
+            max.set(max.get().max(in_flight.get()));
+            async move {
+                let keep = delayed(v < 5, 2).await;
+                in_flight.set(in_flight.get() - 1);
+                keep
+            }
+        }));
+        assert_eq!(removed, 5);
+        assert_eq!(max.get(), 2);
+        assert_eq!(a.len(), 5);
+    }
+
+    #[test]
+    fn test_poll_take_ready() {
+        let mut jobs = (0..6).map(|i| Box::pin(delayed(i % 2 == 0, i))).collect::<Vec<_>>();
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut polls = 0;
+        let mut done = Vec::new();
+        while !jobs.is_empty() {
+            polls += 1;
+            done.extend(jobs.poll_take_ready(&mut cx));
+        }
+        // the future delayed by `i` polls completes at the poll `i + 1`
+        assert_eq!(polls, 6);
+        assert_eq!(done, vec![true, false, true, false, true, false]);
+    }
+}
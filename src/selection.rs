@@ -0,0 +1,118 @@
+//! Selecting elements in a pass and acting on all of them at once.
+
+/// The elements selected by [`InplaceVector::select`](crate::prelude::InplaceVector::select).
+///
+/// Nothing is changed until `remove_all()` or `take_all()` is called, dropping the selection
+/// leaves the vector as it is.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut numbers = vec![1, 2, 3, 4, 5, 6];
+/// let mut selection = numbers.select(|n| n % 2 == 0);
+/// assert_eq!(selection.len(), 3);
+/// selection.retain(|n| *n > 2);
+/// assert_eq!(selection.take_all(), vec![4, 6]);
+/// assert_eq!(numbers, vec![1, 2, 3, 5]);
+/// ```
+pub struct Selection<'a, T> {
+    /// The vector the elements are selected from.
+    vector: &'a mut Vec<T>,
+    /// The indices of the selected elements, in ascending order.
+    indices: Vec<usize>,
+}
+
+impl<'a, T> Selection<'a, T> {
+    pub(crate) fn new<F>(vector: &'a mut Vec<T>, mut pred: F) -> Self
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let indices = vector.iter().enumerate().filter(|(_, v)| pred(v)).map(|(i, _)| i).collect();
+        Self { vector, indices }
+    }
+
+    /// The number of selected elements.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns `true` if no element is selected.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The indices of the selected elements, in ascending order.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// The selected elements, in their order in the vector.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.indices.iter().map(|&i| &self.vector[i])
+    }
+
+    /// Keeps only the selected elements for which `pred` returns `true`.
+    pub fn retain<F>(&mut self, mut pred: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let vector = &*self.vector;
+        self.indices.retain(|&i| pred(&vector[i]));
+    }
+
+    /// Removes the selected elements and returns how many were removed.
+    ///
+    /// Each removal moves the last element of the vector into the freed place, so the order
+    /// of the remaining elements is not preserved.
+    pub fn remove_all(self) -> usize {
+        let len = self.len();
+        self.take_all();
+        len
+    }
+
+    /// Removes the selected elements and returns them, in the order they had in the vector.
+    ///
+    /// Each removal moves the last element of the vector into the freed place, so the order
+    /// of the remaining elements is not preserved.
+    pub fn take_all(self) -> Vec<T> {
+        // removing from the back, the last element is never a selected one not yet removed
+        let mut taken = self.indices.iter().rev().map(|&i| self.vector.swap_remove(i)).collect::<Vec<_>>();
+        taken.reverse();
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::InplaceVector;
+
+    #[test]
+    fn test_remove_all() {
+        let mut a = (0..20).collect::<Vec<_>>();
+        let removed = a.select(|v| v % 3 != 1).remove_all();
+        assert_eq!(removed, 13);
+        a.sort();
+        assert_eq!(a, vec![1, 4, 7, 10, 13, 16, 19]);
+    }
+
+    #[test]
+    fn test_take_all_with_selected_tail() {
+        let mut a = vec!["a", "b", "c", "d", "e"];
+        let selection = a.select(|s| *s != "b");
+        assert_eq!(selection.iter().copied().collect::<Vec<_>>(), vec!["a", "c", "d", "e"]);
+        assert_eq!(selection.take_all(), vec!["a", "c", "d", "e"]);
+        assert_eq!(a, vec!["b"]);
+    }
+
+    #[test]
+    fn test_dropped_selection_changes_nothing() {
+        let mut a = vec![1, 2, 3];
+        let mut selection = a.select(|_| true);
+        selection.retain(|v| *v == 2);
+        assert_eq!(selection.indices(), &[1]);
+        drop(selection);
+        assert_eq!(a, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,275 @@
+//! In-place removal of segments (characters, grapheme clusters, words...) from a `String`.
+//!
+//! Text must keep its order, so the removed segments are compacted away like in the order
+//! preserving vector iterator: the kept segments are moved down as the iteration moves on, and
+//! the unvisited tail is moved once, when the iterator is dropped.
+
+use std::marker::PhantomData;
+use std::{ptr, slice, str};
+#[cfg(feature = "loop-lifetime-guard")]
+use crate::epoch::{check_detached, is_detached, Epoch, ItemEpoch};
+
+/// A trait that extends strings with methods for in-place removal of segments.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::prelude::*;
+///
+/// let mut text = String::from("a1b2c3");
+/// for item in text.removable_chars() {
+///     if item.get().chars().all(|c| c.is_ascii_digit()) {
+///         item.remove();
+///     }
+/// }
+/// assert_eq!(text, "abc");
+/// ```
+pub trait InplaceString {
+    /// Returns an iterator over the characters, which allows removing them during iteration.
+    fn removable_chars(&mut self) -> RemovableSegments<'_, fn(&str) -> usize>;
+
+    /// Returns an iterator over the segments produced by `split`, which allows removing them
+    /// during iteration.
+    ///
+    /// `split` is called with the unvisited rest of the string and returns the byte length of
+    /// its first segment. This is where a grapheme segmenter plugs in, so user visible
+    /// characters (emoji, combining sequences) are removed as a whole:
+    ///
+    /// ```ignore
+    /// use unicode_segmentation::UnicodeSegmentation;
+    ///
+    /// for item in text.removable_segments(|rest| rest.graphemes(true).next().map_or(0, str::len)) {
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if `split` returns 0 or a length which does not end on a character
+    /// boundary of the rest.
+    fn removable_segments<F>(&mut self, split: F) -> RemovableSegments<'_, F>
+    where
+        F: FnMut(&str) -> usize;
+}
+
+impl InplaceString for String {
+    fn removable_chars(&mut self) -> RemovableSegments<'_, fn(&str) -> usize> {
+        RemovableSegments::new(self, |rest| rest.chars().next().map_or(0, char::len_utf8))
+    }
+
+    fn removable_segments<F>(&mut self, split: F) -> RemovableSegments<'_, F>
+    where
+        F: FnMut(&str) -> usize,
+    {
+        RemovableSegments::new(self, split)
+    }
+}
+
+/// An iterator over the segments of a string, which allows removing them.
+pub struct RemovableSegments<'a, F> {
+    /// The underlying string bytes, their length is 0 while iterating.
+    vector: &'a mut Vec<u8>,
+    /// A raw pointer to the string data for unsafe access.
+    data: *mut u8,
+    /// The length of the string before the iteration.
+    len: usize,
+    /// The byte index of the next segment to visit.
+    read: usize,
+    /// The length of the kept and already moved prefix.
+    write: usize,
+    /// The last visited segment, if it is not yet moved to its place.
+    pending: Option<(usize, usize)>,
+    /// A flag indicating whether the last visited segment has been removed.
+    removed: bool,
+    /// Splits the next segment off the rest.
+    split: F,
+    /// The epoch of the yielded items, advanced to invalidate them.
+    #[cfg(feature = "loop-lifetime-guard")]
+    epoch: Epoch,
+}
+
+impl<'a, F> RemovableSegments<'a, F>
+where
+    F: FnMut(&str) -> usize,
+{
+    fn new(s: &'a mut String, split: F) -> Self {
+        // removing whole segments keeps the string valid UTF-8
+        let vector = unsafe { s.as_mut_vec() };
+        let len = vector.len();
+        // if the iterator is leaked, the string is left empty instead of holding invalid UTF-8
+        unsafe { vector.set_len(0) };
+        Self {
+            data: vector.as_mut_ptr(),
+            vector,
+            len,
+            read: 0,
+            write: 0,
+            pending: None,
+            removed: false,
+            split,
+            #[cfg(feature = "loop-lifetime-guard")]
+            epoch: Epoch::default(),
+        }
+    }
+}
+
+impl<'a, F> RemovableSegments<'a, F> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn rotten_item(&mut self) {
+        self.epoch.advance();
+    }
+
+    /// Moves the last visited segment to its place, unless it was removed.
+    fn settle_last(&mut self) {
+        if let Some((start, end)) = self.pending.take()
+            && !std::mem::take(&mut self.removed)
+        {
+            unsafe { ptr::copy(self.data.add(start), self.data.add(self.write), end - start) };
+            self.write += end - start;
+        }
+    }
+}
+
+impl<'a, F> Drop for RemovableSegments<'a, F> {
+    fn drop(&mut self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        // the tail cannot be moved back into a vector changed behind the iterator, leak it
+        #[cfg(feature = "loop-lifetime-guard")]
+        if !is_detached(self.vector, self.data) {
+            return;
+        }
+        self.settle_last();
+        unsafe {
+            ptr::copy(self.data.add(self.read), self.data.add(self.write), self.len - self.read);
+            self.vector.set_len(self.write + self.len - self.read);
+        }
+    }
+}
+
+impl<'a, F> Iterator for RemovableSegments<'a, F>
+where
+    F: FnMut(&str) -> usize,
+{
+    type Item = RemovableSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.rotten_item();
+        #[cfg(feature = "loop-lifetime-guard")]
+        check_detached(self.vector, self.data);
+        self.settle_last();
+        if self.read >= self.len {
+            return None;
+        }
+        let rest = unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.data.add(self.read), self.len - self.read)) };
+        let segment = (self.split)(rest);
+        assert!(segment > 0 && rest.is_char_boundary(segment), "the segment must end on a character boundary");
+        let start = self.read;
+        self.read += segment;
+        self.pending = Some((start, self.read));
+        #[cfg(feature = "loop-lifetime-guard")]
+        let rotten = self.epoch.item();
+        Some(RemovableSegment {
+            _lifetime_guard: PhantomData,
+            data: unsafe { self.data.add(start) },
+            len: segment,
+            removed: &mut self.removed,
+            #[cfg(feature = "loop-lifetime-guard")]
+            rotten,
+        })
+    }
+}
+
+/// A segment of a string, which can be removed from it.
+///
+/// The segment borrows the string, so it cannot be kept after the string is changed or dropped:
+///
+/// ```compile_fail
+/// use inplace_iter::prelude::*;
+///
+/// let mut text = String::from("abc");
+/// let mut kept = None;
+/// for item in text.removable_chars() {
+///     kept = Some(item);
+/// }
+/// drop(text);
+/// kept.unwrap().get();
+/// ```
+pub struct RemovableSegment<'a> {
+    /// This ties the segment to the borrow of the string by the iterator.
+    _lifetime_guard: PhantomData<&'a mut String>,
+    /// A raw pointer to the first byte of the segment.
+    data: *const u8,
+    /// The byte length of the segment.
+    len: usize,
+    /// An indicator to the iterator that we have removed the segment
+    removed: *mut bool,
+    /// Indicator that this iterator item should no longer be used!
+    #[cfg(feature = "loop-lifetime-guard")]
+    rotten: ItemEpoch,
+}
+
+impl<'a> RemovableSegment<'a> {
+    #[cfg(feature = "loop-lifetime-guard")]
+    fn check_rotten(&self) {
+        self.rotten.check();
+    }
+
+    /// Returns the segment.
+    pub fn get(&self) -> &str {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.data, self.len)) }
+    }
+
+    /// Removes the segment from the string, the rest of the string keeps its order.
+    pub fn remove(self) {
+        #[cfg(feature = "loop-lifetime-guard")]
+        self.check_rotten();
+        unsafe { *self.removed = true };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::InplaceString;
+
+    #[test]
+    fn test_remove_chars() {
+        let mut s = String::from("héllo wörld");
+        for item in s.removable_chars() {
+            if !item.get().is_ascii() || item.get() == " " {
+                item.remove();
+            }
+        }
+        assert_eq!(s, "hllowrld");
+    }
+
+    #[test]
+    fn test_remove_words_early_stop() {
+        let mut s = String::from("one two three four");
+        let mut iter = s.removable_segments(|rest| rest.find(' ').map_or(rest.len(), |i| i + 1));
+        iter.next();
+        iter.next().unwrap().remove();
+        drop(iter);
+        assert_eq!(s, "one three four");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_inside_char() {
+        let mut s = String::from("ü");
+        for _ in s.removable_segments(|_| 1) {}
+    }
+
+    #[test]
+    fn test_leak_leaves_valid_string() {
+        let mut s = String::from("abc");
+        let mut iter = s.removable_chars();
+        iter.next().unwrap().remove();
+        iter.next();
+        std::mem::forget(iter);
+        assert_eq!(s, "");
+    }
+}
@@ -0,0 +1,121 @@
+//! Access to the element which a swap removal moves into the place of the removed one.
+
+/// A trait for the items of swap removing passes, which know the element that replaces them on
+/// removal.
+pub trait SwapItem<T> {
+    /// Returns a reference to the element which would be moved into the place of the current
+    /// one if it was removed now, i.e. the last element of the vector. Returns `None` if the
+    /// current element is the last one, it is popped without a replacement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// // remove an entry only if the entry replacing it is newer
+    /// let mut versions = vec![("a", 1), ("b", 2), ("c", 3)];
+    /// for item in versions.removable_iter() {
+    ///     if item.peek_replacement().is_some_and(|r| r.1 > item.get().1 + 1) {
+    ///         item.remove();
+    ///     }
+    /// }
+    /// assert_eq!(versions, vec![("c", 3), ("b", 2)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn peek_replacement(&self) -> Option<&T>;
+
+    /// Removes the current element and makes the item refer to the element moved into its
+    /// place, which is returned. The iterator does not yield the replacement again, so a loop
+    /// can keep removing through the same item.
+    ///
+    /// Returns `None` if there is no replacement to continue with: the removed element was the
+    /// last one, or the replacement was already visited (in the wrapped around part of
+    /// [`InplaceVector::removable_iter_from`](crate::prelude::InplaceVector::removable_iter_from)).
+    /// The item must not be used after that.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 6, 8];
+    /// let mut visited = 0;
+    /// for mut item in numbers.removable_iter() {
+    ///     visited += 1;
+    ///     let mut current = *item.get();
+    ///     while current % 2 == 0 {
+    ///         match item.remove_and_continue() {
+    ///             Some(next) => current = *next,
+    ///             None => break,
+    ///         }
+    ///     }
+    /// }
+    /// // the even elements were all removed through the item of 2
+    /// assert_eq!(visited, 2);
+    /// assert_eq!(numbers, vec![1, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the item was already removed by this method. With the feature
+    /// `loop-lifetime-guard` enabled, this will panic, if the item was moved outside the loops
+    /// scope. Without the feature, this will cause undefined behavior.
+    fn remove_and_continue(&mut self) -> Option<&T>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::{InplaceVector, RemovableItem, RemovableItemMut, SwapItem, TakeableItem, VisitationPolicy};
+
+    #[test]
+    fn test_peek_replacement() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut replacements = Vec::new();
+        for item in a.takeable_iter_with(VisitationPolicy::SkipSwapped) {
+            replacements.push(item.peek_replacement().copied());
+            if *item.get() == 2 {
+                assert_eq!(item.take(), 2);
+            }
+        }
+        assert_eq!(replacements, vec![Some(4), Some(4), None]);
+        assert_eq!(a, vec![1, 4, 3]);
+    }
+
+    #[test]
+    fn test_remove_and_continue() {
+        let mut a = vec![2, 1, 4, 6, 3];
+        let mut visited = Vec::new();
+        let mut replacements = Vec::new();
+        for mut item in a.removable_iter_mut() {
+            visited.push(*item.get());
+            if *item.get() % 2 == 0 {
+                // the item refers to the swapped in element now
+                replacements.push(*item.remove_and_continue().unwrap());
+                *item.get_mut() *= 10;
+            }
+        }
+        assert_eq!(visited, vec![2, 1, 4]);
+        assert_eq!(replacements, vec![3, 6]);
+        assert_eq!(a, vec![30, 1, 60]);
+    }
+
+    #[test]
+    fn test_remove_and_continue_in_wrapped_part() {
+        let mut a = vec![1, 2, 3];
+        let mut iter = a.removable_iter_from(1, true);
+        assert_eq!(iter.next().unwrap().get(), &2);
+        assert_eq!(iter.next().unwrap().get(), &3);
+        let mut item = iter.next().unwrap();
+        // 3 was already visited
+        assert_eq!(item.remove_and_continue(), None);
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *item.get())).is_err());
+        drop(item);
+        assert!(iter.next().is_none());
+        drop(iter);
+        assert_eq!(a, vec![3, 2]);
+    }
+}
@@ -1,5 +1,20 @@
 //! Traits and implementations for taking ownership of elements during iteration.
 
+use std::ops::{Deref, DerefMut};
+
+/// What [`TakeableItem::decide`] does with an element.
+///
+/// Named apart from [`Decision`](crate::prelude::Decision), which has no slot to take into.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict<'a, T> {
+    /// The element stays in the container, the item is not touched.
+    Keep,
+    /// The element is removed from the container and dropped.
+    Remove,
+    /// The element is taken from the container and pushed into the slot.
+    Take(&'a mut Vec<T>),
+}
+
 /// A trait for items that can be taken from their container during iteration.
 ///
 /// This trait is implemented by iterator items that allow taking ownership of elements
@@ -46,6 +61,114 @@ pub trait TakeableItem<T> {
     /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
     /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
     fn get(&self) -> &T;
+
+    /// Returns a reference to the target of the current item, e.g. `&dyn Trait` for
+    /// `Box<dyn Trait>` elements.
+    ///
+    /// Taking a boxed trait object moves only the box out of the container, the object itself
+    /// stays where it was allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fmt::Display;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut values: Vec<Box<dyn Display>> = vec![Box::new(1), Box::new("two"), Box::new(3.5)];
+    /// let mut taken = Vec::new();
+    /// for item in values.takeable_iter() {
+    ///     if item.get_dyn().to_string().contains('.') {
+    ///         taken.push(item.take());
+    ///     }
+    /// }
+    /// assert_eq!(taken[0].to_string(), "3.5");
+    /// assert_eq!(values.len(), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn<'s, U: ?Sized>(&'s self) -> &'s U
+    where
+        T: Deref<Target = U> + 's,
+    {
+        self.get()
+    }
+
+    /// Keeps, removes or takes the current item, as decided by `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut numbers = vec![1, 2, 3, 4, 5, 6];
+    /// let mut big = Vec::new();
+    /// for item in numbers.takeable_iter() {
+    ///     item.decide(|n| match n {
+    ///         1 | 2 => Verdict::Remove,
+    ///         5.. => Verdict::Take(&mut big),
+    ///         _ => Verdict::Keep,
+    ///     });
+    /// }
+    /// assert_eq!(big, vec![6, 5]);
+    /// assert_eq!(numbers, vec![4, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn decide<'d, F>(self, f: F)
+    where
+        Self: Sized,
+        T: 'd,
+        F: FnOnce(&T) -> Verdict<'d, T>,
+    {
+        match f(self.get()) {
+            Verdict::Keep => {}
+            Verdict::Remove => drop(self.take()),
+            Verdict::Take(slot) => slot.push(self.take()),
+        }
+    }
+
+    /// Takes the current item into `dest`, only if `dest` has spare capacity, so it never
+    /// reallocates.
+    ///
+    /// Returns `false` if `dest` is full, the element then stays in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let mut jobs = vec![1, 2, 3, 4];
+    /// let mut queue = Vec::with_capacity(2);
+    /// let mut full = 0;
+    /// for item in jobs.takeable_iter() {
+    ///     if !item.take_into_spare(&mut queue) {
+    ///         full += 1;
+    ///     }
+    /// }
+    /// assert_eq!((queue, full), (vec![1, 4], 2));
+    /// assert_eq!(jobs, vec![3, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn take_into_spare(self, dest: &mut Vec<T>) -> bool
+    where
+        Self: Sized,
+    {
+        if dest.len() == dest.capacity() {
+            return false;
+        }
+        dest.push(self.take());
+        true
+    }
 }
 
 /// A trait for items that can be taken from their container during iteration, with mutable access.
@@ -105,5 +228,73 @@ pub trait TakeableItemMut<T> {
     ///
     /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
     /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
-    fn get_mut(&self) -> &mut T;
+    fn get_mut(&mut self) -> &mut T;
+
+    /// Returns a reference to the target of the current item, e.g. `&dyn Trait` for
+    /// `Box<dyn Trait>` elements.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn<'s, U: ?Sized>(&'s self) -> &'s U
+    where
+        T: Deref<Target = U> + 's,
+    {
+        self.get()
+    }
+
+    /// Returns a mutable reference to the target of the current item, e.g. `&mut dyn Trait`
+    /// for `Box<dyn Trait>` elements.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn get_dyn_mut<'s, U: ?Sized>(&'s mut self) -> &'s mut U
+    where
+        T: DerefMut<Target = U> + 's,
+    {
+        self.get_mut()
+    }
+
+    /// Keeps, removes or takes the current item, as decided by `f`, which can also change the
+    /// item.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn decide<'d, F>(mut self, f: F)
+    where
+        Self: Sized,
+        T: 'd,
+        F: FnOnce(&mut T) -> Verdict<'d, T>,
+    {
+        match f(self.get_mut()) {
+            Verdict::Keep => {}
+            Verdict::Remove => drop(self.take()),
+            Verdict::Take(slot) => slot.push(self.take()),
+        }
+    }
+
+    /// Takes the current item into `dest`, only if `dest` has spare capacity, so it never
+    /// reallocates.
+    ///
+    /// Returns `false` if `dest` is full, the element then stays in the container.
+    ///
+    /// # Panics
+    ///
+    /// With the feature `loop-lifetime-guard` enabled, this will panic, if the item was
+    /// moved outside the loops scope. Without the feature, this will cause undefined behavior.
+    fn take_into_spare(self, dest: &mut Vec<T>) -> bool
+    where
+        Self: Sized,
+    {
+        if dest.len() == dest.capacity() {
+            return false;
+        }
+        dest.push(self.take());
+        true
+    }
 }
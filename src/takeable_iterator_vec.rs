@@ -1,8 +1,9 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::InplaceVector;
+    use crate::prelude::{InplaceVector, Verdict, VisitationPolicy};
     use crate::prelude::TakeableItem;
+    use crate::prelude::TakeableItemMut;
 
 
     #[test]
@@ -23,7 +24,7 @@ mod tests {
     }
 
 
-    #[cfg(feature = "loop-lifetime-guard")]
+    #[cfg(all(feature = "loop-lifetime-guard", not(feature = "guard-abort")))]
     mod loop_lifetime_guard {
         use crate::prelude::InplaceVector;
         use crate::prelude::TakeableItem;
@@ -44,10 +45,117 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_take_where_into_capacity() {
+        let mut a = vec![1,2,3,4,5,6,7,8];
+        let mut dest = Vec::with_capacity(3);
+        let capacity = dest.capacity();
+        assert_eq!(a.take_where_into_capacity(|v| v % 2 == 0, &mut dest), capacity);
+        assert_eq!(dest.capacity(), capacity);
+        assert_eq!(a.len() + dest.len(), 8);
+        // dest is full, nothing else is taken
+        assert_eq!(a.take_where_into_capacity(|_| true, &mut dest), 0);
+        assert_eq!(a.len() + dest.len(), 8);
+    }
+
     #[test]
     fn test_empty() {
         let mut a: Vec<u32> = vec![];
         let mut iter = a.takeable_iter();
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn test_take_boxed_unsized() {
+        let mut a: Vec<Box<str>> = vec!["a".into(), "bb".into(), "ccc".into()];
+        let mut taken = Vec::new();
+        for mut item in a.takeable_iter_mut() {
+            if item.get_dyn().len() > 1 {
+                taken.push(item.take());
+            } else {
+                item.get_dyn_mut().make_ascii_uppercase();
+            }
+        }
+        taken.sort();
+        assert_eq!(taken, vec!["bb".into(), "ccc".into()] as Vec<Box<str>>);
+        assert_eq!(a, vec!["A".into()] as Vec<Box<str>>);
+    }
+
+    #[test]
+    fn test_takeable_iterator_skip_swapped() {
+        let mut a = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut visited = Vec::new();
+        let mut taken = Vec::new();
+        for mut item in a.takeable_iter_mut_with(VisitationPolicy::SkipSwapped) {
+            visited.push(*item.get());
+            if *item.get() % 3 == 0 {
+                taken.push(item.take());
+            } else {
+                *item.get_mut() *= 10;
+            }
+        }
+        // 8 is swapped into the place of 3 and 7 into the place of 6, neither is visited
+        assert_eq!(visited, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(taken, vec![3, 6]);
+        assert_eq!(a, vec![10, 20, 8, 40, 50, 7]);
+    }
+
+    #[test]
+    fn test_zero_sized_elements() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Debug, PartialEq)]
+        struct ZstMarker;
+
+        impl Drop for ZstMarker {
+            fn drop(&mut self) {
+                DROPS.with(|drops| drops.set(drops.get() + 1));
+            }
+        }
+
+        let mut a = (0..8).map(|_| ZstMarker).collect::<Vec<_>>();
+        let mut taken = Vec::new();
+        for (i, item) in a.takeable_iter().enumerate() {
+            if i % 2 == 0 {
+                taken.push(item.take());
+            }
+        }
+        assert_eq!((a.len(), taken.len()), (4, 4));
+        assert_eq!(DROPS.with(Cell::get), 0);
+
+        let mut merged = 0;
+        for pair in a.pairwise_merge_iter() {
+            merged += 1;
+            pair.merge(|_, _| {});
+        }
+        assert_eq!((merged, a.len()), (3, 1));
+        assert_eq!(DROPS.with(Cell::get), 3);
+
+        a.extend(taken);
+        assert_eq!(a.drain_unordered().take(2).count(), 2);
+        assert!(a.is_empty());
+        assert_eq!(DROPS.with(Cell::get), 3 + 5);
+    }
+
+    #[test]
+    fn test_decide_mut() {
+        let mut a = vec![1, 2, 3, 4];
+        let mut big = Vec::new();
+        for item in a.takeable_iter_mut() {
+            item.decide(|n| {
+                *n *= 10;
+                match n {
+                    20 => Verdict::Remove,
+                    30.. => Verdict::Take(&mut big),
+                    _ => Verdict::Keep,
+                }
+            });
+        }
+        assert_eq!(a, vec![10]);
+        assert_eq!(big, vec![40, 30]);
+    }
 }
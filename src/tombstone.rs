@@ -0,0 +1,250 @@
+//! A vector with stable indices: removals leave tombstones, and a compaction relocates the
+//! elements.
+
+use crate::inplace_vector::InplaceVector;
+use crate::removable_iterator::RemovableItem;
+
+/// A key to an element of a [`TombstoneVec`].
+///
+/// The key stays valid until its element is removed or the vector is compacted. A key of an
+/// older compaction epoch is translated by the [`Relocation`] returned by the compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TombstoneKey {
+    /// The index of the slot.
+    index: usize,
+    /// The compaction epoch the index belongs to.
+    epoch: u64,
+}
+
+impl TombstoneKey {
+    /// Returns the index of the slot.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the compaction epoch the index belongs to.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+}
+
+/// A vector whose removals leave tombstones, so the indices of the other elements are stable,
+/// until a [`compact`](TombstoneVec::compact) removes the tombstones.
+///
+/// A lightweight slot allocator for the users who can't adopt a slot map: the elements stay in
+/// a single vector, and the compaction returns a [`Relocation`] table, to update the keys stored
+/// elsewhere. The compaction preserves the order of the elements.
+///
+/// # Examples
+///
+/// ```
+/// use inplace_iter::tombstone::TombstoneVec;
+///
+/// let mut tasks = TombstoneVec::new();
+/// let a = tasks.push("a");
+/// let b = tasks.push("b");
+/// let c = tasks.push("c");
+/// assert_eq!(tasks.remove(b), Some("b"));
+/// // the other keys are still valid
+/// assert_eq!(tasks.get(c), Some(&"c"));
+/// assert_eq!(tasks.tombstones(), 1);
+///
+/// let relocation = tasks.compact();
+/// assert_eq!(tasks.get(c), None);
+/// let c = relocation.relocate(c).unwrap();
+/// assert_eq!((c.index(), tasks.get(c)), (1, Some(&"c")));
+/// assert_eq!(relocation.relocate(a).unwrap().index(), 0);
+/// assert_eq!(relocation.relocate(b), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TombstoneVec<T> {
+    /// The slots, `None` for a tombstone.
+    slots: Vec<Option<T>>,
+    /// The number of tombstones.
+    tombstones: usize,
+    /// The number of compactions.
+    epoch: u64,
+}
+
+impl<T> Default for TombstoneVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TombstoneVec<T> {
+    /// Creates an empty vector.
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            tombstones: 0,
+            epoch: 0,
+        }
+    }
+
+    /// Appends `value` and returns its key.
+    pub fn push(&mut self, value: T) -> TombstoneKey {
+        self.slots.push(Some(value));
+        self.key(self.slots.len() - 1)
+    }
+
+    /// Returns the element of `key`, `None` if it was removed or the key is of another epoch.
+    pub fn get(&self, key: TombstoneKey) -> Option<&T> {
+        self.slot(key).and_then(|index| self.slots[index].as_ref())
+    }
+
+    /// Returns the element of `key`, `None` if it was removed or the key is of another epoch.
+    pub fn get_mut(&mut self, key: TombstoneKey) -> Option<&mut T> {
+        self.slot(key).and_then(|index| self.slots[index].as_mut())
+    }
+
+    /// Removes the element of `key`, leaving a tombstone, and returns it.
+    pub fn remove(&mut self, key: TombstoneKey) -> Option<T> {
+        let value = self.slot(key).and_then(|index| self.slots[index].take());
+        if value.is_some() {
+            self.tombstones += 1;
+        }
+        value
+    }
+
+    /// Removes the elements matching `pred`, leaving tombstones, and returns the number of
+    /// removed elements.
+    pub fn remove_where<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut removed = 0;
+        for slot in &mut self.slots {
+            if slot.as_ref().is_some_and(&mut pred) {
+                *slot = None;
+                removed += 1;
+            }
+        }
+        self.tombstones += removed;
+        removed
+    }
+
+    /// Returns the number of elements, the tombstones are not counted.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.tombstones
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tombstones, which the next compaction removes.
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Returns the current compaction epoch, the number of compactions so far.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Iterates over the elements with their keys, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (TombstoneKey, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_ref().map(|value| (self.key(index), value)))
+    }
+
+    /// Removes the tombstones, preserving the order of the elements, and returns the table
+    /// translating the keys of the current epoch to the next one.
+    ///
+    /// The epoch is advanced even if there are no tombstones, so the keys of every epoch are
+    /// translated by exactly one table.
+    ///
+    /// # Performance
+    ///
+    /// - O(len), every element after the first tombstone is moved once
+    pub fn compact(&mut self) -> Relocation {
+        let mut table = Vec::with_capacity(self.slots.len());
+        let mut next = 0;
+        for item in self.slots.ordered_removable_iter() {
+            if item.get().is_none() {
+                table.push(None);
+                item.remove();
+            } else {
+                table.push(Some(next));
+                next += 1;
+            }
+        }
+        self.tombstones = 0;
+        self.epoch += 1;
+        Relocation { epoch: self.epoch, table }
+    }
+
+    fn key(&self, index: usize) -> TombstoneKey {
+        TombstoneKey { index, epoch: self.epoch }
+    }
+
+    fn slot(&self, key: TombstoneKey) -> Option<usize> {
+        (key.epoch == self.epoch && key.index < self.slots.len()).then_some(key.index)
+    }
+}
+
+/// The table translating the keys of a [`TombstoneVec`] across a compaction, returned by
+/// [`TombstoneVec::compact`].
+#[derive(Debug, Clone)]
+pub struct Relocation {
+    /// The epoch the keys are translated to.
+    epoch: u64,
+    /// The new index of every old index, `None` for a tombstone.
+    table: Vec<Option<usize>>,
+}
+
+impl Relocation {
+    /// Translates a key of the compacted epoch, returns `None` if its element was removed or
+    /// the key is of another epoch.
+    pub fn relocate(&self, key: TombstoneKey) -> Option<TombstoneKey> {
+        if key.epoch + 1 != self.epoch {
+            return None;
+        }
+        let index = (*self.table.get(key.index)?)?;
+        Some(TombstoneKey { index, epoch: self.epoch })
+    }
+
+    /// Returns the epoch the keys are translated to.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Iterates over the moved elements, as pairs of the old and the new index.
+    pub fn moves(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.table
+            .iter()
+            .enumerate()
+            .filter_map(|(old, new)| new.filter(|new| *new != old).map(|new| (old, new)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TombstoneVec;
+
+    #[test]
+    fn test_tombstones_and_epochs() {
+        let mut v = TombstoneVec::new();
+        let keys = (0..6).map(|i| v.push(i * 10)).collect::<Vec<_>>();
+        assert_eq!(v.remove_where(|value| value % 20 == 0), 3);
+        assert_eq!(v.remove(keys[0]), None);
+        *v.get_mut(keys[5]).unwrap() += 1;
+        assert_eq!((v.len(), v.tombstones()), (3, 3));
+        assert_eq!(v.iter().map(|(key, value)| (key.index(), *value)).collect::<Vec<_>>(), vec![(1, 10), (3, 30), (5, 51)]);
+
+        let relocation = v.compact();
+        assert_eq!(relocation.moves().collect::<Vec<_>>(), vec![(1, 0), (3, 1), (5, 2)]);
+        let keys = keys.iter().filter_map(|key| relocation.relocate(*key)).collect::<Vec<_>>();
+        assert_eq!(keys.iter().map(|key| *v.get(*key).unwrap()).collect::<Vec<_>>(), vec![10, 30, 51]);
+        assert_eq!((v.len(), v.tombstones(), v.epoch()), (3, 0, 1));
+
+        // a table translates only the keys of the epoch it compacted
+        let next = v.compact();
+        assert_eq!(relocation.relocate(keys[0]), None);
+        assert_eq!(next.relocate(keys[0]).unwrap().epoch(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+//! Fallible access to vectors behind other containers, e.g. a possibly poisoned `Mutex`.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use crate::handle::ToHandle;
+use crate::inplace_iterator::InplaceIterator;
+use crate::inplace_vector::InplaceVector;
+use crate::removable_confirm_iterator_vec::{EagerRemovableItem, RemovableConfirmIterator};
+use crate::removable_iterator::{RemovableItem, RemovableItemMut};
+use crate::swap_item::SwapItem;
+use crate::takeable_iterator::{TakeableItem, TakeableItemMut};
+
+/// A trait for containers whose vector may not be accessible, with fallible variants of the
+/// [`InplaceVector`] constructors.
+///
+/// # Implementations
+///
+/// - `Vec<T>`, which never fails
+/// - `Mutex<Vec<T>>` and `RwLock<Vec<T>>`, which fail with [`Poisoned`] if the lock is
+///   poisoned. No locking is needed, as the container is borrowed mutably.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Mutex;
+/// use inplace_iter::prelude::*;
+///
+/// let mut shared = Mutex::new(vec![1, 2, 3, 4]);
+/// for item in shared.try_removable_iter().expect("the lock is poisoned") {
+///     if *item.get() % 2 == 0 {
+///         item.remove();
+///     }
+/// }
+/// assert_eq!(shared.into_inner().unwrap(), vec![1, 3]);
+/// ```
+pub trait TryInplaceVector<T> {
+    /// The reason the vector cannot be accessed.
+    type Error;
+
+    /// Returns the vector, or the reason it cannot be accessed.
+    fn try_vec_mut(&mut self) -> Result<&mut Vec<T>, Self::Error>;
+
+    /// The fallible variant of [`InplaceVector::takeable_iter`].
+    fn try_takeable_iter<'s>(&'s mut self) -> Result<impl InplaceIterator<T, Item = impl TakeableItem<T> + ToHandle + SwapItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.takeable_iter())
+    }
+
+    /// The fallible variant of [`InplaceVector::takeable_iter_mut`].
+    fn try_takeable_iter_mut<'s>(&'s mut self) -> Result<impl InplaceIterator<T, Item = impl TakeableItemMut<T> + ToHandle + SwapItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.takeable_iter_mut())
+    }
+
+    /// The fallible variant of [`InplaceVector::removable_iter`].
+    fn try_removable_iter<'s>(&'s mut self) -> Result<impl InplaceIterator<T, Item = impl RemovableItem<T> + ToHandle + SwapItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.removable_iter())
+    }
+
+    /// The fallible variant of [`InplaceVector::removable_iter_mut`].
+    fn try_removable_iter_mut<'s>(&'s mut self) -> Result<impl InplaceIterator<T, Item = impl RemovableItemMut<T> + ToHandle + SwapItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.removable_iter_mut())
+    }
+
+    /// The fallible variant of [`InplaceVector::removable_confirm_iter`].
+    fn try_removable_confirm_iter<'s>(&'s mut self) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItem<T> + EagerRemovableItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.removable_confirm_iter())
+    }
+
+    /// The fallible variant of [`InplaceVector::removable_confirm_iter_mut`].
+    fn try_removable_confirm_iter_mut<'s>(&'s mut self) -> Result<impl RemovableConfirmIterator<Element = T, Item = impl RemovableItemMut<T> + EagerRemovableItem<T>>, Self::Error>
+    where
+        T: 's,
+    {
+        Ok(self.try_vec_mut()?.removable_confirm_iter_mut())
+    }
+}
+
+/// The error of a container whose lock was poisoned by a panic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the lock of the vector is poisoned")
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+impl<T> TryInplaceVector<T> for Vec<T> {
+    type Error = Infallible;
+
+    fn try_vec_mut(&mut self) -> Result<&mut Vec<T>, Self::Error> {
+        Ok(self)
+    }
+}
+
+impl<T> TryInplaceVector<T> for Mutex<Vec<T>> {
+    type Error = Poisoned;
+
+    fn try_vec_mut(&mut self) -> Result<&mut Vec<T>, Self::Error> {
+        self.get_mut().map_err(|_| Poisoned)
+    }
+}
+
+impl<T> TryInplaceVector<T> for RwLock<Vec<T>> {
+    type Error = Poisoned;
+
+    fn try_vec_mut(&mut self) -> Result<&mut Vec<T>, Self::Error> {
+        self.get_mut().map_err(|_| Poisoned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, RwLock};
+    use crate::prelude::{RemovableConfirmIterator, RemovableItem, TakeableItem, TryInplaceVector};
+    use super::Poisoned;
+
+    #[test]
+    fn test_poisoned_lock_is_reported() {
+        let mut shared = Mutex::new(vec![1, 2, 3]);
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = shared.lock().unwrap();
+            panic!("poisoning the lock");
+        }));
+        assert_eq!(shared.try_takeable_iter().err(), Some(Poisoned));
+        shared.clear_poison();
+        let taken = shared.try_takeable_iter().unwrap().map(|item| item.take()).sum::<i32>();
+        assert_eq!(taken, 6);
+    }
+
+    #[test]
+    fn test_rwlock_and_vec() {
+        let mut shared = RwLock::new(vec![1, 2, 3]);
+        let mut confirm = shared.try_removable_confirm_iter().unwrap();
+        for item in confirm.iter() {
+            if *item.get() == 2 {
+                item.remove();
+            }
+        }
+        confirm.confirm_removals();
+        assert_eq!(*shared.read().unwrap(), vec![1, 3]);
+
+        let mut plain = vec![1, 2];
+        let Ok(iter) = plain.try_removable_iter();
+        assert_eq!(iter.count(), 2);
+    }
+}
@@ -0,0 +1,142 @@
+//! Pruning of vectors of weak pointers, whose targets were dropped.
+
+use std::rc::{self, Rc};
+use std::sync::{self, Arc};
+use crate::inplace_vector::InplaceVector;
+use crate::removable_iterator::RemovableItem;
+
+/// A weak pointer, which can be upgraded to its strong counterpart while the target is alive.
+pub trait WeakPointer {
+    /// The strong pointer, `Arc<T>` for `sync::Weak<T>` and `Rc<T>` for `rc::Weak<T>`.
+    type Strong;
+
+    /// Returns the strong pointer, or `None` if the target was dropped.
+    fn upgrade(&self) -> Option<Self::Strong>;
+}
+
+impl<T> WeakPointer for sync::Weak<T> {
+    type Strong = Arc<T>;
+
+    fn upgrade(&self) -> Option<Arc<T>> {
+        sync::Weak::upgrade(self)
+    }
+}
+
+impl<T> WeakPointer for rc::Weak<T> {
+    type Strong = Rc<T>;
+
+    fn upgrade(&self) -> Option<Rc<T>> {
+        rc::Weak::upgrade(self)
+    }
+}
+
+/// A trait for vectors of weak pointers, e.g. observer lists, dropping the dead ones.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use inplace_iter::prelude::*;
+///
+/// let alive = Arc::new("alive");
+/// let gone = Arc::new("gone");
+/// let mut observers = vec![Arc::downgrade(&alive), Arc::downgrade(&gone)];
+/// drop(gone);
+/// assert_eq!(observers.prune_dead_weaks(), 1);
+/// assert_eq!(observers.len(), 1);
+/// ```
+pub trait PruneDeadWeaks<W: WeakPointer> {
+    /// Removes the weak pointers whose targets were dropped and returns their number.
+    ///
+    /// # Performance
+    ///
+    /// - Removal is O(1) time complexity, the order of the pointers is not preserved
+    fn prune_dead_weaks(&mut self) -> usize;
+
+    /// Returns an iterator over the live targets, removing the dead weak pointers as it goes.
+    ///
+    /// It yields the upgraded strong pointer together with the item of its weak pointer, which
+    /// can remove the weak pointer from the vector as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    /// use inplace_iter::prelude::*;
+    ///
+    /// let listeners = [1, 2, 3].map(Rc::new);
+    /// let mut weaks = listeners.iter().map(Rc::downgrade).collect::<Vec<_>>();
+    /// let [a, b, c] = listeners;
+    /// drop(b);
+    /// let mut notified = Vec::new();
+    /// for (listener, item) in weaks.removable_upgrade_iter() {
+    ///     notified.push(*listener);
+    ///     if *listener == 3 {
+    ///         // unsubscribe
+    ///         item.remove();
+    ///     }
+    /// }
+    /// notified.sort();
+    /// assert_eq!(notified, vec![1, 3]);
+    /// assert_eq!(weaks.len(), 1);
+    /// # drop((a, c));
+    /// ```
+    fn removable_upgrade_iter(&mut self) -> impl Iterator<Item = (W::Strong, impl RemovableItem<W>)>;
+}
+
+impl<W: WeakPointer> PruneDeadWeaks<W> for Vec<W> {
+    fn prune_dead_weaks(&mut self) -> usize {
+        let len = self.len();
+        // the dead pointers are removed by the iteration itself
+        self.removable_upgrade_iter().for_each(drop);
+        len - self.len()
+    }
+
+    fn removable_upgrade_iter(&mut self) -> impl Iterator<Item = (W::Strong, impl RemovableItem<W>)> {
+        self.removable_iter().filter_map(|item| match item.get().upgrade() {
+            Some(strong) => Some((strong, item)),
+            None => {
+                item.remove();
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use crate::prelude::{PruneDeadWeaks, RemovableItem};
+
+    #[test]
+    fn test_prune_dead_arcs() {
+        let targets = (0..6).map(Arc::new).collect::<Vec<_>>();
+        let mut weaks = targets.iter().map(Arc::downgrade).collect::<Vec<_>>();
+        let kept = targets.into_iter().filter(|t| **t % 3 == 0).collect::<Vec<_>>();
+        assert_eq!(weaks.prune_dead_weaks(), 4);
+        assert_eq!(weaks.prune_dead_weaks(), 0);
+        let mut alive = weaks.iter().map(|w| *w.upgrade().unwrap()).collect::<Vec<_>>();
+        alive.sort();
+        assert_eq!(alive, vec![0, 3]);
+        drop(kept);
+    }
+
+    #[test]
+    fn test_upgrade_iter_skips_and_removes_dead() {
+        let targets = (0..5).map(Rc::new).collect::<Vec<_>>();
+        let mut weaks = targets.iter().map(Rc::downgrade).collect::<Vec<_>>();
+        let kept = targets.into_iter().filter(|t| **t != 2).collect::<Vec<_>>();
+        let mut visited = Vec::new();
+        for (strong, item) in weaks.removable_upgrade_iter() {
+            visited.push(*strong);
+            if *strong == 4 {
+                item.remove();
+            }
+        }
+        visited.sort();
+        assert_eq!(visited, vec![0, 1, 3, 4]);
+        assert_eq!(weaks.len(), 3);
+        drop(kept);
+    }
+}
@@ -0,0 +1,55 @@
+//! The allocations are counted by a global allocator, which would replace the allocator of any
+//! test binary it is part of, so this test has a binary of its own.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use inplace_iter::prelude::{InplaceVector, RemovableConfirmIterator, RemovableItem, TakeableItem};
+
+thread_local! {
+    /// The number of allocations done by this thread.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The system allocator, counting the allocations per thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_items_do_not_allocate() {
+    let mut v = (0..1000).collect::<Vec<u32>>();
+    let mut taken = Vec::with_capacity(1000);
+    // the shared counter of the guard is allocated with the pass
+    let mut iter = v.takeable_iter();
+    let before = ALLOCATIONS.with(Cell::get);
+    for item in &mut iter {
+        if *item.get() % 3 == 0 {
+            taken.push(item.take());
+        }
+    }
+    assert_eq!(ALLOCATIONS.with(Cell::get), before, "the items of a guarded pass allocate");
+    drop(iter);
+
+    let mut confirm = v.removable_confirm_iter();
+    let before = ALLOCATIONS.with(Cell::get);
+    for item in confirm.iter() {
+        if *item.get() % 2 == 0 {
+            item.remove();
+        }
+    }
+    assert_eq!(ALLOCATIONS.with(Cell::get), before, "the items of a confirm session allocate");
+    confirm.confirm_removals();
+    assert_eq!(v.len(), 333);
+}
@@ -0,0 +1,27 @@
+//! The violation hook is global to the process, so it is tested in its own test binary, where
+//! no other test can hit a misuse while it is set.
+#![cfg(feature = "loop-lifetime-guard")]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use inplace_iter::epoch::set_violation_hook;
+use inplace_iter::prelude::{InplaceVector, RemovableItem};
+
+fn hooked(message: &str) -> ! {
+    panic!("hooked: {message}");
+}
+
+#[test]
+fn test_violation_hook() {
+    let previous = set_violation_hook(Some(hooked));
+    assert!(previous.is_none());
+    let mut v = vec![1, 2, 3];
+    let mut iter = v.removable_iter();
+    let item = iter.next().unwrap();
+    iter.next();
+    let panic = catch_unwind(AssertUnwindSafe(|| item.remove())).unwrap_err();
+    assert_eq!(panic.downcast_ref::<String>().unwrap(), "hooked: This iterator item is no longer valid!");
+    drop(iter);
+
+    assert!(set_violation_hook(None).is_some());
+    assert!(set_violation_hook(None).is_none());
+}